@@ -1,4 +1,4 @@
-// 3) Using a custom hasher (e.g. RandomState) instead of the default SipHash
+// 3) Using a custom hasher (e.g. RandomState) instead of the default seeded XXH3
 use hyperloglog::HyperLogLog;
 use std::collections::hash_map::RandomState;
 
@@ -7,11 +7,10 @@ struct Person {
     name: String,
 }
 
+// encoded as the tuple of its fields, which keeps the fields apart
 impl ToBytes for Person {
     fn to_bytes(&self) -> Vec<u8> {
-        let mut v = self.id.to_le_bytes().to_vec();
-        v.extend(self.name.as_bytes());
-        v
+        (self.id, self.name.as_str()).to_bytes()
     }
 
     const TYPE_ID: &'static [u8] = b"Person";
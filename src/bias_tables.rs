@@ -0,0 +1,484 @@
+// Empirical bias data for the HyperLogLog++ estimator, one row per precision p = 4..=18.
+// Generated by `cargo run --release --example generate_bias_tables`, do not edit by hand.
+
+/// Mean raw estimates at evenly spaced cardinalities up to 5m, sorted ascending.
+pub(crate) static RAW_ESTIMATE_DATA: [&[f64]; 15] = [
+    // p = 4
+    &[
+        11.2376, 11.7225, 12.2231, 12.7394, 13.2706, 13.8179, 14.3809, 14.9601,
+        15.5555, 16.1662, 16.7930, 17.4342, 18.0912, 18.7636, 19.4515, 20.1534,
+        20.8721, 21.6039, 22.3499, 23.1092, 23.8824, 24.6683, 25.4659, 26.2745,
+        27.0939, 27.9255, 28.7697, 29.6236, 30.4859, 31.3617, 32.2431, 33.1372,
+        34.0365, 34.9464, 35.8582, 36.7768, 37.7061, 38.6415, 39.5768, 40.5174,
+        41.4595, 42.4125, 43.3694, 44.3333, 45.2959, 46.2614, 47.2336, 48.2075,
+        49.1829, 50.1606, 51.1384, 52.1199, 53.1070, 54.0953, 55.0827, 56.0705,
+        57.0634, 58.0558, 59.0427, 60.0376, 61.0410, 62.0332, 63.0283, 64.0262,
+        65.0261, 66.0229, 67.0162, 68.0112, 69.0108, 70.0083, 71.0103, 72.0057,
+        73.0090, 74.0088, 75.0085, 76.0053, 77.0014, 77.9967, 78.9933, 79.9973,
+    ],
+    // p = 5
+    &[
+        22.7795, 23.7520, 24.2491, 25.2660, 26.3131, 26.8471, 27.9405, 28.4990,
+        29.6360, 30.8042, 31.4003, 32.6145, 33.2318, 34.4879, 35.7707, 36.4246,
+        37.7525, 38.4274, 39.8012, 41.2018, 41.9105, 43.3537, 44.0855, 45.5634,
+        47.0671, 47.8296, 49.3742, 50.1559, 51.7354, 53.3385, 54.1498, 55.7829,
+        56.6095, 58.2786, 59.9627, 60.8139, 62.5295, 63.3930, 65.1283, 66.8837,
+        67.7700, 69.5517, 70.4496, 72.2554, 74.0757, 74.9881, 76.8253, 77.7460,
+        79.5994, 81.4619, 82.3936, 84.2741, 85.2187, 87.1072, 89.0152, 89.9655,
+        91.8831, 92.8455, 94.7698, 96.6930, 97.6534, 99.6005, 100.5727, 102.5120,
+        104.4632, 105.4393, 107.3954, 108.3733, 110.3311, 112.2974, 113.2758, 115.2507,
+        116.2355, 118.2094, 120.1909, 121.1754, 123.1611, 124.1592, 126.1347, 128.1203,
+        129.1225, 131.1103, 132.1101, 134.0865, 136.0822, 137.0816, 139.0774, 140.0706,
+        142.0634, 144.0549, 145.0548, 147.0402, 148.0458, 150.0440, 152.0314, 153.0293,
+        155.0083, 156.0171, 158.0088, 160.0056,
+    ],
+    // p = 6
+    &[
+        46.8212, 48.2987, 49.8093, 51.3541, 53.4638, 55.0841, 56.7364, 58.4231,
+        60.1455, 62.4909, 64.2857, 66.1153, 67.9740, 69.8696, 72.4462, 74.4183,
+        76.4175, 78.4489, 80.5112, 83.3065, 85.4342, 87.5956, 89.7865, 92.0062,
+        95.0106, 97.2918, 99.5923, 101.9278, 104.2848, 107.4715, 109.8820, 112.3275,
+        114.7974, 117.2809, 120.6401, 123.1772, 125.7262, 128.3080, 130.9107, 134.3990,
+        137.0402, 139.6863, 142.3588, 145.0527, 148.6528, 151.3797, 154.1232, 156.8780,
+        159.6365, 163.3363, 166.1273, 168.9251, 171.7338, 174.5552, 178.3321, 181.1739,
+        184.0230, 186.8914, 189.7626, 193.6041, 196.5045, 199.3872, 202.2811, 205.1976,
+        209.0898, 212.0082, 214.9269, 217.8694, 220.7938, 224.7034, 227.6434, 230.5960,
+        233.5366, 236.4886, 240.4168, 243.3696, 246.3240, 249.3237, 252.2828, 256.2300,
+        259.1999, 262.1765, 265.1349, 268.1289, 272.0986, 275.0760, 278.0421, 281.0400,
+        284.0230, 288.0041, 290.9780, 293.9650, 296.9555, 299.9543, 303.9511, 306.9490,
+        309.9467, 312.9459, 315.9535, 319.9447,
+    ],
+    // p = 7
+    &[
+        94.4598, 97.4308, 100.9762, 104.0901, 107.8063, 111.0579, 114.3785, 118.3311,
+        121.7895, 125.9089, 129.5136, 133.1769, 137.5260, 141.3344, 145.8596, 149.8029,
+        153.8045, 158.5510, 162.6923, 167.5987, 171.8748, 176.1914, 181.3039, 185.7507,
+        190.9884, 195.5394, 200.1496, 205.5955, 210.3043, 215.8812, 220.7113, 225.5776,
+        231.3145, 236.2826, 242.1510, 247.2085, 252.3201, 258.3207, 263.5096, 269.6009,
+        274.8754, 280.1856, 286.3829, 291.7378, 298.0267, 303.4342, 308.9017, 315.3095,
+        320.8330, 327.3333, 332.8913, 338.4808, 345.0384, 350.6744, 357.2548, 362.9408,
+        368.6129, 375.2771, 381.0079, 387.7082, 393.4831, 399.2858, 406.0254, 411.8663,
+        418.6260, 424.4635, 430.2887, 437.1378, 442.9625, 449.8077, 455.6759, 461.5432,
+        468.3956, 474.2952, 481.1877, 487.0816, 493.0111, 499.9438, 505.8958, 512.8163,
+        518.7691, 524.7098, 531.6599, 537.5699, 544.5031, 550.4791, 556.4247, 563.3914,
+        569.3583, 576.3364, 582.3351, 588.3021, 595.2701, 601.2443, 608.2506, 614.2586,
+        620.2647, 627.2851, 633.2573, 640.2077,
+    ],
+    // p = 8
+    &[
+        189.6975, 196.1562, 202.7613, 209.5276, 216.4442, 222.9617, 230.1739, 237.5341,
+        245.0467, 252.7033, 259.9178, 267.8674, 275.9599, 284.2173, 292.6342, 300.5055,
+        309.1801, 317.9851, 326.9410, 336.0467, 344.5621, 353.8916, 363.3761, 372.9764,
+        382.7236, 391.8235, 401.8073, 411.8833, 422.1206, 432.4438, 442.1013, 452.6667,
+        463.3014, 474.0778, 484.9536, 495.0518, 506.0887, 517.2322, 528.4743, 539.7626,
+        550.3020, 561.7286, 573.2718, 584.8656, 596.5167, 607.3420, 619.1865, 631.0498,
+        642.9508, 654.9445, 666.0782, 678.1545, 690.3490, 702.5503, 714.7861, 726.1396,
+        738.4931, 750.8462, 763.2075, 775.6018, 787.1363, 799.5898, 812.1425, 824.7205,
+        837.2894, 848.9629, 861.5565, 874.2805, 886.9684, 899.6534, 911.3407, 924.0713,
+        936.7889, 949.5696, 962.3334, 974.2074, 987.0217, 999.9018, 1012.7862, 1025.6468,
+        1037.5152, 1050.4139, 1063.3547, 1076.2510, 1089.1441, 1101.0653, 1113.9897, 1126.9246,
+        1139.8674, 1152.7745, 1164.6491, 1177.6245, 1190.5479, 1203.4618, 1216.4957, 1228.4316,
+        1241.4056, 1254.3757, 1267.3305, 1280.3055,
+    ],
+    // p = 9
+    &[
+        380.6772, 393.6114, 406.3335, 419.8749, 433.6989, 447.3090, 461.7458, 475.9125,
+        490.9176, 506.2507, 521.2827, 537.2078, 552.7979, 569.3153, 586.0848, 602.4972,
+        619.8319, 636.8136, 654.7660, 672.9883, 690.7368, 709.4873, 727.7571, 746.9794,
+        766.4236, 785.3960, 805.3965, 824.8656, 845.2486, 865.9062, 886.0295, 907.1331,
+        927.6093, 949.1358, 970.7917, 991.7572, 1013.8970, 1035.3119, 1057.7477, 1080.3597,
+        1102.1725, 1125.1173, 1147.2882, 1170.5832, 1193.9464, 1216.4524, 1240.0558, 1262.8503,
+        1286.6775, 1310.5973, 1333.6707, 1357.8918, 1381.2635, 1405.6540, 1430.0712, 1453.6789,
+        1478.3025, 1502.0243, 1526.8336, 1551.6526, 1575.6092, 1600.5905, 1624.6633, 1649.8251,
+        1674.9406, 1699.2558, 1724.6485, 1749.1473, 1774.5294, 1799.8461, 1824.3149, 1849.8443,
+        1874.4182, 1900.0343, 1925.6429, 1950.3225, 1975.9028, 2000.5158, 2026.2178, 2051.9488,
+        2076.6521, 2102.4161, 2127.0103, 2152.8270, 2178.7474, 2203.6311, 2229.4442, 2254.3278,
+        2280.2006, 2306.0183, 2330.8897, 2356.6283, 2381.4225, 2407.3286, 2433.2603, 2458.2601,
+        2484.0880, 2508.8165, 2534.7883, 2560.6943,
+    ],
+    // p = 10
+    &[
+        762.6381, 788.0061, 813.9887, 840.5532, 868.2236, 895.9222, 924.2611, 953.1495,
+        982.6933, 1013.3766, 1044.0267, 1075.2354, 1107.0434, 1139.4499, 1173.0258, 1206.5402,
+        1240.5375, 1275.2008, 1310.3422, 1346.6598, 1382.7808, 1419.5253, 1456.7227, 1494.4739,
+        1533.3079, 1571.9693, 1611.2494, 1650.9296, 1691.0682, 1732.3863, 1773.4544, 1814.7278,
+        1856.4492, 1898.6698, 1942.1274, 1985.0235, 2028.2938, 2071.9779, 2115.9318, 2160.8453,
+        2205.4212, 2250.4950, 2295.7947, 2341.3060, 2387.8515, 2433.7648, 2479.9626, 2526.4903,
+        2573.2399, 2621.2707, 2668.5805, 2715.8807, 2763.3581, 2811.0654, 2859.9962, 2908.3293,
+        2956.8103, 3005.3873, 3053.9981, 3103.5566, 3152.3774, 3201.3885, 3250.5847, 3299.7774,
+        3349.8648, 3399.2049, 3448.7841, 3498.5068, 3548.3034, 3599.2616, 3649.0999, 3699.1606,
+        3749.1689, 3799.2982, 3850.3769, 3900.5366, 3950.9948, 4001.2364, 4051.6672, 4102.9836,
+        4153.6207, 4203.9819, 4254.4887, 4305.1665, 4356.7919, 4407.5142, 4458.0838, 4509.0710,
+        4559.6078, 4610.9821, 4661.5893, 4712.4208, 4763.2287, 4814.1451, 4865.7568, 4916.6837,
+        4967.6975, 5018.3985, 5069.0231, 5120.9845,
+    ],
+    // p = 11
+    &[
+        1526.0579, 1576.8660, 1629.3521, 1682.5544, 1737.4583, 1792.9540, 1849.6630, 1908.0909,
+        1967.1140, 2027.8958, 2089.2333, 2151.7225, 2216.0137, 2280.7525, 2347.2288, 2414.3924,
+        2482.5150, 2552.3156, 2622.4995, 2694.7126, 2767.2299, 2840.7788, 2915.9801, 2991.5242,
+        3068.5456, 3145.9493, 3224.3803, 3304.3350, 3384.3155, 3466.2477, 3548.0604, 3630.8844,
+        3715.1795, 3799.5594, 3885.1989, 3970.6809, 4057.3616, 4145.6766, 4233.6418, 4323.2087,
+        4412.6483, 4502.6207, 4593.6948, 4684.4778, 4776.9717, 4869.0071, 4961.7198, 5055.7950,
+        5149.4296, 5244.3129, 5338.6713, 5433.8604, 5530.1604, 5625.5380, 5722.6530, 5818.8905,
+        5915.5201, 6013.4492, 6110.5406, 6208.7987, 6306.5682, 6404.8407, 6504.1740, 6602.8287,
+        6702.4032, 6801.0620, 6899.9319, 7000.4445, 7099.5283, 7200.8136, 7300.3220, 7400.5393,
+        7501.9428, 7602.5427, 7704.2516, 7804.4170, 7905.2274, 8007.3731, 8107.7661, 8208.7249,
+        8309.5855, 8410.8062, 8512.8346, 8614.1955, 8716.3359, 8817.9988, 8919.2757, 9021.9952,
+        9123.7396, 9225.9769, 9327.4602, 9428.8531, 9531.2233, 9633.6801, 9735.9361, 9837.3185,
+        9939.6900, 10042.2351, 10143.7502, 10246.3941,
+    ],
+    // p = 12
+    &[
+        3052.8329, 3154.9155, 3259.4276, 3366.2425, 3475.3586, 3586.4205, 3700.4668, 3816.8160,
+        3935.1578, 4056.0712, 4178.7548, 4304.3251, 4432.1577, 4562.3927, 4694.8762, 4828.8830,
+        4965.7071, 5104.9433, 5246.3081, 5389.3511, 5534.0112, 5681.3830, 5830.8186, 5982.1695,
+        6135.5025, 6290.1916, 6447.3103, 6606.1870, 6766.7839, 6929.5126, 7093.3195, 7259.6612,
+        7427.4660, 7597.2339, 7767.9620, 7939.5612, 8113.3446, 8287.8847, 8464.3365, 8642.4147,
+        8820.5993, 9001.1171, 9182.7311, 9365.5328, 9549.4484, 9733.4875, 9920.1783, 10107.2950,
+        10296.0125, 10484.9224, 10673.6087, 10863.9747, 11054.8567, 11247.3848, 11439.7268, 11632.2421,
+        11825.9450, 12020.3343, 12215.5923, 12411.0474, 12606.4764, 12803.0118, 13001.1658, 13199.3965,
+        13397.7527, 13594.8915, 13793.8120, 13994.2644, 14194.3588, 14394.1155, 14593.3327, 14794.4194,
+        14994.7571, 15195.5824, 15396.1777, 15595.9304, 15798.3294, 15999.9954, 16202.2198, 16404.2317,
+        16606.4629, 16809.4042, 17012.1950, 17213.9387, 17416.3171, 17619.0944, 17822.6155, 18025.5946,
+        18230.0348, 18432.8783, 18636.4092, 18840.7851, 19044.0907, 19249.1567, 19452.6280, 19655.9332,
+        19857.9252, 20061.9151, 20267.5206, 20471.3066,
+    ],
+    // p = 13
+    &[
+        6107.1579, 6311.2706, 6519.6355, 6733.1707, 6951.6194, 7174.3037, 7402.1120, 7634.2106,
+        7871.3668, 8113.3647, 8359.4000, 8610.6198, 8865.9353, 9126.2695, 9391.4011, 9659.8981,
+        9933.6419, 10211.3241, 10494.1234, 10780.7710, 11071.0684, 11366.0415, 11665.1116, 11968.5577,
+        12276.2899, 12586.5429, 12901.3042, 13218.5204, 13540.6030, 13866.4958, 14193.9536, 14525.6223,
+        14860.4602, 15199.4130, 15541.1020, 15885.1921, 16233.2155, 16583.3563, 16937.4430, 17293.3273,
+        17651.3080, 18012.5003, 18375.0964, 18740.6583, 19109.9863, 19479.1326, 19850.3326, 20222.6330,
+        20598.3634, 20974.5911, 21354.2478, 21736.9010, 22118.5264, 22502.6038, 22887.9135, 23273.0511,
+        23660.1003, 24048.3464, 24438.0147, 24832.2558, 25224.5240, 25618.5903, 26012.5845, 26406.3362,
+        26802.6061, 27198.3549, 27595.8241, 27994.9111, 28395.1509, 28796.3703, 29198.6634, 29597.9633,
+        30000.6675, 30403.3489, 30805.3337, 31205.4552, 31608.7688, 32012.0837, 32417.4459, 32821.7573,
+        33228.0800, 33634.3406, 34039.3348, 34445.4250, 34852.0366, 35257.9585, 35663.9610, 36070.5863,
+        36476.5905, 36883.3797, 37290.0455, 37698.5220, 38106.4709, 38515.5108, 38927.9824, 39337.0894,
+        39744.4891, 40149.8814, 40559.0701, 40965.0873,
+    ],
+    // p = 14
+    &[
+        12215.4848, 12623.4197, 13040.6477, 13467.6777, 13904.5566, 14349.9663, 14805.0185, 15270.0283,
+        15743.9262, 16227.6065, 16720.3353, 17221.9948, 17733.0335, 18251.8795, 18781.9692, 19320.4070,
+        19868.0023, 20423.7775, 20987.8166, 21561.8611, 22143.0940, 22731.6928, 23329.4139, 23935.3401,
+        24548.8017, 25169.8897, 25798.0115, 26433.5854, 27076.7570, 27728.5294, 28386.8168, 29050.1797,
+        29719.5121, 30397.8371, 31081.3197, 31767.9328, 32463.5864, 33164.9359, 33871.2956, 34581.5812,
+        35297.4383, 36017.1566, 36743.3452, 37471.5360, 38208.1492, 38947.3437, 39689.5680, 40438.9835,
+        41189.7706, 41945.5352, 42703.1869, 43462.8803, 44227.6751, 44993.4575, 45766.3800, 46539.0487,
+        47315.5515, 48095.6092, 48875.0410, 49656.0534, 50443.4241, 51230.2173, 52020.8791, 52812.7756,
+        53605.4808, 54397.4004, 55193.9276, 55988.6326, 56788.8561, 57590.1617, 58394.6511, 59195.3535,
+        59999.2279, 60806.7757, 61614.4775, 62417.7539, 63225.8799, 64033.8499, 64844.7544, 65656.4675,
+        66468.3863, 67281.4833, 68096.4345, 68909.0556, 69724.8073, 70537.4699, 71349.2994, 72165.4540,
+        72979.3813, 73796.2631, 74612.0070, 75426.8144, 76245.1444, 77063.4491, 77879.9683, 78693.3705,
+        79509.4170, 80327.8050, 81140.3006, 81961.8993,
+    ],
+    // p = 15
+    &[
+        24431.6795, 25247.5913, 26082.7265, 26935.8231, 27810.4244, 28702.2377, 29612.3432, 30542.7202,
+        31490.1571, 32457.3767, 33442.3952, 34446.5569, 35468.9051, 36507.0930, 37566.6824, 38644.8958,
+        39739.2147, 40850.0265, 41980.0078, 43125.4458, 44287.4140, 45463.9410, 46659.6825, 47869.9284,
+        49099.0050, 50343.5613, 51604.4844, 52879.1539, 54169.4005, 55470.6139, 56790.3320, 58115.8356,
+        59458.0178, 60807.4068, 62176.4490, 63554.3912, 64942.9480, 66339.9255, 67751.3344, 69177.3969,
+        70605.7717, 72048.2140, 73500.7264, 74962.1860, 76433.6533, 77914.3092, 79404.1486, 80897.1841,
+        82402.0665, 83910.9288, 85426.9794, 86950.2349, 88479.5412, 90015.8931, 91557.0032, 93101.1714,
+        94653.2338, 96208.9875, 97764.7288, 99329.0040, 100899.1053, 102467.1975, 104048.8019, 105626.8557,
+        107215.4290, 108800.7554, 110392.6533, 111990.2285, 113590.7224, 115191.7267, 116792.0635, 118398.6420,
+        120008.5764, 121617.5103, 123227.1447, 124839.8023, 126450.3570, 128066.0067, 129684.7803, 131303.9706,
+        132926.3162, 134542.9344, 136169.3040, 137791.1388, 139417.7992, 141047.4080, 142674.9289, 144310.3541,
+        145938.8005, 147566.5914, 149197.8388, 150828.1258, 152453.4173, 154084.3598, 155713.9346, 157345.5062,
+        158981.5659, 160606.7055, 162235.9820, 163873.9802,
+    ],
+    // p = 16
+    &[
+        48864.8803, 50495.6334, 52164.6547, 53872.3809, 55617.1111, 57397.9728, 59218.4218, 61075.5068,
+        62972.9478, 64906.1758, 66875.9225, 68884.8091, 70932.3231, 73011.9046, 75128.7327, 77278.2636,
+        79469.6506, 81695.6913, 83956.9878, 86249.5473, 88569.5983, 90928.0031, 93323.2966, 95745.9769,
+        98200.1639, 100692.8962, 103209.7017, 105752.4801, 108321.0742, 110918.1200, 113545.3031, 116200.8612,
+        118881.1500, 121593.8544, 124321.9192, 127071.2732, 129854.3403, 132656.6507, 135473.8444, 138312.2513,
+        141183.6955, 144069.6934, 146978.5449, 149906.0637, 152851.2136, 155801.6619, 158764.9926, 161757.5435,
+        164768.1841, 167793.1717, 170832.8998, 173870.6911, 176927.4016, 179999.7654, 183073.4519, 186164.3640,
+        189266.5204, 192381.5565, 195506.3973, 198644.1041, 201786.8505, 204935.8078, 208099.6462, 211257.3060,
+        214437.0284, 217602.4272, 220787.4262, 223977.5592, 227174.8126, 230380.5198, 233583.8458, 236800.8475,
+        240004.5997, 243217.4554, 246437.2743, 249650.8633, 252890.5821, 256113.9106, 259352.7217, 262591.0279,
+        265843.3112, 269080.8466, 272332.0146, 275572.8040, 278836.8126, 282081.2631, 285344.1132, 288609.7167,
+        291886.3203, 295148.3145, 298394.7194, 301670.0138, 304938.7471, 308219.5188, 311474.2065, 314747.1003,
+        318014.9867, 321285.5616, 324550.2837, 327804.9050,
+    ],
+    // p = 17
+    &[
+        97730.5288, 100992.9376, 104332.7793, 107748.4625, 111239.6235, 114810.0813, 118455.5743, 122175.2712,
+        125974.0445, 129843.2361, 133791.3182, 137808.0415, 141900.3358, 146079.3931, 150312.0722, 154626.2008,
+        159001.3844, 163446.7630, 167973.2317, 172554.2414, 177211.5839, 181927.1266, 186710.2304, 191563.1681,
+        196468.7794, 201444.9768, 206485.8404, 211586.2887, 216736.1046, 221952.6361, 227211.7808, 232506.3978,
+        237860.5024, 243272.8581, 248728.0279, 254231.2881, 259795.0555, 265382.2536, 271036.1678, 276708.6192,
+        282442.8215, 288225.0526, 294027.9687, 299847.8995, 305732.0337, 311658.0636, 317594.2181, 323578.3146,
+        329597.7543, 335640.5621, 341706.1381, 347789.1440, 353902.7827, 360034.9517, 366179.8038, 372391.1933,
+        378607.0453, 384818.9468, 391058.2190, 397303.7652, 403565.9445, 409879.7465, 416207.7431, 422525.5138,
+        428848.3099, 435200.9333, 441586.7316, 447919.6736, 454319.5517, 460696.0791, 467136.2653, 473551.4388,
+        479977.1121, 486423.7997, 492855.0107, 499316.6868, 505792.4027, 512248.4804, 518763.4327, 525234.2004,
+        531721.9127, 538220.6296, 544697.6676, 551202.7190, 557716.3797, 564236.7723, 570742.3754, 577250.4556,
+        583717.1945, 590227.4532, 596722.7140, 603289.0097, 609794.7555, 616288.5942, 622796.9179, 629334.4450,
+        635864.5796, 642406.2255, 648896.2864, 655469.6812,
+    ],
+    // p = 18
+    &[
+        195463.7843, 201989.7187, 208666.2116, 215494.3922, 222476.8979, 229609.1536, 236899.6772, 244345.3394,
+        251928.0037, 259662.5280, 267546.5915, 275562.2361, 283729.5793, 292064.7982, 300548.2793, 309173.6490,
+        317932.0076, 326833.5593, 335868.9681, 345062.2520, 354364.4869, 363807.6479, 373371.7207, 383068.1967,
+        392875.7230, 402825.1513, 412862.1263, 423042.6717, 433336.5448, 443743.7511, 454291.2010, 464952.2337,
+        475686.9517, 486544.3514, 497489.7315, 508474.9384, 519602.6742, 530800.8177, 542127.6613, 553520.5959,
+        565006.0678, 576572.2852, 588173.9714, 599887.0842, 611663.7912, 623507.2694, 635386.6499, 647333.9117,
+        659351.4662, 671461.9931, 683600.2439, 695833.4524, 708111.1130, 720383.5088, 732681.1262, 745069.6084,
+        757482.0044, 769940.1179, 782431.2392, 794958.2580, 807559.0910, 820092.8368, 832647.7950, 845332.8745,
+        858084.1922, 870826.2805, 883549.1495, 896257.5443, 909092.9991, 921974.2959, 934786.8394, 947641.7914,
+        960458.8473, 973292.7468, 986200.4966, 999180.4391, 1012123.8500, 1025000.7162, 1037944.0979, 1050858.7770,
+        1063856.0718, 1076820.1734, 1089821.1240, 1102843.4196, 1115839.9521, 1128867.1324, 1141840.4250, 1154847.5958,
+        1167894.4069, 1180971.3638, 1194057.5825, 1207068.7986, 1220202.9668, 1233235.6050, 1246228.5808, 1259298.5882,
+        1272451.5162, 1285584.5711, 1298697.3835, 1311780.9277,
+    ],
+];
+
+/// Bias of the raw estimate at the matching entry of `RAW_ESTIMATE_DATA`.
+pub(crate) static BIAS_DATA: [&[f64]; 15] = [
+    // p = 4
+    &[
+        10.2376, 9.7225, 9.2231, 8.7394, 8.2706, 7.8179, 7.3809, 6.9601,
+        6.5555, 6.1662, 5.7930, 5.4342, 5.0912, 4.7636, 4.4515, 4.1534,
+        3.8721, 3.6039, 3.3499, 3.1092, 2.8824, 2.6683, 2.4659, 2.2745,
+        2.0939, 1.9255, 1.7697, 1.6236, 1.4859, 1.3617, 1.2431, 1.1372,
+        1.0365, 0.9464, 0.8582, 0.7768, 0.7061, 0.6415, 0.5768, 0.5174,
+        0.4595, 0.4125, 0.3694, 0.3333, 0.2959, 0.2614, 0.2336, 0.2075,
+        0.1829, 0.1606, 0.1384, 0.1199, 0.1070, 0.0953, 0.0827, 0.0705,
+        0.0634, 0.0558, 0.0427, 0.0376, 0.0410, 0.0332, 0.0283, 0.0262,
+        0.0261, 0.0229, 0.0162, 0.0112, 0.0108, 0.0083, 0.0103, 0.0057,
+        0.0090, 0.0088, 0.0085, 0.0053, 0.0014, -0.0033, -0.0067, -0.0027,
+    ],
+    // p = 5
+    &[
+        21.7795, 20.7520, 20.2491, 19.2660, 18.3131, 17.8471, 16.9405, 16.4990,
+        15.6360, 14.8042, 14.4003, 13.6145, 13.2318, 12.4879, 11.7707, 11.4246,
+        10.7525, 10.4274, 9.8012, 9.2018, 8.9105, 8.3537, 8.0855, 7.5634,
+        7.0671, 6.8296, 6.3742, 6.1559, 5.7354, 5.3385, 5.1498, 4.7829,
+        4.6095, 4.2786, 3.9627, 3.8139, 3.5295, 3.3930, 3.1283, 2.8837,
+        2.7700, 2.5517, 2.4496, 2.2554, 2.0757, 1.9881, 1.8253, 1.7460,
+        1.5994, 1.4619, 1.3936, 1.2741, 1.2187, 1.1072, 1.0152, 0.9655,
+        0.8831, 0.8455, 0.7698, 0.6930, 0.6534, 0.6005, 0.5727, 0.5120,
+        0.4632, 0.4393, 0.3954, 0.3733, 0.3311, 0.2974, 0.2758, 0.2507,
+        0.2355, 0.2094, 0.1909, 0.1754, 0.1611, 0.1592, 0.1347, 0.1203,
+        0.1225, 0.1103, 0.1101, 0.0865, 0.0822, 0.0816, 0.0774, 0.0706,
+        0.0634, 0.0549, 0.0548, 0.0402, 0.0458, 0.0440, 0.0314, 0.0293,
+        0.0083, 0.0171, 0.0088, 0.0056,
+    ],
+    // p = 6
+    &[
+        43.8212, 42.2987, 40.8093, 39.3541, 37.4638, 36.0841, 34.7364, 33.4231,
+        32.1455, 30.4909, 29.2857, 28.1153, 26.9740, 25.8696, 24.4462, 23.4183,
+        22.4175, 21.4489, 20.5112, 19.3065, 18.4342, 17.5956, 16.7865, 16.0062,
+        15.0106, 14.2918, 13.5923, 12.9278, 12.2848, 11.4715, 10.8820, 10.3275,
+        9.7974, 9.2809, 8.6401, 8.1772, 7.7262, 7.3080, 6.9107, 6.3990,
+        6.0402, 5.6863, 5.3588, 5.0527, 4.6528, 4.3797, 4.1232, 3.8780,
+        3.6365, 3.3363, 3.1273, 2.9251, 2.7338, 2.5552, 2.3321, 2.1739,
+        2.0230, 1.8914, 1.7626, 1.6041, 1.5045, 1.3872, 1.2811, 1.1976,
+        1.0898, 1.0082, 0.9269, 0.8694, 0.7938, 0.7034, 0.6434, 0.5960,
+        0.5366, 0.4886, 0.4168, 0.3696, 0.3240, 0.3237, 0.2828, 0.2300,
+        0.1999, 0.1765, 0.1349, 0.1289, 0.0986, 0.0760, 0.0421, 0.0400,
+        0.0230, 0.0041, -0.0220, -0.0350, -0.0445, -0.0457, -0.0489, -0.0510,
+        -0.0533, -0.0541, -0.0465, -0.0553,
+    ],
+    // p = 7
+    &[
+        88.4598, 85.4308, 81.9762, 79.0901, 75.8063, 73.0579, 70.3785, 67.3311,
+        64.7895, 61.9089, 59.5136, 57.1769, 54.5260, 52.3344, 49.8596, 47.8029,
+        45.8045, 43.5510, 41.6923, 39.5987, 37.8748, 36.1914, 34.3039, 32.7507,
+        30.9884, 29.5394, 28.1496, 26.5955, 25.3043, 23.8812, 22.7113, 21.5776,
+        20.3145, 19.2826, 18.1510, 17.2085, 16.3201, 15.3207, 14.5096, 13.6009,
+        12.8754, 12.1856, 11.3829, 10.7378, 10.0267, 9.4342, 8.9017, 8.3095,
+        7.8330, 7.3333, 6.8913, 6.4808, 6.0384, 5.6744, 5.2548, 4.9408,
+        4.6129, 4.2771, 4.0079, 3.7082, 3.4831, 3.2858, 3.0254, 2.8663,
+        2.6260, 2.4635, 2.2887, 2.1378, 1.9625, 1.8077, 1.6759, 1.5432,
+        1.3956, 1.2952, 1.1877, 1.0816, 1.0111, 0.9438, 0.8958, 0.8163,
+        0.7691, 0.7098, 0.6599, 0.5699, 0.5031, 0.4791, 0.4247, 0.3914,
+        0.3583, 0.3364, 0.3351, 0.3021, 0.2701, 0.2443, 0.2506, 0.2586,
+        0.2647, 0.2851, 0.2573, 0.2077,
+    ],
+    // p = 8
+    &[
+        177.6975, 171.1562, 164.7613, 158.5276, 152.4442, 146.9617, 141.1739, 135.5341,
+        130.0467, 124.7033, 119.9178, 114.8674, 109.9599, 105.2173, 100.6342, 96.5055,
+        92.1801, 87.9851, 83.9410, 80.0467, 76.5621, 72.8916, 69.3761, 65.9764,
+        62.7236, 59.8235, 56.8073, 53.8833, 51.1206, 48.4438, 46.1013, 43.6667,
+        41.3014, 39.0778, 36.9536, 35.0518, 33.0887, 31.2322, 29.4743, 27.7626,
+        26.3020, 24.7286, 23.2718, 21.8656, 20.5167, 19.3420, 18.1865, 17.0498,
+        15.9508, 14.9445, 14.0782, 13.1545, 12.3490, 11.5503, 10.7861, 10.1396,
+        9.4931, 8.8462, 8.2075, 7.6018, 7.1363, 6.5898, 6.1425, 5.7205,
+        5.2894, 4.9629, 4.5565, 4.2805, 3.9684, 3.6534, 3.3407, 3.0713,
+        2.7889, 2.5696, 2.3334, 2.2074, 2.0217, 1.9018, 1.7862, 1.6468,
+        1.5152, 1.4139, 1.3547, 1.2510, 1.1441, 1.0653, 0.9897, 0.9246,
+        0.8674, 0.7745, 0.6491, 0.6245, 0.5479, 0.4618, 0.4957, 0.4316,
+        0.4056, 0.3757, 0.3305, 0.3055,
+    ],
+    // p = 9
+    &[
+        355.6772, 342.6114, 330.3335, 317.8749, 305.6989, 294.3090, 282.7458, 271.9125,
+        260.9176, 250.2507, 240.2827, 230.2078, 220.7979, 211.3153, 202.0848, 193.4972,
+        184.8319, 176.8136, 168.7660, 160.9883, 153.7368, 146.4873, 139.7571, 132.9794,
+        126.4236, 120.3960, 114.3965, 108.8656, 103.2486, 97.9062, 93.0295, 88.1331,
+        83.6093, 79.1358, 74.7917, 70.7572, 66.8970, 63.3119, 59.7477, 56.3597,
+        53.1725, 50.1173, 47.2882, 44.5832, 41.9464, 39.4524, 37.0558, 34.8503,
+        32.6775, 30.5973, 28.6707, 26.8918, 25.2635, 23.6540, 22.0712, 20.6789,
+        19.3025, 18.0243, 16.8336, 15.6526, 14.6092, 13.5905, 12.6633, 11.8251,
+        10.9406, 10.2558, 9.6485, 9.1473, 8.5294, 7.8461, 7.3149, 6.8443,
+        6.4182, 6.0343, 5.6429, 5.3225, 4.9028, 4.5158, 4.2178, 3.9488,
+        3.6521, 3.4161, 3.0103, 2.8270, 2.7474, 2.6311, 2.4442, 2.3278,
+        2.2006, 2.0183, 1.8897, 1.6283, 1.4225, 1.3286, 1.2603, 1.2601,
+        1.0880, 0.8165, 0.7883, 0.6943,
+    ],
+    // p = 10
+    &[
+        711.6381, 686.0061, 660.9887, 636.5532, 612.2236, 588.9222, 566.2611, 544.1495,
+        522.6933, 501.3766, 481.0267, 461.2354, 442.0434, 423.4499, 405.0258, 387.5402,
+        370.5375, 354.2008, 338.3422, 322.6598, 307.7808, 293.5253, 279.7227, 266.4739,
+        253.3079, 240.9693, 229.2494, 217.9296, 207.0682, 196.3863, 186.4544, 176.7278,
+        167.4492, 158.6698, 150.1274, 142.0235, 134.2938, 126.9779, 119.9318, 112.8453,
+        106.4212, 100.4950, 94.7947, 89.3060, 83.8515, 78.7648, 73.9626, 69.4903,
+        65.2399, 61.2707, 57.5805, 53.8807, 50.3581, 47.0654, 43.9962, 41.3293,
+        38.8103, 36.3873, 33.9981, 31.5566, 29.3774, 27.3885, 25.5847, 23.7774,
+        21.8648, 20.2049, 18.7841, 17.5068, 16.3034, 15.2616, 14.0999, 13.1606,
+        12.1689, 11.2982, 10.3769, 9.5366, 8.9948, 8.2364, 7.6672, 6.9836,
+        6.6207, 5.9819, 5.4887, 5.1665, 4.7919, 4.5142, 4.0838, 4.0710,
+        3.6078, 2.9821, 2.5893, 2.4208, 2.2287, 2.1451, 1.7568, 1.6837,
+        1.6975, 1.3985, 1.0231, 0.9845,
+    ],
+    // p = 11
+    &[
+        1424.0579, 1372.8660, 1322.3521, 1273.5544, 1225.4583, 1178.9540, 1133.6630, 1089.0909,
+        1046.1140, 1003.8958, 963.2333, 923.7225, 885.0137, 847.7525, 811.2288, 776.3924,
+        742.5150, 709.3156, 677.4995, 646.7126, 617.2299, 588.7788, 560.9801, 534.5242,
+        508.5456, 483.9493, 460.3803, 437.3350, 415.3155, 394.2477, 374.0604, 354.8844,
+        336.1795, 318.5594, 301.1989, 284.6809, 269.3616, 254.6766, 240.6418, 227.2087,
+        214.6483, 202.6207, 190.6948, 179.4778, 168.9717, 159.0071, 149.7198, 140.7950,
+        132.4296, 124.3129, 116.6713, 109.8604, 103.1604, 96.5380, 90.6530, 84.8905,
+        79.5201, 74.4492, 69.5406, 64.7987, 60.5682, 56.8407, 53.1740, 49.8287,
+        46.4032, 43.0620, 39.9319, 37.4445, 34.5283, 32.8136, 30.3220, 28.5393,
+        26.9428, 25.5427, 24.2516, 22.4170, 21.2274, 20.3731, 18.7661, 16.7249,
+        15.5855, 14.8062, 13.8346, 13.1955, 12.3359, 11.9988, 11.2757, 10.9952,
+        10.7396, 9.9769, 9.4602, 8.8531, 8.2233, 8.6801, 7.9361, 7.3185,
+        7.6900, 7.2351, 6.7502, 6.3941,
+    ],
+    // p = 12
+    &[
+        2848.8329, 2745.9155, 2645.4276, 2547.2425, 2451.3586, 2358.4205, 2267.4668, 2178.8160,
+        2092.1578, 2008.0712, 1926.7548, 1847.3251, 1770.1577, 1695.3927, 1622.8762, 1552.8830,
+        1484.7071, 1418.9433, 1355.3081, 1293.3511, 1234.0112, 1176.3830, 1120.8186, 1067.1695,
+        1015.5025, 966.1916, 918.3103, 872.1870, 827.7839, 785.5126, 745.3195, 706.6612,
+        669.4660, 634.2339, 599.9620, 567.5612, 536.3446, 505.8847, 477.3365, 450.4147,
+        424.5993, 400.1171, 376.7311, 354.5328, 333.4484, 313.4875, 295.1783, 277.2950,
+        261.0125, 244.9224, 229.6087, 214.9747, 200.8567, 188.3848, 175.7268, 164.2421,
+        152.9450, 142.3343, 132.5923, 123.0474, 114.4764, 106.0118, 99.1658, 92.3965,
+        85.7527, 78.8915, 72.8120, 68.2644, 63.3588, 58.1155, 53.3327, 49.4194,
+        44.7571, 40.5824, 36.1777, 31.9304, 29.3294, 25.9954, 23.2198, 20.2317,
+        18.4629, 16.4042, 14.1950, 10.9387, 8.3171, 7.0944, 5.6155, 3.5946,
+        3.0348, 0.8783, 0.4092, -0.2149, -1.9093, -1.8433, -3.3720, -4.0668,
+        -7.0748, -8.0849, -7.4794, -8.6934,
+    ],
+    // p = 13
+    &[
+        5698.1579, 5492.2706, 5291.6355, 5095.1707, 4903.6194, 4717.3037, 4535.1120, 4358.2106,
+        4185.3668, 4017.3647, 3854.4000, 3695.6198, 3541.9353, 3392.2695, 3247.4011, 3106.8981,
+        2970.6419, 2839.3241, 2712.1234, 2588.7710, 2470.0684, 2355.0415, 2245.1116, 2138.5577,
+        2036.2899, 1937.5429, 1842.3042, 1750.5204, 1662.6030, 1578.4958, 1496.9536, 1418.6223,
+        1344.4602, 1273.4130, 1205.1020, 1140.1921, 1078.2155, 1019.3563, 963.4430, 909.3273,
+        858.3080, 809.5003, 763.0964, 718.6583, 677.9863, 638.1326, 599.3326, 562.6330,
+        528.3634, 494.5911, 465.2478, 437.9010, 410.5264, 384.6038, 359.9135, 336.0511,
+        313.1003, 292.3464, 272.0147, 256.2558, 239.5240, 223.5903, 208.5845, 192.3362,
+        178.6061, 165.3549, 152.8241, 142.9111, 133.1509, 124.3703, 117.6634, 106.9633,
+        100.6675, 93.3489, 85.3337, 76.4552, 69.7688, 64.0837, 59.4459, 53.7573,
+        51.0800, 47.3406, 43.3348, 39.4250, 36.0366, 32.9585, 28.9610, 26.5863,
+        22.5905, 19.3797, 17.0455, 15.5220, 14.4709, 13.5108, 15.9824, 16.0894,
+        13.4891, 9.8814, 9.0701, 5.0873,
+    ],
+    // p = 14
+    &[
+        11396.4848, 10985.4197, 10583.6477, 10191.6777, 9808.5566, 9434.9663, 9071.0185, 8717.0283,
+        8371.9262, 8035.6065, 7709.3353, 7391.9948, 7084.0335, 6783.8795, 6493.9692, 6213.4070,
+        5942.0023, 5678.7775, 5423.8166, 5177.8611, 4940.0940, 4709.6928, 4488.4139, 4275.3401,
+        4068.8017, 3870.8897, 3680.0115, 3496.5854, 3320.7570, 3152.5294, 2991.8168, 2836.1797,
+        2686.5121, 2545.8371, 2409.3197, 2276.9328, 2153.5864, 2035.9359, 1923.2956, 1813.5812,
+        1710.4383, 1611.1566, 1518.3452, 1427.5360, 1344.1492, 1264.3437, 1187.5680, 1117.9835,
+        1049.7706, 985.5352, 924.1869, 864.8803, 810.6751, 757.4575, 710.3800, 664.0487,
+        621.5515, 582.6092, 543.0410, 504.0534, 472.4241, 440.2173, 411.8791, 384.7756,
+        357.4808, 330.4004, 307.9276, 283.6326, 264.8561, 246.1617, 231.6511, 213.3535,
+        198.2279, 186.7757, 174.4775, 158.7539, 147.8799, 136.8499, 128.7544, 120.4675,
+        113.3863, 107.4833, 103.4345, 97.0556, 92.8073, 86.4699, 79.2994, 76.4540,
+        71.3813, 68.2631, 65.0070, 60.8144, 60.1444, 59.4491, 55.9683, 50.3705,
+        47.4170, 46.8050, 40.3006, 41.8993,
+    ],
+    // p = 15
+    &[
+        22793.6795, 21971.5913, 21167.7265, 20382.8231, 19618.4244, 18872.2377, 18144.3432, 17435.7202,
+        16745.1571, 16073.3767, 15420.3952, 14786.5569, 14169.9051, 13570.0930, 12990.6824, 12430.8958,
+        11887.2147, 11359.0265, 10851.0078, 10357.4458, 9881.4140, 9419.9410, 8976.6825, 8548.9284,
+        8139.0050, 7745.5613, 7368.4844, 7004.1539, 6656.4005, 6318.6139, 6000.3320, 5687.8356,
+        5391.0178, 5102.4068, 4832.4490, 4572.3912, 4322.9480, 4080.9255, 3854.3344, 3641.3969,
+        3431.7717, 3236.2140, 3049.7264, 2873.1860, 2705.6533, 2548.3092, 2400.1486, 2254.1841,
+        2121.0665, 1990.9288, 1868.9794, 1754.2349, 1644.5412, 1542.8931, 1445.0032, 1351.1714,
+        1265.2338, 1181.9875, 1099.7288, 1025.0040, 957.1053, 887.1975, 829.8019, 769.8557,
+        719.4290, 666.7554, 620.6533, 579.2285, 541.7224, 503.7267, 466.0635, 434.6420,
+        405.5764, 376.5103, 347.1447, 321.8023, 294.3570, 271.0067, 251.7803, 231.9706,
+        216.3162, 194.9344, 182.3040, 166.1388, 153.7992, 145.4080, 134.9289, 131.3541,
+        121.8005, 110.5914, 103.8388, 96.1258, 82.4173, 75.3598, 65.9346, 59.5062,
+        57.5659, 43.7055, 34.9820, 33.9802,
+    ],
+    // p = 16
+    &[
+        45588.8803, 43942.6334, 42334.6547, 40765.3809, 39233.1111, 37737.9728, 36281.4218, 34861.5068,
+        33481.9478, 32138.1758, 30831.9225, 29563.8091, 28334.3231, 27136.9046, 25976.7327, 24850.2636,
+        23764.6506, 22713.6913, 21697.9878, 20713.5473, 19757.5983, 18839.0031, 17957.2966, 17102.9769,
+        16280.1639, 15496.8962, 14736.7017, 14002.4801, 13294.0742, 12614.1200, 11965.3031, 11343.8612,
+        10747.1500, 10182.8544, 9633.9192, 9107.2732, 8613.3403, 8138.6507, 7678.8444, 7240.2513,
+        6835.6955, 6444.6934, 6076.5449, 5727.0637, 5395.2136, 5069.6619, 4755.9926, 4471.5435,
+        4205.1841, 3953.1717, 3716.8998, 3477.6911, 3257.4016, 3052.7654, 2849.4519, 2664.3640,
+        2489.5204, 2327.5565, 2175.3973, 2036.1041, 1902.8505, 1774.8078, 1661.6462, 1542.3060,
+        1445.0284, 1334.4272, 1242.4262, 1155.5592, 1075.8126, 1004.5198, 931.8458, 871.8475,
+        798.5997, 734.4554, 677.2743, 614.8633, 577.5821, 523.9106, 485.7217, 447.0279,
+        423.3112, 383.8466, 358.0146, 321.8040, 308.8126, 277.2631, 263.1132, 251.7167,
+        251.3203, 236.3145, 206.7194, 205.0138, 196.7471, 200.5188, 178.2065, 175.1003,
+        165.9867, 159.5616, 147.2837, 124.9050,
+    ],
+    // p = 17
+    &[
+        91177.5288, 87885.9376, 84672.7793, 81534.4625, 78471.6235, 75489.0813, 72580.5743, 69747.2712,
+        66992.0445, 64307.2361, 61702.3182, 59165.0415, 56704.3358, 54329.3931, 52008.0722, 49769.2008,
+        47590.3844, 45482.7630, 43455.2317, 41482.2414, 39586.5839, 37748.1266, 35978.2304, 34277.1681,
+        32628.7794, 31051.9768, 29538.8404, 28086.2887, 26682.1046, 25344.6361, 24050.7808, 22791.3978,
+        21592.5024, 20450.8581, 19352.0279, 18302.2881, 17312.0555, 16346.2536, 15446.1678, 14564.6192,
+        13745.8215, 12974.0526, 12223.9687, 11489.8995, 10820.0337, 10193.0636, 9575.2181, 9006.3146,
+        8471.7543, 7960.5621, 7473.1381, 7002.1440, 6562.7827, 6140.9517, 5731.8038, 5390.1933,
+        5052.0453, 4710.9468, 4396.2190, 4087.7652, 3796.9445, 3556.7465, 3331.7431, 3095.5138,
+        2864.3099, 2663.9333, 2495.7316, 2275.6736, 2121.5517, 1944.0791, 1831.2653, 1692.4388,
+        1565.1121, 1457.7997, 1335.0107, 1243.6868, 1165.4027, 1068.4804, 1029.4327, 946.2004,
+        880.9127, 825.6296, 749.6676, 700.7190, 660.3797, 627.7723, 579.3754, 534.4556,
+        447.1945, 403.4532, 345.7140, 358.0097, 310.7555, 250.5942, 204.9179, 189.4450,
+        165.5796, 154.2255, 90.2864, 109.6812,
+    ],
+    // p = 18
+    &[
+        182356.7843, 175775.7187, 169345.2116, 163066.3922, 156940.8979, 150966.1536, 145149.6772, 139488.3394,
+        133964.0037, 128590.5280, 123367.5915, 118276.2361, 113336.5793, 108564.7982, 103940.2793, 99458.6490,
+        95110.0076, 90904.5593, 86832.9681, 82918.2520, 79113.4869, 75449.6479, 71906.7207, 68496.1967,
+        65195.7230, 62038.1513, 58968.1263, 56041.6717, 53228.5448, 50527.7511, 47968.2010, 45522.2337,
+        43149.9517, 40900.3514, 38737.7315, 36615.9384, 34636.6742, 32727.8177, 30947.6613, 29232.5959,
+        27611.0678, 26070.2852, 24564.9714, 23171.0842, 21839.7912, 20576.2694, 19348.6499, 18188.9117,
+        17099.4662, 16101.9931, 15133.2439, 14259.4524, 13430.1130, 12595.5088, 11785.1262, 11066.6084,
+        10372.0044, 9723.1179, 9107.2392, 8526.2580, 8020.0910, 7446.8368, 6894.7950, 6472.8745,
+        6116.1922, 5751.2805, 5367.1495, 4968.5443, 4696.9991, 4470.2959, 4175.8394, 3923.7914,
+        3633.8473, 3360.7468, 3160.4966, 3033.4391, 2869.8500, 2639.7162, 2476.0979, 2282.7770,
+        2173.0718, 2030.1734, 1924.1240, 1839.4196, 1727.9521, 1648.1324, 1514.4250, 1414.5958,
+        1354.4069, 1323.3638, 1302.5825, 1206.7986, 1233.9668, 1159.6050, 1044.5808, 1007.5882,
+        1053.5162, 1079.5711, 1085.3835, 1060.9277,
+    ],
+];
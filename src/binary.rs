@@ -0,0 +1,280 @@
+use std::io::{Read, Write};
+
+use crate::error::HyperLogLogError;
+use crate::registers::{DenseRegisters, RegisterBackend};
+use crate::sparse::{SparseRegisters, SPARSE_PRECISION};
+use crate::stable::HasherState;
+use crate::{bucket_count, max_rank, Buckets};
+
+/// First bytes of every encoded sketch.
+pub(crate) const MAGIC: [u8; 4] = *b"HLLS";
+
+/// Format version written by this release. Readers dispatch on it, so that a newer
+/// release keeps reading every older layout.
+pub const FORMAT_VERSION: u8 = 4;
+
+// p, representation, backend and fingerprint, following magic and version
+const HEADER_FIXED_LEN: usize = 1 + 1 + 1 + 8;
+
+// representation codes
+const SPARSE: u8 = 0;
+const DENSE_U8: u8 = 1;
+const DENSE_PACKED6: u8 = 2;
+const DENSE_HLL4: u8 = 3;
+
+/// A sketch read back from the binary format, checked against its checksum and layout
+/// but not yet against the hasher.
+pub(crate) struct Decoded {
+    pub(crate) p: u32,
+    pub(crate) m: usize,
+    pub(crate) backend: RegisterBackend,
+    pub(crate) fingerprint: u64,
+    pub(crate) hasher: Option<HasherRecord>, // absent in version 1
+    pub(crate) prehashed: Option<u64>, // absent before version 4
+    pub(crate) buckets: Buckets,
+}
+
+/// Hash algorithm, seed and parameters recorded in the header.
+pub(crate) struct HasherRecord {
+    pub(crate) algorithm: String,
+    pub(crate) seed: u64,
+    pub(crate) parameters: Vec<u8>, // empty in version 2
+}
+
+/// Writes a sketch in the current format version:
+///
+/// | bytes | field |
+/// |-------|-------|
+/// | 4 | magic `HLLS` |
+/// | 1 | format version |
+/// | 1 | p |
+/// | 1 | representation: 0 sparse, 1 `u8` registers, 2 packed 6-bit, 3 HLL4 |
+/// | 1 | register backend: 0 `U8`, 1 `Packed6`, 2 `Hll4` |
+/// | 8 | hasher fingerprint |
+/// | 1 | length of the hash algorithm name |
+/// | n | hash algorithm name, UTF-8 |
+/// | 8 | hash seed |
+/// | 2 | length of the hasher parameters |
+/// | n | hasher parameters, see `HasherState` |
+/// | 1 | 1 for a sketch fed with raw hashes, 0 otherwise |
+/// | 8 | id of the hash function of the raw hashes, only present for a sketch fed with them |
+/// | 4 | payload length |
+/// | n | payload |
+/// | 4 | CRC-32 of all the bytes above |
+///
+/// Integers are little-endian. Version 3 had no raw hash record, version 2 no hasher parameters either,
+/// and version 1 no hash algorithm name and seed.
+pub(crate) fn write<W: Write, H: HasherState>(
+    writer: &mut W,
+    p: u32,
+    backend: RegisterBackend,
+    fingerprint: u64,
+    hasher: &H,
+    prehashed: Option<u64>,
+    buckets: &Buckets,
+) -> std::io::Result<()> {
+    let algorithm = hasher.algorithm().as_bytes();
+    if algorithm.len() > u8::MAX as usize {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "hash algorithm name longer than 255 bytes"));
+    }
+    let parameters = hasher.parameters();
+    if parameters.len() > u16::MAX as usize {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "hasher parameters longer than 65535 bytes"));
+    }
+
+    let mut payload = Vec::new();
+    let representation = match buckets {
+        Buckets::Sparse(sparse) => {
+            sparse.write_payload(&mut payload);
+            SPARSE
+        }
+        Buckets::Dense(registers) => {
+            registers.write_payload(&mut payload);
+            match registers.backend() {
+                RegisterBackend::U8 => DENSE_U8,
+                RegisterBackend::Packed6 => DENSE_PACKED6,
+                RegisterBackend::Hll4 => DENSE_HLL4,
+            }
+        }
+    };
+
+    let mut header = Vec::with_capacity(MAGIC.len() + 1 + HEADER_FIXED_LEN + 1 + algorithm.len() + 8 + 2 + parameters.len() + 1 + 8 + 4);
+    header.extend_from_slice(&MAGIC);
+    header.push(FORMAT_VERSION);
+    header.push(p as u8);
+    header.push(representation);
+    header.push(backend_code(backend));
+    header.extend_from_slice(&fingerprint.to_le_bytes());
+    header.push(algorithm.len() as u8);
+    header.extend_from_slice(algorithm);
+    header.extend_from_slice(&hasher.seed().to_le_bytes());
+    header.extend_from_slice(&(parameters.len() as u16).to_le_bytes());
+    header.extend_from_slice(&parameters);
+    match prehashed {
+        Some(hash_id) => {
+            header.push(1);
+            header.extend_from_slice(&hash_id.to_le_bytes());
+        }
+        None => header.push(0),
+    }
+    header.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+
+    let checksum = crc32(crc32(0, &header), &payload);
+    writer.write_all(&header)?;
+    writer.write_all(&payload)?;
+    writer.write_all(&checksum.to_le_bytes())
+}
+
+/// Reads one sketch, consuming exactly the bytes `write` produced.
+pub(crate) fn read<R: Read>(reader: &mut R) -> Result<Decoded, HyperLogLogError> {
+    let mut header = vec![0u8; MAGIC.len() + 1];
+    reader.read_exact(&mut header)?;
+    if header[..4] != MAGIC {
+        return Err(HyperLogLogError::InvalidMagic);
+    }
+
+    match header[4] {
+        version @ 1..=4 => read_layout(reader, header, version),
+        version => Err(HyperLogLogError::UnsupportedVersion(version)),
+    }
+}
+
+/// Reads the rest of a version 1 to 4 sketch, which only differ in the hasher and raw hash records
+/// written after the fingerprint. `header` holds the bytes read so far.
+fn read_layout<R: Read>(reader: &mut R, mut header: Vec<u8>, version: u8) -> Result<Decoded, HyperLogLogError> {
+    let at = read_into(reader, &mut header, HEADER_FIXED_LEN)?;
+    let p = header[at] as u32;
+    // before anything is sized from `p`, which comes from untrusted bytes
+    let m = bucket_count(p)?;
+    let representation = header[at + 1];
+    let backend = backend_from_code(header[at + 2])?;
+    let fingerprint = u64::from_le_bytes(header[at + 3..at + 11].try_into().unwrap());
+
+    let hasher = if version >= 2 {
+        let at = read_into(reader, &mut header, 1)?;
+        let len = header[at] as usize;
+        let at = read_into(reader, &mut header, len + 8)?;
+        let algorithm = String::from_utf8(header[at..at + len].to_vec())
+            .map_err(|_| HyperLogLogError::CorruptPayload("hash algorithm name is not UTF-8".to_string()))?;
+        let seed = u64::from_le_bytes(header[at + len..].try_into().unwrap());
+        let parameters = if version >= 3 {
+            let at = read_into(reader, &mut header, 2)?;
+            let len = u16::from_le_bytes(header[at..].try_into().unwrap()) as usize;
+            let at = read_into(reader, &mut header, len)?;
+            header[at..].to_vec()
+        } else {
+            Vec::new()
+        };
+        Some(HasherRecord { algorithm, seed, parameters })
+    } else {
+        None
+    };
+
+    let prehashed = if version >= 4 {
+        let at = read_into(reader, &mut header, 1)?;
+        match header[at] {
+            0 => None,
+            1 => {
+                let at = read_into(reader, &mut header, 8)?;
+                Some(u64::from_le_bytes(header[at..].try_into().unwrap()))
+            }
+            other => {
+                return Err(HyperLogLogError::CorruptPayload(format!("unknown raw hash marker {}", other)));
+            }
+        }
+    } else {
+        None
+    };
+
+    let at = read_into(reader, &mut header, 4)?;
+    let payload_len = u32::from_le_bytes(header[at..].try_into().unwrap()) as usize;
+
+    // read through `take` so that a corrupt length cannot make us allocate more than is there
+    let mut payload = Vec::new();
+    reader.take(payload_len as u64).read_to_end(&mut payload)?;
+    if payload.len() != payload_len {
+        return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+    }
+
+    let mut checksum = [0u8; 4];
+    reader.read_exact(&mut checksum)?;
+    let expected = crc32(crc32(0, &header), &payload);
+    let found = u32::from_le_bytes(checksum);
+    if expected != found {
+        return Err(HyperLogLogError::ChecksumMismatch(expected, found));
+    }
+
+    let buckets = match representation {
+        SPARSE => {
+            if p >= SPARSE_PRECISION {
+                return Err(HyperLogLogError::SparseNotAllowed(p));
+            }
+            Buckets::Sparse(SparseRegisters::read_payload(&payload)?)
+        }
+        DENSE_U8 | DENSE_PACKED6 | DENSE_HLL4 => {
+            let layout = backend_from_code(representation - DENSE_U8)?;
+            if layout != backend {
+                return Err(HyperLogLogError::CorruptPayload(format!(
+                    "representation {} does not match backend {:?}", representation, backend
+                )));
+            }
+            Buckets::Dense(DenseRegisters::read_payload(layout, m, max_rank(p), &payload)?)
+        }
+        other => {
+            return Err(HyperLogLogError::CorruptPayload(format!("unknown representation {}", other)));
+        }
+    };
+
+    Ok(Decoded { p, m, backend, fingerprint, hasher, prehashed, buckets })
+}
+
+/// Appends `len` bytes from `reader` to `bytes`, returning where they start.
+fn read_into<R: Read>(reader: &mut R, bytes: &mut Vec<u8>, len: usize) -> std::io::Result<usize> {
+    let start = bytes.len();
+    bytes.resize(start + len, 0);
+    reader.read_exact(&mut bytes[start..])?;
+    Ok(start)
+}
+
+fn backend_code(backend: RegisterBackend) -> u8 {
+    match backend {
+        RegisterBackend::U8 => 0,
+        RegisterBackend::Packed6 => 1,
+        RegisterBackend::Hll4 => 2,
+    }
+}
+
+fn backend_from_code(code: u8) -> Result<RegisterBackend, HyperLogLogError> {
+    match code {
+        0 => Ok(RegisterBackend::U8),
+        1 => Ok(RegisterBackend::Packed6),
+        2 => Ok(RegisterBackend::Hll4),
+        other => Err(HyperLogLogError::CorruptPayload(format!("unknown register backend {}", other))),
+    }
+}
+
+// CRC-32 lookup table, reflected polynomial 0xedb88320 as in zlib
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xedb8_8320 } else { crc >> 1 };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// Continues the CRC-32 `crc` over `bytes`, starting from 0.
+fn crc32(crc: u32, bytes: &[u8]) -> u32 {
+    let mut crc = !crc;
+    for &byte in bytes {
+        crc = CRC_TABLE[((crc ^ byte as u32) & 0xff) as usize] ^ (crc >> 8);
+    }
+    !crc
+}
@@ -0,0 +1,38 @@
+/// Bytes written to a hasher that only hashes its input in `finish`, as the hashers of the other
+/// implementations do. Items up to `INLINE_LEN` bytes stay on the stack, longer ones spill to the heap.
+#[derive(Debug, Clone)]
+pub(crate) struct HashBuffer {
+    inline: [u8; INLINE_LEN],
+    len: usize, // bytes used in `inline`, unused once spilled
+    spill: Vec<u8>,
+}
+
+const INLINE_LEN: usize = 64;
+
+impl HashBuffer {
+    pub(crate) fn new() -> Self {
+        HashBuffer { inline: [0; INLINE_LEN], len: 0, spill: Vec::new() }
+    }
+
+    pub(crate) fn extend_from_slice(&mut self, bytes: &[u8]) {
+        if self.spill.is_empty() && self.len + bytes.len() <= INLINE_LEN {
+            self.inline[self.len..self.len + bytes.len()].copy_from_slice(bytes);
+            self.len += bytes.len();
+        } else {
+            if self.spill.is_empty() {
+                self.spill.extend_from_slice(&self.inline[..self.len]);
+            }
+            self.spill.extend_from_slice(bytes);
+        }
+    }
+
+    pub(crate) fn as_slice(&self) -> &[u8] {
+        if self.spill.is_empty() { &self.inline[..self.len] } else { &self.spill }
+    }
+}
+
+impl Default for HashBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
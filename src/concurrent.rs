@@ -0,0 +1,118 @@
+use std::borrow::Borrow;
+use std::hash::{BuildHasher, Hasher};
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::sync::OnceLock;
+
+use crate::{bucket_count, index_and_rank, HyperLogLog, HyperLogLogError, StableHasherBuilder, ToBytes};
+
+/// HyperLogLog that many threads can insert into at once through a shared reference.
+///
+/// Every register is an `AtomicU8` raised with `fetch_max`, so `insert` takes `&self` and never
+/// locks. The registers are always dense. Estimation, merging and serialization go through
+/// `snapshot`, which copies the registers into a regular `HyperLogLog`.
+pub struct ConcurrentHyperLogLog<T: ToBytes, S = StableHasherBuilder> {
+    p: u32, // number of bits
+    registers: Vec<AtomicU8>, // one register per bucket
+    hasher_builder: S, // hasher to use
+    prehashed: OnceLock<u64>, // id of the hash function given to insert_hash
+    has_items: AtomicBool, // set once items were inserted, which rules out raw hashes
+    // items are only hashed, never stored, so the sketch is `Sync` whatever `T` is
+    _marker: PhantomData<fn(T)>,
+}
+
+impl<T: ToBytes> ConcurrentHyperLogLog<T, StableHasherBuilder> {
+    /// Creates a concurrent sketch with `p` bits and the default hasher.
+    pub fn new(p: u32) -> Result<Self, HyperLogLogError> {
+        Self::with_hasher(p, Default::default())
+    }
+}
+
+impl<T: ToBytes, S: BuildHasher + Default + Clone> ConcurrentHyperLogLog<T, S> {
+    /// Creates a concurrent sketch with `p` bits and a custom hasher.
+    /// Fails if `p < 4` or if `p` is too large to shift safely.
+    pub fn with_hasher(p: u32, hasher_builder: S) -> Result<Self, HyperLogLogError> {
+        let m = bucket_count(p)?;
+        let registers = (0..m).map(|_| AtomicU8::new(0)).collect();
+        Ok(Self { p, registers, hasher_builder, prehashed: OnceLock::new(), has_items: AtomicBool::new(false), _marker: PhantomData })
+    }
+
+    /// Inserts an element. Registers only ever grow, so concurrent inserts of any items commute.
+    pub fn insert(&self, item: T) {
+        self.insert_ref(&item);
+    }
+
+    /// Inserts an element without taking ownership of it, as `HyperLogLog::insert_ref` does.
+    pub fn insert_ref<Q: ToBytes + ?Sized>(&self, item: &Q)
+    where
+        T: Borrow<Q>,
+    {
+        debug_assert!(self.prehashed.get().is_none(), "items cannot be inserted into a sketch of raw hashes");
+        if !self.has_items.load(Ordering::Relaxed) {
+            self.has_items.store(true, Ordering::Relaxed);
+        }
+        let mut hasher = self.hasher_builder.build_hasher();
+        item.hash_into(&mut hasher);
+        self.insert_hashed(hasher.finish());
+    }
+
+    /// Inserts a hash of the hash function `hash_id` computed outside of the sketch and marks the
+    /// sketch as prehashed, failing as `HyperLogLog::insert_hash` does. An item and a first hash
+    /// inserted at the same time on different threads can both get in; mixing is caught otherwise.
+    pub fn insert_hash(&self, hash_id: u64, hash: u64) -> Result<(), HyperLogLogError> {
+        if self.has_items.load(Ordering::Relaxed) {
+            return Err(HyperLogLogError::NotPrehashed);
+        }
+        let id = *self.prehashed.get_or_init(|| hash_id);
+        if id != hash_id {
+            return Err(HyperLogLogError::PrehashedIdMismatch(id, hash_id));
+        }
+        self.insert_hashed(hash);
+        Ok(())
+    }
+
+    /// Whether the sketch was fed with raw hashes through `insert_hash`.
+    pub fn is_prehashed(&self) -> bool {
+        self.prehashed.get().is_some()
+    }
+
+    /// The id of the hash function the sketch was fed with through `insert_hash`, if any.
+    pub fn prehashed_id(&self) -> Option<u64> {
+        self.prehashed.get().copied()
+    }
+
+    fn insert_hashed(&self, hash: u64) {
+        let (idx, rank) = index_and_rank(hash, self.p);
+        // the register is read first so that repeated items do not write the shared cache line
+        if self.registers[idx].load(Ordering::Relaxed) < rank {
+            // releases the prehashed id set before a raw hash to `snapshot`
+            self.registers[idx].fetch_max(rank, Ordering::Release);
+        }
+    }
+
+    /// Copies the registers into a regular dense `HyperLogLog`.
+    ///
+    /// Since registers only grow and each one is read once, the copy is the sketch of a set of items
+    /// holding every insert that completed before the call, and possibly some that ran during it.
+    pub fn snapshot(&self) -> HyperLogLog<T, S> {
+        let values = self.registers.iter().map(|register| register.load(Ordering::Acquire)).collect();
+        let mut hll = HyperLogLog::from_buckets(self.p, values, self.hasher_builder.clone())
+            .expect("registers of a valid precision hold valid ranks");
+        // read after the registers, so that any raw hash among them comes with its id
+        hll.prehashed = self.prehashed_id();
+        hll
+    }
+
+    /// Estimates the cardinality of a snapshot with the default estimator.
+    pub fn calculate_cardinality(&self) -> u64 {
+        self.snapshot().calculate_cardinality()
+    }
+
+    pub fn get_p(&self) -> u32 {
+        self.p
+    }
+
+    pub fn get_m(&self) -> usize {
+        self.registers.len()
+    }
+}
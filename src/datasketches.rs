@@ -0,0 +1,416 @@
+use std::hash::{BuildHasher, Hasher};
+
+use crate::buffer::HashBuffer;
+use crate::error::HyperLogLogError;
+use crate::registers::{DenseRegisters, RegisterBackend};
+use crate::stable::{HasherIdentity, HasherState};
+
+/// Register layout of a DataSketches `HllSketch`, its `TgtHllType`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DataSketchesHllType {
+    /// 4-bit registers relative to the current minimum, with an exception table.
+    #[default]
+    Hll4,
+    /// 6-bit packed registers.
+    Hll6,
+    /// One byte per register.
+    Hll8,
+}
+
+// seed of `Util.DEFAULT_UPDATE_SEED`
+const DEFAULT_UPDATE_SEED: u64 = 9001;
+// `HllSketch.DEFAULT_LG_K`
+const DEFAULT_LG_K: u32 = 12;
+const MIN_LG_K: u32 = 4;
+const MAX_LG_K: u32 = 21;
+
+// preamble layout of serialization version 1
+const SER_VER: u8 = 1;
+const FAMILY_ID: u8 = 7;
+const LIST_PREINTS: u8 = 2;
+const SET_PREINTS: u8 = 3;
+const HLL_PREINTS: u8 = 10;
+const LIST_INT_ARR_START: usize = 8;
+const HASH_SET_COUNT_INT: usize = 8;
+const HASH_SET_INT_ARR_START: usize = 12;
+const HIP_ACCUM_DOUBLE: usize = 8;
+const KXQ0_DOUBLE: usize = 16;
+const KXQ1_DOUBLE: usize = 24;
+const CUR_MIN_COUNT_INT: usize = 32;
+const AUX_COUNT_INT: usize = 36;
+const HLL_BYTE_ARR_START: usize = 40;
+// `LG_INIT_LIST_SIZE`, written for empty sketches
+const LG_INIT_LIST_SIZE: u8 = 3;
+
+// flag bits
+const BIG_ENDIAN_FLAG: u8 = 1;
+const EMPTY_FLAG: u8 = 4;
+const COMPACT_FLAG: u8 = 8;
+const OUT_OF_ORDER_FLAG: u8 = 16;
+
+// current modes, bits 0-1 of the mode byte
+const LIST: u8 = 0;
+const SET: u8 = 1;
+const HLL: u8 = 2;
+
+// coupons hold the slot in their low 26 bits and the value in the 6 bits above
+const KEY_BITS_26: u32 = 26;
+const KEY_MASK_26: u32 = (1 << KEY_BITS_26) - 1;
+// nibble marking an HLL_4 register stored in the exception table
+const AUX_TOKEN: u8 = 15;
+// largest value of a DataSketches register
+const MAX_VALUE: u8 = 63;
+// initial log2 size of the HLL_4 exception table for each lgK, `LG_AUX_ARR_INTS`
+const LG_AUX_ARR_INTS: [u8; 22] = [0, 2, 2, 2, 2, 2, 2, 3, 3, 3, 4, 4, 5, 5, 6, 7, 8, 9, 10, 11, 12, 13];
+
+/// MurmurHash3_x64_128, returning both 64-bit halves.
+pub(crate) fn murmurhash3_x64_128(data: &[u8], seed: u64) -> (u64, u64) {
+    const C1: u64 = 0x87c37b91114253d5;
+    const C2: u64 = 0x4cf5ad432745937f;
+
+    let mix_k1 = |k1: u64| k1.wrapping_mul(C1).rotate_left(31).wrapping_mul(C2);
+    let mix_k2 = |k2: u64| k2.wrapping_mul(C2).rotate_left(33).wrapping_mul(C1);
+
+    let (mut h1, mut h2) = (seed, seed);
+    let mut blocks = data.chunks_exact(16);
+    for block in &mut blocks {
+        let k1 = u64::from_le_bytes(block[..8].try_into().unwrap());
+        let k2 = u64::from_le_bytes(block[8..].try_into().unwrap());
+
+        h1 ^= mix_k1(k1);
+        h1 = h1.rotate_left(27).wrapping_add(h2).wrapping_mul(5).wrapping_add(0x52dce729);
+        h2 ^= mix_k2(k2);
+        h2 = h2.rotate_left(31).wrapping_add(h1).wrapping_mul(5).wrapping_add(0x38495ab5);
+    }
+
+    let tail = blocks.remainder();
+    let (mut k1, mut k2) = (0u64, 0u64);
+    for (i, &byte) in tail.iter().enumerate() {
+        if i < 8 {
+            k1 |= (byte as u64) << (8 * i);
+        } else {
+            k2 |= (byte as u64) << (8 * (i - 8));
+        }
+    }
+    if tail.len() > 8 {
+        h2 ^= mix_k2(k2);
+    }
+    if !tail.is_empty() {
+        h1 ^= mix_k1(k1);
+    }
+
+    h1 ^= data.len() as u64;
+    h2 ^= data.len() as u64;
+    h1 = h1.wrapping_add(h2);
+    h2 = h2.wrapping_add(h1);
+    h1 = fmix64(h1);
+    h2 = fmix64(h2);
+    h1 = h1.wrapping_add(h2);
+    h2 = h2.wrapping_add(h1);
+    (h1, h2)
+}
+
+fn fmix64(mut k: u64) -> u64 {
+    k ^= k >> 33;
+    k = k.wrapping_mul(0xff51afd7ed558ccd);
+    k ^= k >> 33;
+    k = k.wrapping_mul(0xc4ceb9fe1a85ec53);
+    k ^= k >> 33;
+    k
+}
+
+/// `BuildHasher` hashing items the way a DataSketches `HllSketch` with `lg_k` does,
+/// MurmurHash3_x64_128 with the default seed, so that sketches built on both sides can be merged.
+/// `lg_k` must be the precision of the sketch the hasher is used with.
+/// DataSketches hashes integers as little-endian words and strings as UTF-8, as `ToBytes` does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DataSketchesHasherBuilder {
+    lg_k: u32,
+}
+
+impl DataSketchesHasherBuilder {
+    /// Hasher for sketches with precision `lg_k`, between 4 and 21 as in DataSketches.
+    pub fn new(lg_k: u32) -> Result<Self, HyperLogLogError> {
+        if lg_k < MIN_LG_K {
+            return Err(HyperLogLogError::PrecisionBelowThreshold);
+        }
+        check_lg_k(lg_k)?;
+        Ok(Self { lg_k })
+    }
+
+    pub fn get_lg_k(&self) -> u32 {
+        self.lg_k
+    }
+}
+
+impl Default for DataSketchesHasherBuilder {
+    /// Hasher for the DataSketches default `lgK = 12`.
+    fn default() -> Self {
+        Self { lg_k: DEFAULT_LG_K }
+    }
+}
+
+impl BuildHasher for DataSketchesHasherBuilder {
+    type Hasher = DataSketchesHasher;
+
+    fn build_hasher(&self) -> DataSketchesHasher {
+        DataSketchesHasher { lg_k: self.lg_k, bytes: HashBuffer::new() }
+    }
+}
+
+impl HasherIdentity for DataSketchesHasherBuilder {
+    fn algorithm(&self) -> &'static str {
+        "datasketches-murmur3-x64-128"
+    }
+
+    fn seed(&self) -> u64 {
+        DEFAULT_UPDATE_SEED
+    }
+}
+
+// the hash bits are moved around for `lg_k`, which is recorded as a single byte
+impl HasherState for DataSketchesHasherBuilder {
+    fn parameters(&self) -> Vec<u8> {
+        vec![self.lg_k as u8]
+    }
+
+    fn restore(seed: u64, parameters: &[u8]) -> Option<Self> {
+        match parameters {
+            &[lg_k] if seed == Self::default().seed() => Self::new(lg_k as u32).ok(),
+            _ => None,
+        }
+    }
+}
+
+/// Hasher built by `DataSketchesHasherBuilder`.
+///
+/// DataSketches takes the slot from the low `lgK` bits of the first half of the hash and the value
+/// from the leading zeros of the second half, while this crate reads the index from the top bits
+/// and the rank from the leading zeros of the bits below it. `finish` moves the slot to the top,
+/// followed by the second half, so that both agree. The ranks only differ past `64 - lgK` leading
+/// zeros, where this crate caps them.
+#[derive(Debug, Clone)]
+pub struct DataSketchesHasher {
+    lg_k: u32,
+    bytes: HashBuffer,
+}
+
+impl Hasher for DataSketchesHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        let (h1, h2) = murmurhash3_x64_128(self.bytes.as_slice(), DEFAULT_UPDATE_SEED);
+        let slot = h1 & ((1u64 << self.lg_k) - 1);
+        (slot << (64 - self.lg_k)) | (h2 >> self.lg_k)
+    }
+}
+
+/// `lgK` and the register values of a serialized `HllSketch`, in any mode and register layout.
+/// Values above the largest rank this crate stores at `lgK`, `64 - lgK + 1`, are lowered to it.
+pub(crate) fn decode(bytes: &[u8]) -> Result<(u32, Vec<u8>), HyperLogLogError> {
+    let corrupt = |msg: String| HyperLogLogError::CorruptPayload(format!("DataSketches HLL: {}", msg));
+    let truncated = || corrupt(format!("image of {} bytes is truncated", bytes.len()));
+
+    if bytes.len() < 8 {
+        return Err(truncated());
+    }
+    let (pre_ints, ser_ver, family, lg_k, lg_arr, flags, byte6, mode) =
+        (bytes[0], bytes[1], bytes[2], bytes[3] as u32, bytes[4] as u32, bytes[5], bytes[6], bytes[7]);
+    if family != FAMILY_ID {
+        return Err(HyperLogLogError::InvalidMagic);
+    }
+    if ser_ver != SER_VER {
+        return Err(HyperLogLogError::UnsupportedVersion(ser_ver));
+    }
+    if !(MIN_LG_K..=MAX_LG_K).contains(&lg_k) {
+        return Err(corrupt(format!("lgK {} out of range", lg_k)));
+    }
+    if flags & BIG_ENDIAN_FLAG != 0 {
+        return Err(corrupt("big-endian images are not supported".to_string()));
+    }
+
+    let k = 1usize << lg_k;
+    let mut registers = vec![0u8; k];
+    let (cur_mode, hll_type) = (mode & 3, (mode >> 2) & 3);
+    let compact = flags & COMPACT_FLAG != 0;
+
+    let expected_pre_ints = match cur_mode {
+        LIST => LIST_PREINTS,
+        SET => SET_PREINTS,
+        HLL => HLL_PREINTS,
+        other => return Err(corrupt(format!("unknown mode {}", other))),
+    };
+    if pre_ints != expected_pre_ints {
+        return Err(corrupt(format!("{} preamble ints for mode {}", pre_ints, cur_mode)));
+    }
+
+    if flags & EMPTY_FLAG != 0 {
+        return Ok((lg_k, registers));
+    }
+
+    let read_int = |offset: usize| -> Result<u32, HyperLogLogError> {
+        let chunk = bytes.get(offset..offset + 4).ok_or_else(truncated)?;
+        Ok(u32::from_le_bytes(chunk.try_into().unwrap()))
+    };
+    // `count` ints when compact, otherwise a hash table of `2^lg_arr` ints with 0 for empty slots
+    let read_ints = |start: usize, count: usize| -> Result<Vec<u32>, HyperLogLogError> {
+        let len = if compact {
+            count
+        } else {
+            1usize.checked_shl(lg_arr).filter(|&len| len <= k).ok_or_else(|| corrupt(format!("lgArr {} out of range", lg_arr)))?
+        };
+        let ints = (0..len).map(|i| read_int(start + 4 * i)).collect::<Result<Vec<_>, _>>()?;
+        Ok(ints.into_iter().filter(|&v| v != 0).collect())
+    };
+
+    match cur_mode {
+        LIST | SET => {
+            let coupons = if cur_mode == LIST {
+                read_ints(LIST_INT_ARR_START, byte6 as usize)?
+            } else {
+                read_ints(HASH_SET_INT_ARR_START, read_int(HASH_SET_COUNT_INT)? as usize)?
+            };
+            for coupon in coupons {
+                let slot = (coupon & KEY_MASK_26) as usize & (k - 1);
+                let value = (coupon >> KEY_BITS_26) as u8;
+                registers[slot] = registers[slot].max(value);
+            }
+        }
+        _ => {
+            let array = &bytes.get(HLL_BYTE_ARR_START..).ok_or_else(truncated)?;
+            match hll_type {
+                // HLL_4
+                0 => {
+                    let cur_min = byte6;
+                    let nibbles = array.get(..k / 2).ok_or_else(truncated)?;
+                    let aux_count = read_int(AUX_COUNT_INT)? as usize;
+                    let aux = if aux_count == 0 { Vec::new() } else { read_ints(HLL_BYTE_ARR_START + k / 2, aux_count)? };
+
+                    let mut exceptions = vec![None; k];
+                    for pair in aux {
+                        let slot = (pair & KEY_MASK_26) as usize;
+                        if slot >= k || exceptions[slot].is_some() {
+                            return Err(corrupt(format!("invalid exception for slot {}", slot)));
+                        }
+                        exceptions[slot] = Some((pair >> KEY_BITS_26) as u8);
+                    }
+
+                    for (slot, register) in registers.iter_mut().enumerate() {
+                        let nibble = (nibbles[slot / 2] >> ((slot % 2) * 4)) & 0x0f;
+                        let value = match (nibble, exceptions[slot].take()) {
+                            (AUX_TOKEN, Some(value)) => Some(value),
+                            (AUX_TOKEN, None) => None,
+                            (_, Some(_)) => None,
+                            (nibble, None) => cur_min.checked_add(nibble).filter(|&v| v <= MAX_VALUE),
+                        };
+                        *register = value.ok_or_else(|| corrupt(format!("register {} does not match the exceptions", slot)))?;
+                    }
+                }
+                // HLL_6, one spare byte follows the packed registers
+                1 => {
+                    if array.len() < k * 3 / 4 + 1 {
+                        return Err(truncated());
+                    }
+                    let packed = &array[..k * 3 / 4];
+                    registers = DenseRegisters::read_payload(RegisterBackend::Packed6, k, MAX_VALUE, packed)?.to_vec();
+                }
+                // HLL_8
+                2 => {
+                    registers = array.get(..k).ok_or_else(truncated)?.to_vec();
+                    if let Some(&value) = registers.iter().find(|&&v| v > MAX_VALUE) {
+                        return Err(HyperLogLogError::RegisterOutOfRange(value, MAX_VALUE));
+                    }
+                }
+                other => return Err(corrupt(format!("unknown register type {}", other))),
+            }
+        }
+    }
+
+    // DataSketches ranks go up to 63 whatever lgK is
+    let max_rank = (64 - lg_k + 1) as u8;
+    for register in registers.iter_mut() {
+        *register = (*register).min(max_rank);
+    }
+    Ok((lg_k, registers))
+}
+
+/// Serializes registers at `lg_k` as a compact `HllSketch` image using `hll_type`.
+/// Empty sketches are written in the empty list mode, all others in the HLL mode, flagged
+/// out of order since the HIP estimator is not tracked here; DataSketches then uses its
+/// composite estimator, and `estimate` is only stored as the HIP accumulator.
+pub(crate) fn encode(lg_k: u32, registers: &[u8], hll_type: DataSketchesHllType, estimate: f64) -> Vec<u8> {
+    let type_bits = match hll_type {
+        DataSketchesHllType::Hll4 => 0,
+        DataSketchesHllType::Hll6 => 1,
+        DataSketchesHllType::Hll8 => 2,
+    } << 2;
+
+    if registers.iter().all(|&v| v == 0) {
+        return vec![LIST_PREINTS, SER_VER, FAMILY_ID, lg_k as u8, LG_INIT_LIST_SIZE, EMPTY_FLAG | COMPACT_FLAG, 0, LIST | type_bits];
+    }
+
+    let cur_min = match hll_type {
+        DataSketchesHllType::Hll4 => registers.iter().copied().min().unwrap_or(0),
+        _ => 0,
+    };
+    let num_at_cur_min = registers.iter().filter(|&&v| v == cur_min).count() as u32;
+    // sums of 2^-v, split as in DataSketches to keep precision
+    let kxq0 = registers.iter().filter(|&&v| v < 32).fold(0.0, |sum, &v| sum + 2f64.powi(-(v as i32)));
+    let kxq1 = registers.iter().filter(|&&v| v >= 32).fold(0.0, |sum, &v| sum + 2f64.powi(-(v as i32)));
+
+    let mut array = Vec::new();
+    let mut aux = Vec::new();
+    match hll_type {
+        DataSketchesHllType::Hll4 => {
+            array = vec![0u8; registers.len() / 2];
+            for (slot, &value) in registers.iter().enumerate() {
+                let nibble = if value - cur_min >= AUX_TOKEN {
+                    aux.push(((value as u32) << KEY_BITS_26) | slot as u32);
+                    AUX_TOKEN
+                } else {
+                    value - cur_min
+                };
+                array[slot / 2] |= nibble << ((slot % 2) * 4);
+            }
+        }
+        DataSketchesHllType::Hll6 => {
+            DenseRegisters::from_values(RegisterBackend::Packed6, registers.to_vec()).write_payload(&mut array);
+            array.push(0);
+        }
+        DataSketchesHllType::Hll8 => array.extend_from_slice(registers),
+    }
+
+    // log2 size of the exception table a reader should allocate, kept under 3/4 full
+    let lg_aux = if aux.is_empty() {
+        0
+    } else {
+        let mut lg = LG_AUX_ARR_INTS[lg_k as usize];
+        while aux.len() * 4 > 3 << lg {
+            lg += 1;
+        }
+        lg
+    };
+
+    let mut out = vec![0u8; HLL_BYTE_ARR_START];
+    out[..8].copy_from_slice(&[HLL_PREINTS, SER_VER, FAMILY_ID, lg_k as u8, lg_aux, COMPACT_FLAG | OUT_OF_ORDER_FLAG, cur_min, HLL | type_bits]);
+    out[HIP_ACCUM_DOUBLE..KXQ0_DOUBLE].copy_from_slice(&estimate.to_le_bytes());
+    out[KXQ0_DOUBLE..KXQ1_DOUBLE].copy_from_slice(&kxq0.to_le_bytes());
+    out[KXQ1_DOUBLE..CUR_MIN_COUNT_INT].copy_from_slice(&kxq1.to_le_bytes());
+    out[CUR_MIN_COUNT_INT..AUX_COUNT_INT].copy_from_slice(&num_at_cur_min.to_le_bytes());
+    out[AUX_COUNT_INT..HLL_BYTE_ARR_START].copy_from_slice(&(aux.len() as u32).to_le_bytes());
+    out.extend_from_slice(&array);
+    for pair in aux {
+        out.extend_from_slice(&pair.to_le_bytes());
+    }
+    out
+}
+
+/// Checks that `p` is a precision DataSketches supports.
+pub(crate) fn check_lg_k(p: u32) -> Result<(), HyperLogLogError> {
+    if p > MAX_LG_K {
+        return Err(HyperLogLogError::UnsupportedPrecision(p));
+    }
+    Ok(())
+}
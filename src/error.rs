@@ -1,4 +1,4 @@
-use std::fmt::{self, write};
+use std::fmt;
 use std::error::Error;
 
 #[derive(Debug)]
@@ -6,7 +6,26 @@ pub enum HyperLogLogError {
     MisMatchedPrecision(u32, u32),
     MergeFailed(String),
     PrecisionBelowThreshold,
-    PrecisionTooLarge
+    PrecisionTooLarge,
+    BucketCountMismatch(usize, usize),
+    RegisterOutOfRange(u8, u8),
+    InvalidRelativeError(f64),
+    InvalidConfidence(f64),
+    HasherMismatch,
+    InvalidMagic,
+    UnsupportedVersion(u8),
+    ChecksumMismatch(u32, u32),
+    CorruptPayload(String),
+    Io(std::io::Error),
+    InconsistentBucketCount(u32, usize),
+    InvalidSparseEntry(u32),
+    SparseNotAllowed(u32),
+    InvalidRegisterWidth(u32),
+    InvalidExplicitThreshold(i64),
+    UnsupportedPrecision(u32),
+    CannotIncreasePrecision(u32, u32),
+    NotPrehashed,
+    PrehashedIdMismatch(u64, u64),
 }
 
 impl fmt::Display for HyperLogLogError {
@@ -22,7 +41,64 @@ impl fmt::Display for HyperLogLogError {
                 write!(f, "Precision p must be at least 4")
             }
             HyperLogLogError::PrecisionTooLarge => {
-                write!(f, "Precision too large, reduce p")
+                write!(f, "Precision too large, p must be at most {}", crate::MAX_PRECISION)
+            }
+            HyperLogLogError::BucketCountMismatch(expected, actual) => {
+                write!(f, "Bucket count mismatch: expected {}, found {}", expected, actual)
+            }
+            HyperLogLogError::RegisterOutOfRange(value, max) => {
+                write!(f, "Register value {} out of range, at most {} allowed", value, max)
+            }
+            HyperLogLogError::InvalidRelativeError(error) => {
+                write!(f, "Relative error must be between 0 and 1, found {}", error)
+            }
+            HyperLogLogError::InvalidConfidence(confidence) => {
+                write!(f, "Confidence level must be between 0 and 1, found {}", confidence)
+            }
+            HyperLogLogError::HasherMismatch => {
+                write!(f, "Hasher mismatch: sketches hash items differently")
+            }
+            HyperLogLogError::InvalidMagic => {
+                write!(f, "Not a serialized HyperLogLog: wrong magic bytes")
+            }
+            HyperLogLogError::UnsupportedVersion(version) => {
+                write!(f, "Unsupported format version {}", version)
+            }
+            HyperLogLogError::ChecksumMismatch(expected, actual) => {
+                write!(f, "Checksum mismatch: expected {:08x}, found {:08x}", expected, actual)
+            }
+            HyperLogLogError::CorruptPayload(msg) => {
+                write!(f, "Corrupt payload: {}", msg)
+            }
+            HyperLogLogError::Io(err) => {
+                write!(f, "I/O error: {}", err)
+            }
+            HyperLogLogError::InconsistentBucketCount(p, m) => {
+                write!(f, "Corrupt sketch: m = {} does not match 2^p with p = {}", m, p)
+            }
+            HyperLogLogError::InvalidSparseEntry(entry) => {
+                write!(f, "Corrupt sketch: invalid or unsorted sparse entry {:#x}", entry)
+            }
+            HyperLogLogError::SparseNotAllowed(p) => {
+                write!(f, "Corrupt sketch: sparse representation is not used at precision {}", p)
+            }
+            HyperLogLogError::InvalidRegisterWidth(width) => {
+                write!(f, "Register width must be between 1 and 8 bits, found {}", width)
+            }
+            HyperLogLogError::InvalidExplicitThreshold(threshold) => {
+                write!(f, "Explicit threshold must be -1, 0 or a power of 2 up to 2^30, found {}", threshold)
+            }
+            HyperLogLogError::UnsupportedPrecision(p) => {
+                write!(f, "Precision {} is not supported by the target format", p)
+            }
+            HyperLogLogError::CannotIncreasePrecision(from, to) => {
+                write!(f, "Cannot increase precision from {} to {}", from, to)
+            }
+            HyperLogLogError::NotPrehashed => {
+                write!(f, "Raw hashes cannot be inserted into a sketch of hashed items")
+            }
+            HyperLogLogError::PrehashedIdMismatch(expected, actual) => {
+                write!(f, "Raw hash function mismatch: the sketch holds hashes of {}, found {}", expected, actual)
             }
 
         }
@@ -31,7 +107,10 @@ impl fmt::Display for HyperLogLogError {
 
 impl Error for HyperLogLogError {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
-        None
+        match self {
+            HyperLogLogError::Io(err) => Some(err),
+            _ => None,
+        }
     }
 
     fn cause(&self) -> Option<&dyn Error> {
@@ -39,3 +118,8 @@ impl Error for HyperLogLogError {
     }
 }
 
+impl From<std::io::Error> for HyperLogLogError {
+    fn from(err: std::io::Error) -> Self {
+        HyperLogLogError::Io(err)
+    }
+}
@@ -0,0 +1,314 @@
+use crate::bias_tables::{BIAS_DATA, RAW_ESTIMATE_DATA};
+
+/// Precisions covered by the HyperLogLog++ bias tables.
+const BIAS_CORRECTION_MIN_P: u32 = 4;
+const BIAS_CORRECTION_MAX_P: u32 = 18;
+
+/// Number of nearest raw estimates averaged when interpolating the bias.
+const BIAS_NEIGHBOURS: usize = 6;
+
+/// Cardinalities under which linear counting is preferred over the bias corrected estimate,
+/// indexed by `p - 4` (values from the HyperLogLog++ paper).
+const LINEAR_COUNTING_THRESHOLDS: [f64; 15] = [
+    10.0, 20.0, 40.0, 80.0, 220.0, 400.0, 900.0, 1800.0, 3100.0,
+    6500.0, 11500.0, 20000.0, 50000.0, 120000.0, 350000.0,
+];
+
+/// Algorithm used to turn the buckets into a cardinality estimate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Estimator {
+    /// Original HyperLogLog estimator: linear counting below `2.5 * m`, raw harmonic mean above.
+    Classic,
+    /// HyperLogLog++ estimator: the raw estimate is corrected with empirical bias tables and
+    /// linear counting is used below a per-precision threshold.
+    /// Falls back to `Classic` for precisions outside `4..=18`.
+    #[default]
+    HyperLogLogPlusPlus,
+    /// Otmar Ertl's improved raw estimator, computed from the full register histogram.
+    /// Needs no thresholds or bias tables and works for any precision.
+    Ertl,
+}
+
+/// The method that actually produced an estimate.
+/// `Classic` and `HyperLogLogPlusPlus` switch between several of them depending on the range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EstimationMethod {
+    /// Linear counting on the number of empty buckets
+    LinearCounting,
+    /// Raw harmonic mean estimate
+    Raw,
+    /// Raw estimate corrected with the HyperLogLog++ empirical bias
+    BiasCorrected,
+    /// Ertl's improved raw estimator
+    Ertl,
+}
+
+/// A cardinality estimate together with the method that produced it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Estimate {
+    pub cardinality: f64,
+    pub method: EstimationMethod,
+}
+
+impl Estimate {
+    fn new(cardinality: f64, method: EstimationMethod) -> Self {
+        Self { cardinality, method }
+    }
+
+    /// The estimate rounded to the nearest integer, as returned by `calculate_cardinality`.
+    pub fn rounded(&self) -> u64 {
+        self.cardinality.round() as u64
+    }
+}
+
+/// A cardinality estimate with its standard error and the bounds of a confidence interval.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ConfidenceInterval {
+    pub estimate: Estimate,
+    /// theoretical relative standard error, `1.04 / sqrt(m)`
+    pub relative_standard_error: f64,
+    /// confidence level of the bounds, in `(0, 1)`
+    pub confidence: f64,
+    pub lower: f64,
+    pub upper: f64,
+}
+
+impl ConfidenceInterval {
+    pub(crate) fn new(estimate: Estimate, relative_standard_error: f64, confidence: f64) -> Self {
+        // two sided normal interval around the estimate
+        let z = normal_quantile(0.5 + confidence / 2.0);
+        let margin = z * relative_standard_error * estimate.cardinality;
+
+        Self {
+            estimate,
+            relative_standard_error,
+            confidence,
+            lower: (estimate.cardinality - margin).max(0.0),
+            upper: estimate.cardinality + margin,
+        }
+    }
+}
+
+/// Theoretical relative standard error of a sketch with `p` bits, `1.04 / sqrt(2^p)`.
+pub(crate) fn relative_standard_error(p: u32) -> f64 {
+    1.04 / ((1u64 << p) as f64).sqrt()
+}
+
+/// Smallest precision whose relative standard error is at most `error`.
+pub(crate) fn precision_for_error(error: f64) -> u32 {
+    // 1.04 / sqrt(2^p) <= error  <=>  p >= 2 * log2(1.04 / error)
+    let p = (2.0 * (1.04 / error).log2()).ceil();
+    p.max(4.0) as u32
+}
+
+/// Inverse of the standard normal CDF (Acklam's rational approximation, relative error below 1.2e-9).
+fn normal_quantile(q: f64) -> f64 {
+    const A: [f64; 6] = [
+        -3.969683028665376e1, 2.209460984245205e2, -2.759285104469687e2,
+        1.38357751867269e2, -3.066479806614716e1, 2.506628277459239e0,
+    ];
+    const B: [f64; 5] = [
+        -5.447609879822406e1, 1.615858368580409e2, -1.556989798598866e2,
+        6.680131188771972e1, -1.328068155288572e1,
+    ];
+    const C: [f64; 6] = [
+        -7.784894002430293e-3, -3.223964580411365e-1, -2.400758277161838e0,
+        -2.549732539343734e0, 4.374664141464968e0, 2.938163982698783e0,
+    ];
+    const D: [f64; 4] = [
+        7.784695709041462e-3, 3.224671290700398e-1, 2.445134137142996e0,
+        3.754408661907416e0,
+    ];
+    const P_LOW: f64 = 0.02425;
+
+    if q < P_LOW {
+        let r = (-2.0 * q.ln()).sqrt();
+        (((((C[0] * r + C[1]) * r + C[2]) * r + C[3]) * r + C[4]) * r + C[5])
+            / ((((D[0] * r + D[1]) * r + D[2]) * r + D[3]) * r + 1.0)
+    } else if q <= 1.0 - P_LOW {
+        let r = q - 0.5;
+        let s = r * r;
+        (((((A[0] * s + A[1]) * s + A[2]) * s + A[3]) * s + A[4]) * s + A[5]) * r
+            / (((((B[0] * s + B[1]) * s + B[2]) * s + B[3]) * s + B[4]) * s + 1.0)
+    } else {
+        -normal_quantile(1.0 - q)
+    }
+}
+
+/// Estimates the cardinality from the register histogram with the given estimator.
+pub(crate) fn estimate(estimator: Estimator, histogram: &[u32], p: u32) -> Estimate {
+    match estimator {
+        Estimator::Classic => classic(histogram, p),
+        Estimator::HyperLogLogPlusPlus => hyperloglog_plus_plus(histogram, p),
+        Estimator::Ertl => ertl(histogram, p),
+    }
+}
+
+/// Empirical alpha factor for `m` buckets
+fn alpha(m: usize) -> f64 {
+    match m {
+        16 => 0.673,
+        32 => 0.697,
+        64 => 0.709,
+        mm if mm >= 128 => 0.7213 / (1.0 + 1.079 / (mm as f64)),
+        _ => unreachable!("Bucket count m={} is unsupported", m),
+    }
+}
+
+/// Raw HyperLogLog estimate, `alpha * m^2 / sum(2^-bucket_value)`.
+fn raw_estimate(histogram: &[u32], m: usize) -> f64 {
+    // Harmonic mean of 2^{-bucket_value}
+    let sum: f64 = histogram.iter()
+        .enumerate()
+        .map(|(v, &count)| count as f64 * 2f64.powi(-(v as i32)))
+        .sum();
+
+    alpha(m) * (m as f64) * (m as f64) / sum
+}
+
+/// Linear counting estimate: m * ln(m / V)
+fn linear_counting(m: f64, zero: f64) -> f64 {
+    m * (m / zero).ln()
+}
+
+/// Original HyperLogLog estimate from the register histogram.
+fn classic(histogram: &[u32], p: u32) -> Estimate {
+    let m = 1usize << p;
+    let mf = m as f64;
+    // Count zero buckets
+    let zero = histogram[0] as f64;
+
+    // Empty set
+    if zero == mf {
+        return Estimate::new(0.0, EstimationMethod::LinearCounting);
+    }
+
+    let estimate = raw_estimate(histogram, m);
+
+    // Small-range (linear counting) correction
+    if zero > 0.0 {
+        let linear = linear_counting(mf, zero);
+        if linear <= 2.5 * mf {
+            return Estimate::new(linear, EstimationMethod::LinearCounting);
+        }
+    }
+
+    // no large range correction: with 64-bit hashes, collisions only matter close to 2^64,
+    // far beyond anything the registers can tell apart
+    Estimate::new(estimate, EstimationMethod::Raw)
+}
+
+/// HyperLogLog++ estimate from the register histogram.
+fn hyperloglog_plus_plus(histogram: &[u32], p: u32) -> Estimate {
+    if !(BIAS_CORRECTION_MIN_P..=BIAS_CORRECTION_MAX_P).contains(&p) {
+        return classic(histogram, p);
+    }
+
+    let m = 1usize << p;
+    let mf = m as f64;
+    let zero = histogram[0] as f64;
+
+    // Empty set
+    if zero == mf {
+        return Estimate::new(0.0, EstimationMethod::LinearCounting);
+    }
+
+    let raw = raw_estimate(histogram, m);
+    // the bias vanishes past 5m, the tables stop there
+    let corrected = if raw <= 5.0 * mf {
+        Estimate::new(raw - estimate_bias(raw, p), EstimationMethod::BiasCorrected)
+    } else {
+        Estimate::new(raw, EstimationMethod::Raw)
+    };
+
+    if zero > 0.0 {
+        let linear = linear_counting(mf, zero);
+        if linear <= LINEAR_COUNTING_THRESHOLDS[(p - BIAS_CORRECTION_MIN_P) as usize] {
+            return Estimate::new(linear, EstimationMethod::LinearCounting);
+        }
+    }
+
+    corrected
+}
+
+/// Ertl's improved raw estimate from the register histogram, see
+/// "New cardinality estimation algorithms for HyperLogLog sketches" (Otmar Ertl, 2017).
+fn ertl(histogram: &[u32], p: u32) -> Estimate {
+    let m = (1usize << p) as f64;
+    // registers hold values in 0..=q+1, q+1 meaning all the remaining hash bits were zero
+    let q = (64 - p) as usize;
+
+    let mut z = m * tau(1.0 - histogram[q + 1] as f64 / m);
+    for k in (1..=q).rev() {
+        z = 0.5 * (z + histogram[k] as f64);
+    }
+    z += m * sigma(histogram[0] as f64 / m);
+
+    // alpha_inf = 1 / (2 ln 2)
+    let alpha_inf = 1.0 / (2.0 * std::f64::consts::LN_2);
+    Estimate::new(alpha_inf * m * m / z, EstimationMethod::Ertl)
+}
+
+/// sigma(x) = x + sum_{k>=1} x^(2^k) 2^(k-1), correction for the empty registers
+fn sigma(x: f64) -> f64 {
+    if x == 1.0 {
+        return f64::INFINITY;
+    }
+    let mut x = x;
+    let mut y = 1.0;
+    let mut z = x;
+    loop {
+        x *= x;
+        let z_prev = z;
+        z += x * y;
+        y += y;
+        if z == z_prev {
+            return z;
+        }
+    }
+}
+
+/// tau(x) = (1 - x - sum_{k>=1} (1 - x^(2^-k))^2 2^-k) / 3, correction for the saturated registers
+fn tau(x: f64) -> f64 {
+    if x == 0.0 || x == 1.0 {
+        return 0.0;
+    }
+    let mut x = x;
+    let mut y = 1.0;
+    let mut z = 1.0 - x;
+    loop {
+        x = x.sqrt();
+        let z_prev = z;
+        y *= 0.5;
+        z -= (1.0 - x).powi(2) * y;
+        if z == z_prev {
+            return z / 3.0;
+        }
+    }
+}
+
+/// Bias of a raw estimate, averaged over the nearest neighbours in the empirical tables.
+fn estimate_bias(raw: f64, p: u32) -> f64 {
+    let row = (p - BIAS_CORRECTION_MIN_P) as usize;
+    let raw_estimates = RAW_ESTIMATE_DATA[row];
+    let biases = BIAS_DATA[row];
+
+    // raw estimates are sorted, so the neighbours form a window around the insertion point
+    let pos = raw_estimates.partition_point(|&r| r < raw);
+    let mut lo = pos;
+    let mut hi = pos;
+    while hi - lo < BIAS_NEIGHBOURS.min(raw_estimates.len()) {
+        let take_lower = match (lo.checked_sub(1), raw_estimates.get(hi)) {
+            (Some(l), Some(&h)) => raw - raw_estimates[l] <= h - raw,
+            (Some(_), None) => true,
+            (None, _) => false,
+        };
+        if take_lower {
+            lo -= 1;
+        } else {
+            hi += 1;
+        }
+    }
+
+    biases[lo..hi].iter().sum::<f64>() / (hi - lo) as f64
+}
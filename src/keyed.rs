@@ -0,0 +1,192 @@
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+
+use crate::stable::{HasherIdentity, HasherState};
+
+/// Name recorded for sketches hashed with `KeyedHasherBuilder`.
+pub const KEYED_HASH_ALGORITHM: &str = "siphash-2-4-keyed";
+
+/// `BuildHasher` for SipHash-2-4 under a secret 128-bit key, a pseudorandom function: without the
+/// key, nobody can pick items that land in a given register or get a given rank, so items chosen
+/// by an adversary count like random ones.
+///
+/// Every key carries an id, e.g. its generation in a key rotation. The id is hashed before every
+/// item, so sketches of different keys or generations get different fingerprints and are never
+/// merged or compared. The key itself is never written out: serialized sketches record its id and
+/// `key_check`, and are read back with `HyperLogLog::read_from_with_hasher` and the key.
+///
+/// Only items go through the key: hashes given to `HyperLogLog::insert_hash` bypass it, so they get
+/// no protection.
+#[derive(Clone, PartialEq, Eq)]
+pub struct KeyedHasherBuilder {
+    key_id: u32,
+    k0: u64,
+    k1: u64,
+}
+
+impl KeyedHasherBuilder {
+    pub fn new(key_id: u32, key: [u8; 16]) -> Self {
+        KeyedHasherBuilder {
+            key_id,
+            k0: u64::from_le_bytes(key[..8].try_into().unwrap()),
+            k1: u64::from_le_bytes(key[8..].try_into().unwrap()),
+        }
+    }
+
+    /// A builder with id `key_id` and a random key, drawn from the keys of fresh `RandomState`s.
+    /// The key is never recorded, so sketches built with it are read back with a clone of this builder.
+    pub fn random(key_id: u32) -> Self {
+        let mut key = [0; 16];
+        key[..8].copy_from_slice(&RandomState::new().hash_one(key_id).to_le_bytes());
+        key[8..].copy_from_slice(&RandomState::new().hash_one(key_id).to_le_bytes());
+        Self::new(key_id, key)
+    }
+
+    pub fn key_id(&self) -> u32 {
+        self.key_id
+    }
+
+    /// Hash of a fixed label under the key, which tells keys apart without revealing them.
+    pub fn key_check(&self) -> u64 {
+        let mut hasher = self.build_hasher();
+        hasher.write(b"__hyperloglog_key_check__");
+        hasher.finish()
+    }
+}
+
+impl Default for KeyedHasherBuilder {
+    /// A random key with id 0, see `random`. A fixed default key would be public and give no protection.
+    fn default() -> Self {
+        Self::random(0)
+    }
+}
+
+// the key stays out of logs
+impl fmt::Debug for KeyedHasherBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyedHasherBuilder")
+            .field("key_id", &self.key_id)
+            .field("key_check", &format_args!("{:#018x}", self.key_check()))
+            .finish()
+    }
+}
+
+impl BuildHasher for KeyedHasherBuilder {
+    type Hasher = KeyedHasher;
+
+    fn build_hasher(&self) -> KeyedHasher {
+        let mut hasher = KeyedHasher::new(self.k0, self.k1);
+        hasher.write(&self.key_id.to_le_bytes());
+        hasher
+    }
+}
+
+impl HasherIdentity for KeyedHasherBuilder {
+    fn algorithm(&self) -> &'static str {
+        KEYED_HASH_ALGORITHM
+    }
+
+    fn seed(&self) -> u64 {
+        0
+    }
+}
+
+// the key id and check are recorded, the key is not, so a keyed sketch cannot restore its hasher
+impl HasherState for KeyedHasherBuilder {
+    fn parameters(&self) -> Vec<u8> {
+        let mut parameters = self.key_id.to_le_bytes().to_vec();
+        parameters.extend_from_slice(&self.key_check().to_le_bytes());
+        parameters
+    }
+
+    fn restore(_seed: u64, _parameters: &[u8]) -> Option<Self> {
+        None
+    }
+}
+
+/// Hasher built by `KeyedHasherBuilder`: streaming SipHash-2-4 over the key id and the written bytes.
+#[derive(Clone)]
+pub struct KeyedHasher {
+    v0: u64,
+    v1: u64,
+    v2: u64,
+    v3: u64,
+    tail: u64, // bytes of the unfinished word, little-endian
+    ntail: usize, // number of bytes in `tail`
+    length: usize, // total number of bytes written
+}
+
+impl KeyedHasher {
+    fn new(k0: u64, k1: u64) -> Self {
+        KeyedHasher {
+            v0: k0 ^ 0x736f_6d65_7073_6575,
+            v1: k1 ^ 0x646f_7261_6e64_6f6d,
+            v2: k0 ^ 0x6c79_6765_6e65_7261,
+            v3: k1 ^ 0x7465_6462_7974_6573,
+            tail: 0,
+            ntail: 0,
+            length: 0,
+        }
+    }
+
+    fn round(&mut self) {
+        self.v0 = self.v0.wrapping_add(self.v1);
+        self.v1 = self.v1.rotate_left(13) ^ self.v0;
+        self.v0 = self.v0.rotate_left(32);
+        self.v2 = self.v2.wrapping_add(self.v3);
+        self.v3 = self.v3.rotate_left(16) ^ self.v2;
+        self.v0 = self.v0.wrapping_add(self.v3);
+        self.v3 = self.v3.rotate_left(21) ^ self.v0;
+        self.v2 = self.v2.wrapping_add(self.v1);
+        self.v1 = self.v1.rotate_left(17) ^ self.v2;
+        self.v2 = self.v2.rotate_left(32);
+    }
+
+    fn compress(&mut self, word: u64) {
+        self.v3 ^= word;
+        self.round();
+        self.round();
+        self.v0 ^= word;
+    }
+}
+
+impl Hasher for KeyedHasher {
+    fn write(&mut self, mut bytes: &[u8]) {
+        self.length += bytes.len();
+
+        if self.ntail > 0 {
+            while self.ntail < 8 && !bytes.is_empty() {
+                self.tail |= (bytes[0] as u64) << (8 * self.ntail);
+                self.ntail += 1;
+                bytes = &bytes[1..];
+            }
+            if self.ntail < 8 {
+                return;
+            }
+            let word = self.tail;
+            self.compress(word);
+            self.tail = 0;
+            self.ntail = 0;
+        }
+
+        let mut words = bytes.chunks_exact(8);
+        for word in &mut words {
+            self.compress(u64::from_le_bytes(word.try_into().unwrap()));
+        }
+        for (i, &byte) in words.remainder().iter().enumerate() {
+            self.tail |= (byte as u64) << (8 * i);
+        }
+        self.ntail = words.remainder().len();
+    }
+
+    fn finish(&self) -> u64 {
+        let mut state = self.clone();
+        state.compress(((self.length as u64) << 56) | self.tail);
+        state.v2 ^= 0xff;
+        for _ in 0..4 {
+            state.round();
+        }
+        state.v0 ^ state.v1 ^ state.v2 ^ state.v3
+    }
+}
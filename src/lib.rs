@@ -1,201 +1,900 @@
 pub mod tobytes;
 mod error;
-use error::HyperLogLogError;
-pub use tobytes::ToBytes;
+mod sparse;
+mod estimator;
+mod bias_tables;
+mod registers;
+mod setops;
+mod binary;
+mod redis;
+mod datasketches;
+mod postgres;
+mod zetasketch;
+mod concurrent;
+mod buffer;
+mod stable;
+mod keyed;
+pub use error::HyperLogLogError;
+pub use tobytes::{CanonicalF32, CanonicalF64, ToBytes, ENCODING_VERSION};
+use sparse::{SparseRegisters, SPARSE_PRECISION};
+pub use estimator::{ConfidenceInterval, Estimate, EstimationMethod, Estimator};
+pub use registers::RegisterBackend;
+use registers::DenseRegisters;
+pub use setops::{JointEstimate, SetEstimate, SetEstimator};
+pub use binary::FORMAT_VERSION;
+pub use redis::{RedisHasher, RedisHasherBuilder, REDIS_PRECISION};
+pub use datasketches::{DataSketchesHasher, DataSketchesHasherBuilder, DataSketchesHllType};
+pub use postgres::{PostgresHllHasher, PostgresHllHasherBuilder, PostgresHllSettings};
+pub use zetasketch::{ZetaSketchHasher, ZetaSketchHasherBuilder};
+pub use concurrent::ConcurrentHyperLogLog;
+pub use stable::{HasherIdentity, HasherState, StableHasher, StableHasherBuilder, DEFAULT_HASH_SEED, STABLE_HASH_ALGORITHM};
+pub use keyed::{KeyedHasher, KeyedHasherBuilder, KEYED_HASH_ALGORITHM};
 
-use std::{hash::{BuildHasher, BuildHasherDefault, DefaultHasher, Hasher}, marker::PhantomData};
+use std::{borrow::{Borrow, Cow}, hash::{BuildHasher, Hasher}, io::{Read, Write}, marker::PhantomData, sync::Mutex, thread};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde::de::Error as DeError;
 
+/// Largest supported precision, `2^25` buckets: the precision of the sparse representation,
+/// which converts to any dense precision up to it.
+pub const MAX_PRECISION: u32 = SPARSE_PRECISION;
+
+// items each thread of `insert_parallel_iter` takes from the iterator at once
+const PARALLEL_BATCH_LEN: usize = 4096;
+
 /// HyperLogLog is a probabilistic data structure for estimating cardinality.
 /// This implementation uses the HyperLogLog algorithm to estimate the
 /// number of distinct elements in a large stream of data, using `p` bits (which determines the number of buckets).
+///
+/// A new sketch starts in a sparse representation and promotes itself to the dense
+/// `2^p` bucket layout once the sparse form would take more memory than the dense one.
+/// The dense buckets use the `RegisterBackend` picked with `with_backend`.
 #[derive(Clone)]
-pub struct HyperLogLog<T: ToBytes, S = BuildHasherDefault<DefaultHasher>> {
+pub struct HyperLogLog<T: ToBytes, S = StableHasherBuilder> {
     p: u32, // number of bits
     m: usize, // size of buckets
-    buckets: Vec<u8>, // vectors to store the bucket
+    buckets: Buckets, // sparse or dense bucket storage
     hasher_builder: S, // hasher to use
+    backend: RegisterBackend, // layout of the dense buckets
+    estimator: Estimator, // algorithm used by calculate_cardinality
+    prehashed: Option<u64>, // id of the hash function given to insert_hash, part of the fingerprint
     // Marker to associate the generic type `T` without storing a value of it.
     // Ensures the type system correctly tracks ownership and variance of `T`.
     _marker: PhantomData<T>,
 }
 
+/// Storage for the buckets of a HyperLogLog.
+#[derive(Clone)]
+enum Buckets {
+    /// (index, rank) pairs at `SPARSE_PRECISION`, used while the sketch is small
+    Sparse(SparseRegisters),
+    /// one register per bucket
+    Dense(DenseRegisters),
+}
+
+/// How `merge_with` treats sketches of different precisions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MergeMode {
+    /// Fail with `MisMatchedPrecision` unless both sketches have the same precision, as `merge` does.
+    #[default]
+    SamePrecision,
+    /// Fold the sketch with the higher precision down to the lower one before merging.
+    FoldToLowerPrecision,
+}
+
+/// The representation a HyperLogLog is currently using for its buckets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Representation {
+    Sparse,
+    /// dense `u8` registers
+    Dense,
+    /// dense registers packed on 6 bits
+    Packed6,
+    /// dense 4-bit registers with a shared offset and an exception table
+    Hll4,
+}
+
 /// Struct for serializing HyperLogLog
 #[derive(Serialize, Deserialize)]
 struct HyperLogLogSerializable {
     p: u32, // p bits
     m: usize, // size of the buckets
-    buckets: Vec<u8>, // vector to store the buckets
-    fingerprint: u64 // finger value to make sure that when value is saved and loaded it has the same configuration
+    buckets: Vec<u8>, // vector to store the buckets, empty while the sketch is sparse
+    fingerprint: u64, // finger value to make sure that when value is saved and loaded it has the same configuration
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    hash_algorithm: Option<String>, // name of the hash function, absent in sketches saved before it was recorded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    hash_seed: Option<u64>, // seed of the hash function, recorded with the name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    hash_parameters: Option<Vec<u8>>, // further hasher parameters, see HasherState, only present when there are some
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sparse: Option<Vec<u32>>, // encoded sparse entries, only present for sparse sketches
+    #[serde(default, skip_serializing_if = "is_default_backend")]
+    backend: RegisterBackend, // layout of the dense buckets, buckets are always written one value per bucket
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    prehashed: Option<u64>, // id of the hash function of a sketch fed with raw hashes
+}
+
+fn is_default_backend(backend: &RegisterBackend) -> bool {
+    *backend == RegisterBackend::default()
 }
 
 // implementing serialize for HyperLogLog only if T and S meet the criteria of T being ToBytes and S being iether BuildHasher or Default
-impl<T: ToBytes, S: BuildHasher + Default> Serialize for HyperLogLog<T, S> {
+impl<T: ToBytes, S: BuildHasher + Default + HasherState> Serialize for HyperLogLog<T, S> {
     fn serialize<Ser>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error>
     where
         Ser: Serializer,
     {
         // generating a fingerprint
         // This is so that if the state is saved and then reloaded we can ensure the same hashing function is used to maintain consistence
-        let mut hasher = self.hasher_builder.build_hasher();
+        let fingerprint = fingerprint::<T, S>(&self.hasher_builder, self.prehashed);
 
-        hasher.write(b"__hyperloglog_fingerprint__");
-        hasher.write(T::TYPE_ID);
-        let fingerprint = hasher.finish();
+        let (buckets, sparse) = match &self.buckets {
+            Buckets::Sparse(s) => (Vec::new(), Some(s.entries().to_vec())),
+            Buckets::Dense(b) => (b.to_vec(), None),
+        };
 
         // generating serializable structure
         let data = HyperLogLogSerializable {
             p: self.p,
             m: self.m,
-            buckets: self.buckets.clone(),
-            fingerprint: fingerprint
+            buckets,
+            fingerprint,
+            hash_algorithm: Some(self.hasher_builder.algorithm().to_string()),
+            hash_seed: Some(self.hasher_builder.seed()),
+            hash_parameters: Some(self.hasher_builder.parameters()).filter(|parameters| !parameters.is_empty()),
+            sparse,
+            backend: self.backend,
+            prehashed: self.prehashed,
         };
 
-        
         data.serialize(serializer)
     }
 }
 
-impl<'de, T: ToBytes, S: BuildHasher + Default> Deserialize<'de> for HyperLogLog<T, S> {
+impl<'de, T: ToBytes, S: BuildHasher + Default + HasherState> Deserialize<'de> for HyperLogLog<T, S> {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: Deserializer<'de>,
     {
         let data = HyperLogLogSerializable::deserialize(deserializer)?;
 
-        // Recompute fingerprint using S::default()
-        let mut hasher = S::default().build_hasher();
-        hasher.write(b"__hyperloglog_fingerprint__");
-        hasher.write(T::TYPE_ID);
-        let expected_fingerprint = hasher.finish();
+        let hasher_builder: S = restore_hasher(
+            data.hash_algorithm.as_deref(),
+            data.hash_seed.unwrap_or(0),
+            data.hash_parameters.as_deref().unwrap_or_default(),
+        )
+        .map_err(D::Error::custom)?;
+
+        Self::from_serializable(data, hasher_builder)
+    }
+}
 
-        if expected_fingerprint != data.fingerprint {
-            return Err(D::Error::custom("Hasher mismatch: incompatible hasher or datatype used during deserialization"));
+impl<T: ToBytes, S: BuildHasher> HyperLogLog<T, S> {
+    /// Builds a sketch from its serde form once its hasher is known.
+    fn from_serializable<E: DeError>(data: HyperLogLogSerializable, hasher_builder: S) -> Result<Self, E> {
+        // Recompute fingerprint using the restored hasher, the sketch may have been fed with raw hashes
+        if data.fingerprint != fingerprint::<T, S>(&hasher_builder, data.prehashed) {
+            return Err(E::custom("Hasher mismatch: incompatible hasher or datatype used during deserialization"));
         }
 
+        // the payload may come from anywhere, check every invariant before trusting it
+        let m = bucket_count(data.p).map_err(E::custom)?;
+        if data.m != m {
+            return Err(E::custom(HyperLogLogError::InconsistentBucketCount(data.p, data.m)));
+        }
+
+        let buckets = match data.sparse {
+            Some(entries) => {
+                if data.p >= SPARSE_PRECISION {
+                    return Err(E::custom(HyperLogLogError::SparseNotAllowed(data.p)));
+                }
+                if !data.buckets.is_empty() {
+                    return Err(E::custom(HyperLogLogError::BucketCountMismatch(0, data.buckets.len())));
+                }
+                Buckets::Sparse(SparseRegisters::from_entries(entries).map_err(E::custom)?)
+            }
+            None => {
+                check_registers(data.p, &data.buckets).map_err(E::custom)?;
+                Buckets::Dense(DenseRegisters::from_values(data.backend, data.buckets))
+            }
+        };
+
         Ok(Self {
             p: data.p,
             m: data.m,
-            buckets: data.buckets,
-            hasher_builder: S::default(),
+            buckets,
+            hasher_builder,
+            backend: data.backend,
+            estimator: Estimator::default(),
+            prehashed: data.prehashed,
             _marker: PhantomData,
         })
     }
 }
 
 
-impl<T: ToBytes> HyperLogLog<T, BuildHasherDefault<DefaultHasher>> {
-    /// Default constructor using the standard RandomState hasher.
+impl<T: ToBytes> HyperLogLog<T, StableHasherBuilder> {
+    /// Default constructor using `StableHasherBuilder`, XXH3 with `DEFAULT_HASH_SEED`.
     pub fn new(p: u32) -> Result<Self, HyperLogLogError> {
         Self::with_hasher(p, Default::default())
     }
+
+    /// Creates a `HyperLogLog` with the smallest precision whose relative standard error is at most `error`,
+    /// e.g. `HyperLogLog::with_error(0.01)` picks `p = 14`.
+    pub fn with_error(error: f64) -> Result<Self, HyperLogLogError> {
+        Self::with_error_and_hasher(error, Default::default())
+    }
+}
+
+impl<T: ToBytes> HyperLogLog<T, KeyedHasherBuilder> {
+    /// Creates a sketch hashing items with SipHash-2-4 under a secret key, for items chosen by
+    /// someone who might try to skew the count, see `KeyedHasherBuilder`.
+    /// `insert_hash` bypasses the key, so keyed sketches should only be fed through `insert`.
+    pub fn with_key(p: u32, key_id: u32, key: [u8; 16]) -> Result<Self, HyperLogLogError> {
+        Self::with_hasher(p, KeyedHasherBuilder::new(key_id, key))
+    }
 }
 
 impl<T: ToBytes, S: BuildHasher + Default + Clone> HyperLogLog<T, S> {
     
     /// Creates a new `HyperLogLog` with `p` bits.
-    /// Panics if `p < 4` or if `p` is too large to shift safely.
+    /// Fails if `p < 4` or if `p > MAX_PRECISION`.
     pub fn with_hasher(p: u32, hasher_builder: S) -> Result<Self, HyperLogLogError> {
         
-        if p < 4 {
-            return Err(HyperLogLogError::PrecisionBelowThreshold);
+        let m = bucket_count(p)?;
+        let backend = RegisterBackend::default();
+        let buckets = Self::empty_buckets(p, m, backend);
+
+        Ok(HyperLogLog { p, m, buckets, hasher_builder, backend, estimator: Estimator::default(), prehashed: None, _marker: PhantomData })
+    }
+
+    /// Creates a dense `HyperLogLog` with `p` bits from existing bucket values.
+    /// Fails if there are not exactly `2^p` buckets or if a value exceeds the largest rank, `64 - p + 1`.
+    pub fn from_buckets(p: u32, buckets: Vec<u8>, hasher_builder: S) -> Result<Self, HyperLogLogError> {
+        let mut hll = Self::with_hasher(p, hasher_builder)?;
+
+        check_registers(p, &buckets)?;
+
+        hll.buckets = Buckets::Dense(DenseRegisters::from_values(hll.backend, buckets));
+        Ok(hll)
+    }
+
+    /// Same as `with_error` but with a custom hasher.
+    pub fn with_error_and_hasher(error: f64, hasher_builder: S) -> Result<Self, HyperLogLogError> {
+        if !(error > 0.0 && error < 1.0) {
+            return Err(HyperLogLogError::InvalidRelativeError(error));
         }
-        // Compute m = 2^p, panic if overflow
-        let m = match 1usize.checked_shl(p) {
-            Some(m) => m,
-            None => return Err(HyperLogLogError::PrecisionTooLarge),
-        };
-        // Initialize buckets to zero
-        // u8 because the maximum number of leading zeros we can have
-        // is if the hash equals to 0, so 2^8, 256 (technicall xxh3_64 generates a 64 bit hash)
-        // which means max leading zeros is 64 but the smallest data type rust handles is u8
-        let buckets = vec![0u8; m];
+        Self::with_hasher(estimator::precision_for_error(error), hasher_builder)
+    }
 
+    /// Selects the layout of the dense buckets, converting them if the sketch is already dense.
+    pub fn with_backend(mut self, backend: RegisterBackend) -> Self {
+        if let Buckets::Dense(registers) = &self.buckets {
+            self.buckets = Buckets::Dense(registers.convert(backend));
+        }
+        self.backend = backend;
+        self
+    }
+
+    pub fn get_backend(&self) -> RegisterBackend {
+        self.backend
+    }
 
-        Ok(HyperLogLog { p, m, buckets, hasher_builder, _marker: PhantomData })
+    /// Empty storage for a sketch with `p` bits, sparse whenever the sparse precision is higher.
+    fn empty_buckets(p: u32, m: usize, backend: RegisterBackend) -> Buckets {
+        if p < SPARSE_PRECISION {
+            Buckets::Sparse(SparseRegisters::new())
+        } else {
+            // Initialize buckets to zero
+            Buckets::Dense(DenseRegisters::new(backend, m))
+        }
     }
 
     /// Generates hashes.
-    fn hash_input(&mut self, item: T) -> u64 {
+    fn hash_input<Q: ToBytes + ?Sized>(&self, item: &Q) -> u64 {
+        debug_assert!(self.prehashed.is_none(), "items cannot be inserted into a sketch of raw hashes");
         let mut hasher = self.hasher_builder.build_hasher();
-        hasher.write(&item.to_bytes());
+        item.hash_into(&mut hasher);
         hasher.finish()
     }
 
     /// Inserts an element into the HyperLogLog structure.
     pub fn insert(&mut self, item: T) {
+        self.insert_ref(&item);
+    }
+
+    /// Inserts an element without taking ownership of it, e.g. a `&str` into a `HyperLogLog<String>`
+    /// or a `&u64` into a `HyperLogLog<u64>`. `Q` must write the same bytes as the `T` it borrows from,
+    /// as `str` and `String` do. Once the sketch is dense, this does not allocate with hashers
+    /// that stream their input, such as the default one.
+    pub fn insert_ref<Q: ToBytes + ?Sized>(&mut self, item: &Q)
+    where
+        T: Borrow<Q>,
+    {
         let hash = self.hash_input(item);
-        // Bucket index: top `p` bits
-        let idx = (hash >> (64 - self.p)) as usize;
-        // Remaining bits for leading zero count
-        let w = hash << self.p;
-        // Count leading zeros (cap at 64), then +1
-        let leading = (w.leading_zeros() + 1).min(64) as u8;
-        // Update the bucket with the max leading count
-        self.buckets[idx] = self.buckets[idx].max(leading);
+        self.insert_hashed(hash);
+    }
+
+    /// Inserts a 64-bit hash computed outside of the sketch, e.g. upstream in a stream processor,
+    /// skipping the hasher. The register index comes from the top `p` bits and the rank from the
+    /// leading zeros of the rest, so the hash must be uniformly distributed over all 64 bits.
+    ///
+    /// `hash_id` names the upstream hash function, e.g. a hash of its name and seed. The first hash
+    /// marks the sketch as prehashed with `hash_id`, which becomes part of its fingerprint: it can
+    /// then only be merged with or compared to sketches of the same `T` and `S` fed with hashes of
+    /// the same `hash_id`, never with sketches of hashed items. Items cannot be inserted any more,
+    /// which debug builds assert.
+    ///
+    /// Fails with `NotPrehashed` if the sketch already holds hashed items, and with
+    /// `PrehashedIdMismatch` if it holds hashes of another `hash_id`.
+    /// The hasher is skipped entirely, including the secret key of a `KeyedHasherBuilder`.
+    pub fn insert_hash(&mut self, hash_id: u64, hash: u64) -> Result<(), HyperLogLogError> {
+        self.mark_prehashed(hash_id)?;
+        self.insert_hashed(hash);
+        Ok(())
+    }
+
+    /// Inserts many precomputed hashes of the hash function `hash_id`, see `insert_hash`.
+    /// No hashes leave the sketch as it was, not even marked as prehashed.
+    pub fn insert_hashes<I: IntoIterator<Item = u64>>(&mut self, hash_id: u64, hashes: I) -> Result<(), HyperLogLogError> {
+        let mut hashes = hashes.into_iter().peekable();
+        if hashes.peek().is_none() {
+            return Ok(());
+        }
+        self.mark_prehashed(hash_id)?;
+        for hash in hashes {
+            self.insert_hashed(hash);
+        }
+        Ok(())
+    }
+
+    /// Whether the sketch was fed with raw hashes through `insert_hash`.
+    pub fn is_prehashed(&self) -> bool {
+        self.prehashed.is_some()
+    }
+
+    /// The id of the hash function the sketch was fed with through `insert_hash`, if any.
+    pub fn prehashed_id(&self) -> Option<u64> {
+        self.prehashed
+    }
+
+    /// Marks the sketch as fed with raw hashes of `hash_id`, unless it holds anything else.
+    fn mark_prehashed(&mut self, hash_id: u64) -> Result<(), HyperLogLogError> {
+        match self.prehashed {
+            Some(id) if id == hash_id => Ok(()),
+            Some(id) => Err(HyperLogLogError::PrehashedIdMismatch(id, hash_id)),
+            None if self.is_empty() => {
+                self.prehashed = Some(hash_id);
+                Ok(())
+            }
+            None => Err(HyperLogLogError::NotPrehashed),
+        }
+    }
+
+    /// Whether every register is still zero.
+    fn is_empty(&self) -> bool {
+        match &self.buckets {
+            Buckets::Sparse(sparse) => sparse.is_empty(),
+            Buckets::Dense(buckets) => (0..buckets.len()).all(|idx| buckets.get(idx) == 0),
+        }
     }
 
-    /// Calculates the cardinality estimate.
+    /// Records a hash produced by `hash_input` or given to `insert_hash`.
+    fn insert_hashed(&mut self, hash: u64) {
+        match &mut self.buckets {
+            Buckets::Dense(buckets) => {
+                let (idx, leading) = index_and_rank(hash, self.p);
+                // Update the bucket with the max leading count
+                buckets.update(idx, leading);
+            }
+            Buckets::Sparse(sparse) => {
+                let (idx, leading) = index_and_rank(hash, SPARSE_PRECISION);
+                sparse.update(idx as u32, leading, sparse_budget(self.backend, self.m));
+                self.promote_if_needed();
+            }
+        }
+    }
+
+    /// Inserts `items` from `threads` threads. Each thread fills a private sketch from its share of
+    /// the slice, then the private sketches are merged pairwise in parallel and the result into `self`.
+    /// `threads` is at least 1; `std::thread::available_parallelism` is a good default.
+    pub fn insert_parallel(&mut self, items: &[T], threads: usize)
+    where
+        T: Send + Sync,
+        S: Send + Sync,
+    {
+        if items.is_empty() {
+            return;
+        }
+        let chunk_len = items.len().div_ceil(threads.max(1));
+        let this = &*self;
+        let partials: Vec<Self> = thread::scope(|scope| {
+            let handles: Vec<_> = items.chunks(chunk_len).map(|chunk| {
+                scope.spawn(move || {
+                    let mut partial = this.empty_like();
+                    for item in chunk {
+                        partial.insert_hashed(partial.hash_input(item));
+                    }
+                    partial
+                })
+            }).collect();
+            handles.into_iter().map(|handle| handle.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic))).collect()
+        });
+        self.merge_partials(partials);
+    }
+
+    /// Inserts the items of an iterator from `threads` threads, for items that are not in a slice,
+    /// e.g. read from a file. Each thread takes batches of a few thousand items from the shared
+    /// iterator into a private sketch, then those are merged as in `insert_parallel`.
+    /// Only pays off when hashing costs more than taking the lock of the iterator once per batch.
+    pub fn insert_parallel_iter<I>(&mut self, items: I, threads: usize)
+    where
+        I: IntoIterator<Item = T>,
+        I::IntoIter: Send,
+        T: Send + Sync,
+        S: Send + Sync,
+    {
+        let items = Mutex::new(items.into_iter());
+        let (items, this) = (&items, &*self);
+        let partials: Vec<Self> = thread::scope(|scope| {
+            let handles: Vec<_> = (0..threads.max(1)).map(|_| {
+                scope.spawn(move || {
+                    let mut partial = this.empty_like();
+                    let mut batch = Vec::with_capacity(PARALLEL_BATCH_LEN);
+                    loop {
+                        batch.extend(items.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).by_ref().take(PARALLEL_BATCH_LEN));
+                        if batch.is_empty() {
+                            return partial;
+                        }
+                        for item in batch.drain(..) {
+                            partial.insert_hashed(partial.hash_input(&item));
+                        }
+                    }
+                })
+            }).collect();
+            handles.into_iter().map(|handle| handle.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic))).collect()
+        });
+        self.merge_partials(partials);
+    }
+
+    /// Merges the private sketches of `insert_parallel` into `self`.
+    fn merge_partials(&mut self, partials: Vec<Self>)
+    where
+        T: Send,
+        S: Send,
+    {
+        let merged = Self::merge_pairwise(partials).expect("partial sketches share the precision of self");
+        if let Some(merged) = merged {
+            self.merge(&merged).expect("partial sketches share the precision of self");
+        }
+    }
+
+    /// Unions `sketches` from `threads` threads. Each thread merges its share of the sketches into
+    /// one, then those are merged pairwise in parallel. Returns `None` for an empty `sketches`.
+    /// `threads` is at least 1; `std::thread::available_parallelism` is a good default.
+    /// Fails if the sketches do not all have the same precision.
+    pub fn merge_parallel(sketches: Vec<Self>, threads: usize) -> Result<Option<Self>, HyperLogLogError>
+    where
+        T: Send,
+        S: Send,
+    {
+        let chunk_len = sketches.len().div_ceil(threads.max(1)).max(1);
+        let mut sketches = sketches.into_iter();
+        let partials = thread::scope(|scope| {
+            let mut handles = Vec::new();
+            loop {
+                let chunk: Vec<Self> = sketches.by_ref().take(chunk_len).collect();
+                if chunk.is_empty() {
+                    break;
+                }
+                handles.push(scope.spawn(move || Self::merge_all(chunk)));
+            }
+            handles.into_iter().map(|handle| handle.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic))).collect::<Result<Vec<_>, HyperLogLogError>>()
+        })?;
+        Self::merge_pairwise(partials.into_iter().flatten().collect())
+    }
+
+    /// Merges `sketches` in rounds, each merging pairs of sketches on threads of their own, so that
+    /// `n` sketches take about `log2(n)` merges in a row rather than `n`.
+    fn merge_pairwise(mut sketches: Vec<Self>) -> Result<Option<Self>, HyperLogLogError>
+    where
+        T: Send,
+        S: Send,
+    {
+        while sketches.len() > 1 {
+            let mut pairs = sketches.into_iter();
+            sketches = thread::scope(|scope| {
+                let mut handles = Vec::new();
+                while let Some(mut first) = pairs.next() {
+                    let second = pairs.next();
+                    handles.push(scope.spawn(move || {
+                        if let Some(second) = second {
+                            first.merge(&second)?;
+                        }
+                        Ok(first)
+                    }));
+                }
+                handles.into_iter().map(|handle| handle.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic))).collect::<Result<Vec<_>, HyperLogLogError>>()
+            })?;
+        }
+        Ok(sketches.pop())
+    }
+
+    /// Merges `sketches` one after the other into the first of them.
+    fn merge_all(sketches: impl IntoIterator<Item = Self>) -> Result<Option<Self>, HyperLogLogError> {
+        let mut sketches = sketches.into_iter();
+        let Some(mut merged) = sketches.next() else {
+            return Ok(None);
+        };
+        for sketch in sketches {
+            merged.merge(&sketch)?;
+        }
+        Ok(Some(merged))
+    }
+
+    /// An empty sketch with the precision, hasher, backend and estimator of `self`.
+    fn empty_like(&self) -> Self {
+        HyperLogLog {
+            p: self.p,
+            m: self.m,
+            buckets: Self::empty_buckets(self.p, self.m, self.backend),
+            hasher_builder: self.hasher_builder.clone(),
+            backend: self.backend,
+            estimator: self.estimator,
+            prehashed: self.prehashed,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Converts sparse storage to dense once it could take more memory than the dense buckets would.
+    fn promote_if_needed(&mut self) {
+        if let Buckets::Sparse(sparse) = &self.buckets
+            && !sparse.fits(sparse_budget(self.backend, self.m))
+        {
+            self.buckets = Buckets::Dense(sparse.to_registers(self.p, self.backend));
+        }
+    }
+
+    /// Counts how many buckets hold each register value, `histogram[k]` being the number of buckets equal to `k`.
+    fn register_histogram(&self) -> [u32; 65] {
+        let mut histogram = [0u32; 65];
+
+        match &self.buckets {
+            Buckets::Dense(buckets) => buckets.fill_histogram(&mut histogram),
+            Buckets::Sparse(sparse) => {
+                // entries mapping to the same dense bucket are adjacent, keep the max of each run
+                let mut current: Option<(usize, u8)> = None;
+                let mut filled = 0;
+                for (idx, rank) in sparse.dense_entries(self.p) {
+                    match current {
+                        Some((cur_idx, cur_rank)) if cur_idx == idx => current = Some((idx, cur_rank.max(rank))),
+                        _ => {
+                            if let Some((_, cur_rank)) = current {
+                                histogram[cur_rank as usize] += 1;
+                                filled += 1;
+                            }
+                            current = Some((idx, rank));
+                        }
+                    }
+                }
+                if let Some((_, cur_rank)) = current {
+                    histogram[cur_rank as usize] += 1;
+                    filled += 1;
+                }
+                histogram[0] = (self.m - filled) as u32;
+            }
+        }
+
+        histogram
+    }
+
+    /// Calculates the cardinality estimate using the configured estimator.
     pub fn calculate_cardinality(&self) -> u64 {
-        let m = self.m as f64;
-        // Harmonic mean of 2^{-bucket_value}
-        let sum: f64 = self.buckets.iter()
-            .map(|&v| 2f64.powi(-(v as i32)))
-            .sum();
-        // Count zero buckets
-        let zero = self.buckets.iter().filter(|&&v| v == 0).count() as f64;
-
-        // Empty set
-        if zero == m {
-            return 0;
-        }
-
-        let z = 1.0 / sum;
-        // Empirical alpha factor
-        let alpha = match self.m {
-            16 => 0.673,
-            32 => 0.697,
-            64 => 0.709,
-            mm if mm >= 128 => 0.7213 / (1.0 + 1.079 / (mm as f64)),
-            _ => unreachable!("Bucket count m={} is unsupported", self.m),
+        self.calculate_cardinality_with(self.estimator)
+    }
+
+    /// Calculates the cardinality estimate using `estimator`, regardless of the configured one.
+    pub fn calculate_cardinality_with(&self, estimator: Estimator) -> u64 {
+        self.estimate_with(estimator).rounded()
+    }
+
+    /// Estimates the cardinality with the configured estimator, reporting which method produced the value.
+    pub fn estimate(&self) -> Estimate {
+        self.estimate_with(self.estimator)
+    }
+
+    /// Estimates the cardinality with `estimator`, reporting which method produced the value.
+    pub fn estimate_with(&self, estimator: Estimator) -> Estimate {
+        estimator::estimate(estimator, &self.register_histogram(), self.p)
+    }
+
+    /// Selects the estimator used by `calculate_cardinality`.
+    pub fn with_estimator(mut self, estimator: Estimator) -> Self {
+        self.estimator = estimator;
+        self
+    }
+
+    /// Changes the estimator used by `calculate_cardinality`.
+    pub fn set_estimator(&mut self, estimator: Estimator) {
+        self.estimator = estimator;
+    }
+
+    pub fn get_estimator(&self) -> Estimator {
+        self.estimator
+    }
+
+    /// Estimates the cardinality with the configured estimator, together with the theoretical
+    /// relative standard error and the bounds of a `confidence` interval, e.g. `0.95`.
+    pub fn estimate_with_confidence(&self, confidence: f64) -> Result<ConfidenceInterval, HyperLogLogError> {
+        if !(confidence > 0.0 && confidence < 1.0) {
+            return Err(HyperLogLogError::InvalidConfidence(confidence));
+        }
+        Ok(ConfidenceInterval::new(self.estimate(), self.relative_standard_error(), confidence))
+    }
+
+    /// Theoretical relative standard error of the estimates, `1.04 / sqrt(m)`.
+    pub fn relative_standard_error(&self) -> f64 {
+        estimator::relative_standard_error(self.p)
+    }
+
+    /// Memory taken by the dense `u8` buckets of a sketch with `p` bits, in bytes.
+    /// Fails for the precisions `with_hasher` refuses.
+    pub fn memory_cost(p: u32) -> Result<usize, HyperLogLogError> {
+        Self::memory_cost_with_backend(p, RegisterBackend::U8)
+    }
+
+    /// Memory taken by the dense buckets of a sketch with `p` bits using `backend`, in bytes.
+    /// Fails for the precisions `with_hasher` refuses.
+    pub fn memory_cost_with_backend(p: u32, backend: RegisterBackend) -> Result<usize, HyperLogLogError> {
+        backend.size_in_bytes(bucket_count(p)?)
+    }
+
+    /// Memory currently taken by the buckets, in bytes. At most `memory_cost` while the sketch is sparse.
+    pub fn memory_usage(&self) -> usize {
+        match &self.buckets {
+            Buckets::Sparse(sparse) => sparse.size_in_bytes(),
+            Buckets::Dense(buckets) => buckets.size_in_bytes(),
+        }
+    }
+
+    /// Folds the sketch down to `new_p` bits, giving the sketch the same items would have built at that precision.
+    /// Each register moves to the index made of its top `new_p` index bits, and its rank is recomputed
+    /// from the dropped index bits, which come first in the rank. Sparse sketches stay sparse.
+    /// Fails if `new_p` is larger than `p` or below 4.
+    pub fn reduce_precision(&self, new_p: u32) -> Result<Self, HyperLogLogError> {
+        if new_p > self.p {
+            return Err(HyperLogLogError::CannotIncreasePrecision(self.p, new_p));
+        }
+        let mut reduced = Self::with_hasher(new_p, self.hasher_builder.clone())?
+            .with_backend(self.backend)
+            .with_estimator(self.estimator);
+        reduced.prehashed = self.prehashed;
+
+        reduced.buckets = match &self.buckets {
+            // sparse entries keep the full sparse index, whatever the dense precision
+            Buckets::Sparse(sparse) => Buckets::Sparse(sparse.clone()),
+            Buckets::Dense(buckets) => {
+                let values = fold_registers(&buckets.to_vec(), self.p, new_p);
+                Buckets::Dense(DenseRegisters::from_values(self.backend, values))
+            }
         };
-        let mut estimate = alpha * m * m * z;
+        reduced.promote_if_needed();
+        Ok(reduced)
+    }
+
+    /// Unions `other` into `self`. Both sketches must have the same precision.
+    pub fn merge(&mut self, other: &Self) -> Result<(), HyperLogLogError> {
+        self.merge_with(other, MergeMode::SamePrecision)
+    }
 
-        // Small-range (linear counting) correction: m * ln(m / V)
-        if zero > 0.0 {
-            let linear = m * (m / zero).ln();
-            if linear <= 2.5 * m {
-                return linear.round() as u64;
+    /// Unions `other` into `self`, with `mode` deciding what happens when the precisions differ.
+    /// With `MergeMode::FoldToLowerPrecision` the sketch with more bits is folded down with
+    /// `reduce_precision` first, and `self` ends up with the lower of both precisions.
+    pub fn merge_with(&mut self, other: &Self, mode: MergeMode) -> Result<(), HyperLogLogError> {
+        if self.fingerprint() != other.fingerprint() {
+            return Err(HyperLogLogError::HasherMismatch);
+        }
+        if self.p != other.p {
+            match mode {
+                MergeMode::SamePrecision => return Err(HyperLogLogError::MisMatchedPrecision(self.p, other.p)),
+                MergeMode::FoldToLowerPrecision if self.p > other.p => *self = self.reduce_precision(other.p)?,
+                MergeMode::FoldToLowerPrecision => return self.merge_buckets(&other.reduce_precision(self.p)?),
             }
         }
+        self.merge_buckets(other)
+    }
 
-        // overflow correction
-        if estimate > (1.0/30.0) * (2.0f64.powi(32)) {
-            estimate = -2.0f64.powi(32) * (1.0 - (estimate / 2.0f64.powi(32))).ln();
+    fn merge_buckets(&mut self, other: &Self) -> Result<(), HyperLogLogError> {
+        match (&mut self.buckets, &other.buckets) {
+            (Buckets::Sparse(sparse), Buckets::Sparse(other_sparse)) => {
+                sparse.merge(other_sparse);
+                self.promote_if_needed();
+            }
+            (Buckets::Sparse(sparse), Buckets::Dense(other_buckets)) => {
+                let mut buckets = sparse.to_registers(self.p, self.backend);
+                buckets.merge(other_buckets);
+                self.buckets = Buckets::Dense(buckets);
+            }
+            (Buckets::Dense(buckets), Buckets::Sparse(other_sparse)) => {
+                for (idx, rank) in other_sparse.dense_entries(self.p) {
+                    buckets.update(idx, rank);
+                }
+            }
+            (Buckets::Dense(buckets), Buckets::Dense(other_buckets)) => {
+                // iterating over the bucket and getting the max value
+                buckets.merge(other_buckets);
+            }
         }
-        
-        estimate.round() as u64
+
+        Ok(())
+    }
+
+    /// Estimates `|A \ B|`, `|B \ A|`, `|A ∩ B|`, `|A ∪ B|` and the containment `|A ∩ B| / |A|`,
+    /// `A` being `self` and `B` being `other`, each with its standard error.
+    /// Both sketches must have the same precision and hasher.
+    pub fn joint_estimate(&self, other: &Self, estimator: SetEstimator) -> Result<JointEstimate, HyperLogLogError> {
+        self.check_compatible(other)?;
+
+        let counts = setops::joint_histogram(&self.register_values(), &other.register_values());
+        Ok(setops::estimate(&counts, self.p, self.estimator, estimator))
+    }
+
+    /// Estimates `|A ∩ B|`, `A` being `self` and `B` being `other`.
+    pub fn intersection_cardinality(&self, other: &Self, estimator: SetEstimator) -> Result<SetEstimate, HyperLogLogError> {
+        Ok(self.joint_estimate(other, estimator)?.intersection)
+    }
+
+    /// Estimates `|A \ B|`, the items of `self` that are not in `other`.
+    pub fn difference_cardinality(&self, other: &Self, estimator: SetEstimator) -> Result<SetEstimate, HyperLogLogError> {
+        Ok(self.joint_estimate(other, estimator)?.only_self)
     }
 
-    pub fn merge(&mut self, other: &Self) -> Result<(), HyperLogLogError>{
+    /// Estimates the containment `|A ∩ B| / |A|`, the share of the items of `self` that are also in `other`.
+    pub fn containment(&self, other: &Self, estimator: SetEstimator) -> Result<SetEstimate, HyperLogLogError> {
+        Ok(self.joint_estimate(other, estimator)?.containment)
+    }
+
+    /// Estimates the Jaccard similarity `|A ∩ B| / |A ∪ B|` between `self` and `other`.
+    pub fn jaccard(&self, other: &Self, estimator: SetEstimator) -> Result<SetEstimate, HyperLogLogError> {
+        Ok(self.joint_estimate(other, estimator)?.jaccard)
+    }
+
+    /// Jaccard similarity of every pair of `sketches`, `matrix[i][j]` being the similarity of
+    /// `sketches[i]` and `sketches[j]`. The matrix is symmetric, its diagonal is 1 (0 for an empty sketch).
+    /// The registers of each sketch are unpacked once, not once per pair.
+    pub fn jaccard_matrix(sketches: &[Self], estimator: SetEstimator) -> Result<Vec<Vec<f64>>, HyperLogLogError> {
+        if let Some((first, rest)) = sketches.split_first() {
+            for sketch in rest {
+                first.check_compatible(sketch)?;
+            }
+        }
+
+        let registers: Vec<Cow<'_, [u8]>> = sketches.iter().map(|sketch| sketch.register_values()).collect();
+        let n = sketches.len();
+        let mut matrix = vec![vec![0.0; n]; n];
+        for i in 0..n {
+            if registers[i].iter().any(|&v| v != 0) {
+                matrix[i][i] = 1.0;
+            }
+            for j in i + 1..n {
+                let counts = setops::joint_histogram(&registers[i], &registers[j]);
+                let similarity = setops::estimate(&counts, sketches[i].p, sketches[i].estimator, estimator).jaccard.value;
+                matrix[i][j] = similarity;
+                matrix[j][i] = similarity;
+            }
+        }
+        Ok(matrix)
+    }
+
+    /// Fingerprint of the hasher, the element type and the prehashed marker.
+    fn fingerprint(&self) -> u64 {
+        fingerprint::<T, S>(&self.hasher_builder, self.prehashed)
+    }
 
-        // Checking if both the p values are same or not
+    /// Checks that `other` has the same precision and hashes items the same way.
+    fn check_compatible(&self, other: &Self) -> Result<(), HyperLogLogError> {
         if self.p != other.p {
             return Err(HyperLogLogError::MisMatchedPrecision(self.p, other.p));
         }
+        if self.fingerprint() != other.fingerprint() {
+            return Err(HyperLogLogError::HasherMismatch);
+        }
+        Ok(())
+    }
+
+    /// Dense register values, borrowed when the buckets already are `u8` registers.
+    fn register_values(&self) -> Cow<'_, [u8]> {
+        match &self.buckets {
+            Buckets::Dense(DenseRegisters::U8(values)) => Cow::Borrowed(values),
+            Buckets::Dense(registers) => Cow::Owned(registers.to_vec()),
+            Buckets::Sparse(sparse) => Cow::Owned(sparse.to_dense(self.p)),
+        }
+    }
+
+    /// Imports a Redis HyperLogLog, the string `GET` returns for a key filled with `PFADD`,
+    /// in either the dense or the sparse encoding. Redis sketches always have `p = 14`.
+    /// Use `RedisHasherBuilder` as `hasher_builder` to keep adding items the way Redis does.
+    pub fn from_redis(bytes: &[u8], hasher_builder: S) -> Result<Self, HyperLogLogError> {
+        Self::from_buckets(REDIS_PRECISION, redis::decode(bytes)?, hasher_builder)
+    }
+
+    /// Exports the sketch as a Redis HYLL string, which Redis accepts with `SET` and reads with `PFCOUNT`.
+    /// The sparse encoding is used when Redis would use it too. Fails unless `p = 14`.
+    pub fn to_redis(&self) -> Result<Vec<u8>, HyperLogLogError> {
+        if self.p != REDIS_PRECISION {
+            return Err(HyperLogLogError::MisMatchedPrecision(REDIS_PRECISION, self.p));
+        }
+        Ok(redis::encode(&self.register_values()))
+    }
+
+    /// Imports a serialized Apache DataSketches `HllSketch` (`toCompactByteArray` or
+    /// `toUpdatableByteArray`) in any mode (list, set or HLL) and register type (HLL_4, HLL_6 or HLL_8).
+    /// The sketch gets the precision `lgK` of the image. Use `DataSketchesHasherBuilder::new(lgK)` as
+    /// `hasher_builder` so that new items and `merge` with other imports stay consistent.
+    pub fn from_datasketches(bytes: &[u8], hasher_builder: S) -> Result<Self, HyperLogLogError> {
+        let (lg_k, registers) = datasketches::decode(bytes)?;
+        Self::from_buckets(lg_k, registers, hasher_builder)
+    }
+
+    /// Exports the sketch as a compact DataSketches `HllSketch` image with registers of `hll_type`,
+    /// readable with `HllSketch.heapify`. Fails if `p` is above 21, the largest DataSketches `lgK`.
+    pub fn to_datasketches(&self, hll_type: DataSketchesHllType) -> Result<Vec<u8>, HyperLogLogError> {
+        datasketches::check_lg_k(self.p)?;
+        Ok(datasketches::encode(self.p, &self.register_values(), hll_type, self.estimate().cardinality))
+    }
+
+    /// Imports an `hll` value of the postgresql-hll extension, storage specification v1, of any type
+    /// (EMPTY, EXPLICIT, SPARSE or FULL). The sketch gets the precision `log2m` of the value,
+    /// which must be at most `MAX_PRECISION`.
+    /// Use `PostgresHllHasherBuilder::new(log2m)` as `hasher_builder` so that new items and `merge`
+    /// with other imports stay consistent.
+    pub fn from_postgres_hll(bytes: &[u8], hasher_builder: S) -> Result<Self, HyperLogLogError> {
+        match postgres::decode(bytes)? {
+            (log2m, Some(registers)) => Self::from_buckets(log2m, registers, hasher_builder),
+            (log2m, None) => Self::with_hasher(log2m, hasher_builder),
+        }
+    }
+
+    /// Exports the sketch as a postgresql-hll value for a column of type `hll(p, regwidth, expthresh, sparseon)`
+    /// taken from `settings`. Every precision up to `MAX_PRECISION` fits in `log2m`, which goes up to 31.
+    pub fn to_postgres_hll(&self, settings: PostgresHllSettings) -> Result<Vec<u8>, HyperLogLogError> {
+        Ok(postgres::encode(self.p, &self.register_values(), settings))
+    }
 
-        // iterating over the bucket and getting the max value
-        for (i, &bucket) in other.buckets.iter().enumerate() {
-            self.buckets[i] = self.buckets[i].max(bucket);
+    /// Imports a ZetaSketch HyperLogLog++ sketch, the serialized `AggregatorStateProto` that BigQuery
+    /// `HLL_COUNT.INIT` returns, with dense or sparse data. The sketch gets the normal precision of the
+    /// proto. It stays sparse when the sparse precision is 25, as here; other sparse data is folded
+    /// into dense registers. Fails with `HasherMismatch` if the proto records items of another type than `T`.
+    /// Use `ZetaSketchHasherBuilder` as `hasher_builder` to keep adding items the way ZetaSketch does.
+    pub fn from_zetasketch(bytes: &[u8], hasher_builder: S) -> Result<Self, HyperLogLogError> {
+        let (p, decoded) = zetasketch::decode(bytes, zetasketch::value_type(T::TYPE_ID))?;
+        match decoded {
+            zetasketch::Decoded::Dense(registers) => Self::from_buckets(p, registers, hasher_builder),
+            zetasketch::Decoded::Sparse(entries) => {
+                let mut hll = Self::with_hasher(p, hasher_builder)?;
+                hll.buckets = Buckets::Sparse(SparseRegisters::from_entries(entries)?);
+                Ok(hll)
+            }
         }
+    }
 
-        return Ok(())
+    /// Exports the sketch as a ZetaSketch `AggregatorStateProto`, which BigQuery reads with
+    /// `HLL_COUNT.MERGE` and `HLL_COUNT.EXTRACT`. Sparse sketches keep sparse data at precision `p + 5`.
+    /// Fails unless `p` is between 10 and 24, the precisions ZetaSketch supports.
+    pub fn to_zetasketch(&self) -> Result<Vec<u8>, HyperLogLogError> {
+        zetasketch::check_precision(self.p)?;
+        let value_type = zetasketch::value_type(T::TYPE_ID);
+        Ok(match &self.buckets {
+            Buckets::Sparse(sparse) => zetasketch::encode(self.p, Some(&sparse.entries()), &[], value_type),
+            Buckets::Dense(_) => zetasketch::encode(self.p, None, &self.register_values(), value_type),
+        })
     }
 
-    /// Resets the bucket for reuse, sets value of the buckets to 0, doesn't affect p and m
+    /// Resets the bucket for reuse, sets value of the buckets to 0, doesn't affect p and m.
+    /// The sketch goes back to the sparse representation and is no longer marked as prehashed.
     pub fn reset(&mut self) {
-        self.buckets.fill(0);
+        self.buckets = Self::empty_buckets(self.p, self.m, self.backend);
+        self.prehashed = None;
     }
 
     /// Returns a copy of the current state of the bucket.
@@ -204,14 +903,218 @@ impl<T: ToBytes, S: BuildHasher + Default + Clone> HyperLogLog<T, S> {
     ///
     /// 
     pub fn get_buckets(&self) -> Vec<u8> {
-        self.buckets.clone()
+        match &self.buckets {
+            Buckets::Sparse(sparse) => sparse.to_dense(self.p),
+            Buckets::Dense(buckets) => buckets.to_vec(),
+        }
     }
 
     pub fn get_p(&self) -> u32 {
-        self.p.clone()
+        self.p
     }
 
     pub fn get_m(&self) -> usize {
-        self.m.clone()
+        self.m
+    }
+
+    /// Returns the representation currently used for the buckets.
+    pub fn representation(&self) -> Representation {
+        match &self.buckets {
+            Buckets::Sparse(_) => Representation::Sparse,
+            Buckets::Dense(buckets) => match buckets.backend() {
+                RegisterBackend::U8 => Representation::Dense,
+                RegisterBackend::Packed6 => Representation::Packed6,
+                RegisterBackend::Hll4 => Representation::Hll4,
+            },
+        }
+    }
+}
+
+impl<T: ToBytes, S: BuildHasher + Default + Clone + HasherState> HyperLogLog<T, S> {
+    /// Encodes the sketch in the compact binary format, see `write_to`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        self.write_to(&mut bytes).expect("writing to a Vec cannot fail");
+        bytes
+    }
+
+    /// Decodes a sketch produced by `to_bytes`, failing if bytes are left over.
+    pub fn from_bytes(mut bytes: &[u8]) -> Result<Self, HyperLogLogError> {
+        let hll = Self::read_from(&mut bytes)?;
+        if !bytes.is_empty() {
+            return Err(HyperLogLogError::CorruptPayload(format!("{} trailing bytes", bytes.len())));
+        }
+        Ok(hll)
+    }
+
+    /// Writes the sketch to `writer` in the versioned binary format: magic bytes, format version,
+    /// p, representation, hasher fingerprint, hash algorithm, seed and parameters, and the registers
+    /// in their own layout, followed by a CRC-32. Sparse entries are delta-encoded, a dense p=14 sketch with the
+    /// default hasher takes 16 KiB plus 40 bytes.
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        binary::write(writer, self.p, self.backend, self.fingerprint(), &self.hasher_builder, self.prehashed, &self.buckets)
+    }
+
+    /// Reads one sketch written by `write_to` from `reader`, stopping right after it,
+    /// so several sketches can be read from the same stream.
+    /// The hasher is restored from the recorded seed and parameters with `HasherState::restore`,
+    /// and must have the recorded algorithm and fingerprint, as with serde. Sketches of format
+    /// version 1 record no hasher and are read with `S::default()`.
+    pub fn read_from<R: Read>(reader: &mut R) -> Result<Self, HyperLogLogError> {
+        let decoded = binary::read(reader)?;
+
+        let hasher_builder: S = match &decoded.hasher {
+            Some(record) => restore_hasher(Some(&record.algorithm), record.seed, &record.parameters)?,
+            None => restore_hasher(None, 0, &[])?,
+        };
+        Self::from_decoded(decoded, hasher_builder)
+    }
+
+    /// Reads one sketch written by `write_to` with a given hasher, for hashers that cannot be restored
+    /// from what the sketch records, such as `KeyedHasherBuilder` whose key is never written out.
+    /// The recorded algorithm, seed and parameters must be the ones of `hasher_builder`.
+    pub fn read_from_with_hasher<R: Read>(reader: &mut R, hasher_builder: S) -> Result<Self, HyperLogLogError> {
+        let decoded = binary::read(reader)?;
+        if let Some(record) = &decoded.hasher {
+            check_recorded_hasher(&hasher_builder, &record.algorithm, record.seed, &record.parameters)?;
+        }
+        Self::from_decoded(decoded, hasher_builder)
+    }
+
+    /// Decodes a sketch produced by `to_bytes` with a given hasher, see `read_from_with_hasher`.
+    pub fn from_bytes_with_hasher(mut bytes: &[u8], hasher_builder: S) -> Result<Self, HyperLogLogError> {
+        let hll = Self::read_from_with_hasher(&mut bytes, hasher_builder)?;
+        if !bytes.is_empty() {
+            return Err(HyperLogLogError::CorruptPayload(format!("{} trailing bytes", bytes.len())));
+        }
+        Ok(hll)
+    }
+
+    /// Deserializes a sketch with serde and a given hasher, the serde counterpart of `read_from_with_hasher`.
+    pub fn deserialize_with_hasher<'de, D: Deserializer<'de>>(deserializer: D, hasher_builder: S) -> Result<Self, D::Error> {
+        let data = HyperLogLogSerializable::deserialize(deserializer)?;
+        if let Some(algorithm) = &data.hash_algorithm {
+            let parameters = data.hash_parameters.as_deref().unwrap_or_default();
+            check_recorded_hasher(&hasher_builder, algorithm, data.hash_seed.unwrap_or(0), parameters).map_err(D::Error::custom)?;
+        }
+        Self::from_serializable(data, hasher_builder)
+    }
+
+    /// Checks a decoded sketch against the fingerprint of `hasher_builder`.
+    fn from_decoded(decoded: binary::Decoded, hasher_builder: S) -> Result<Self, HyperLogLogError> {
+        if decoded.fingerprint != fingerprint::<T, S>(&hasher_builder, decoded.prehashed) {
+            return Err(HyperLogLogError::HasherMismatch);
+        }
+
+        Ok(Self {
+            p: decoded.p,
+            m: decoded.m,
+            buckets: decoded.buckets,
+            hasher_builder,
+            backend: decoded.backend,
+            estimator: Estimator::default(),
+            prehashed: decoded.prehashed,
+            _marker: PhantomData,
+        })
+    }
+}
+
+/// Checks that `hasher_builder` is the hasher a sketch recorded.
+fn check_recorded_hasher<S: HasherState>(hasher_builder: &S, algorithm: &str, seed: u64, parameters: &[u8]) -> Result<(), HyperLogLogError> {
+    if hasher_builder.algorithm() != algorithm || hasher_builder.seed() != seed || hasher_builder.parameters() != parameters {
+        return Err(HyperLogLogError::HasherMismatch);
+    }
+    Ok(())
+}
+
+/// The hasher a sketch recorded, or `S::default()` for sketches saved before hashers were recorded.
+fn restore_hasher<S: HasherState + Default>(algorithm: Option<&str>, seed: u64, parameters: &[u8]) -> Result<S, HyperLogLogError> {
+    let Some(algorithm) = algorithm else {
+        return Ok(S::default());
+    };
+    match S::restore(seed, parameters) {
+        Some(hasher) if hasher.algorithm() == algorithm && hasher.seed() == seed => Ok(hasher),
+        _ => Err(HyperLogLogError::HasherMismatch),
+    }
+}
+
+/// Fingerprint of the hasher and element type, used to detect sketches that hash differently.
+/// Sketches fed with raw hashes get a fingerprint of their own for each upstream hash function,
+/// and so do sketches of every `ENCODING_VERSION`.
+fn fingerprint<T: ToBytes, S: BuildHasher>(hasher_builder: &S, prehashed: Option<u64>) -> u64 {
+    let mut hasher = hasher_builder.build_hasher();
+    hasher.write(b"__hyperloglog_fingerprint__");
+    hasher.write(&[ENCODING_VERSION]);
+    hasher.write(T::TYPE_ID);
+    if let Some(hash_id) = prehashed {
+        hasher.write(b"__prehashed__");
+        hasher.write(&hash_id.to_le_bytes());
+    }
+    hasher.finish()
+}
+
+/// Number of sparse entries that take as much memory as `m` dense registers laid out with `backend`.
+fn sparse_budget(backend: RegisterBackend, m: usize) -> usize {
+    backend.size_in_bytes(m).expect("register count of a supported precision") / std::mem::size_of::<u32>()
+}
+
+/// Number of buckets `2^p`, failing if `p < 4` or `p > MAX_PRECISION`.
+fn bucket_count(p: u32) -> Result<usize, HyperLogLogError> {
+    if p < 4 {
+        return Err(HyperLogLogError::PrecisionBelowThreshold);
+    }
+    if p > MAX_PRECISION {
+        return Err(HyperLogLogError::PrecisionTooLarge);
+    }
+    Ok(1 << p)
+}
+
+/// Checks that `buckets` holds `2^p` registers, none above the largest rank.
+fn check_registers(p: u32, buckets: &[u8]) -> Result<(), HyperLogLogError> {
+    let m = bucket_count(p)?;
+    if buckets.len() != m {
+        return Err(HyperLogLogError::BucketCountMismatch(m, buckets.len()));
+    }
+    if let Some(&value) = buckets.iter().find(|&&v| v > max_rank(p)) {
+        return Err(HyperLogLogError::RegisterOutOfRange(value, max_rank(p)));
+    }
+    Ok(())
+}
+
+/// Registers of a sketch with `p` bits folded to `new_p` bits. The `p - new_p` index bits dropped
+/// from each register index are the first bits of the rank at `new_p`: the rank is read from them
+/// when one is set, otherwise all of them count as leading zeros on top of the register.
+fn fold_registers(values: &[u8], p: u32, new_p: u32) -> Vec<u8> {
+    let shift = p - new_p;
+    let mut folded = vec![0u8; 1usize << new_p];
+    for (idx, &value) in values.iter().enumerate() {
+        if value == 0 {
+            continue;
+        }
+        let low = (idx & ((1usize << shift) - 1)) as u64;
+        let rank = if low != 0 {
+            (low.leading_zeros() - (64 - shift) + 1) as u8
+        } else {
+            shift as u8 + value
+        };
+        let register = &mut folded[idx >> shift];
+        *register = (*register).max(rank);
     }
+    folded
+}
+
+/// Largest rank a register can hold with `p` index bits: all `64 - p` remaining bits zero.
+fn max_rank(p: u32) -> u8 {
+    (64 - p + 1) as u8
+}
+
+/// Splits a hash into the bucket index (top `p` bits) and the rank of the remaining bits.
+fn index_and_rank(hash: u64, p: u32) -> (usize, u8) {
+    // Bucket index: top `p` bits
+    let idx = (hash >> (64 - p)) as usize;
+    // Remaining bits for leading zero count
+    let w = hash << p;
+    // Count leading zeros, then +1, only 64 - p bits are left so the rank is capped at 64 - p + 1
+    let leading = (w.leading_zeros() + 1).min(max_rank(p) as u32) as u8;
+    (idx, leading)
 }
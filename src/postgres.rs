@@ -0,0 +1,348 @@
+use std::hash::{BuildHasher, Hasher};
+
+use crate::buffer::HashBuffer;
+use crate::datasketches::murmurhash3_x64_128;
+use crate::error::HyperLogLogError;
+use crate::stable::{HasherIdentity, HasherState};
+use crate::MAX_PRECISION;
+
+// storage specification version, the high nibble of the first byte
+const SCHEMA_VERSION: u8 = 1;
+// types, the low nibble of the first byte
+const UNDEFINED: u8 = 0;
+const EMPTY: u8 = 1;
+const EXPLICIT: u8 = 2;
+const SPARSE: u8 = 3;
+const FULL: u8 = 4;
+// version and type, parameters (regwidth - 1 and log2m), cutoffs (sparseon and expthresh)
+const HEADER_LEN: usize = 3;
+// expthresh code meaning "chosen from log2m and regwidth", -1 in the type modifiers
+const EXPTHRESH_AUTO: u8 = 63;
+const SPARSE_ON_BIT: u8 = 1 << 6;
+
+// defaults of `hll_empty()`, `hll.default_*` in the extension
+const DEFAULT_LOG2M: u32 = 11;
+const DEFAULT_REGWIDTH: u32 = 5;
+const MIN_LOG2M: u32 = 4;
+const MAX_LOG2M: u32 = 31;
+const MAX_REGWIDTH: u32 = 8;
+const MAX_EXPTHRESH: i64 = 1 << 30;
+
+// seed of `hll_hash_*` when none is given
+const DEFAULT_SEED: u64 = 0;
+
+/// Type modifiers of a postgresql-hll column, `hll(log2m, regwidth, expthresh, sparseon)`,
+/// without `log2m`, which is the precision of the sketch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PostgresHllSettings {
+    regwidth: u32,
+    expthresh: i64,
+    sparse_on: bool,
+}
+
+impl PostgresHllSettings {
+    /// `regwidth` between 1 and 8 bits, `expthresh` -1 (automatic), 0 (no explicit type) or a power of 2.
+    pub fn new(regwidth: u32, expthresh: i64, sparse_on: bool) -> Result<Self, HyperLogLogError> {
+        if !(1..=MAX_REGWIDTH).contains(&regwidth) {
+            return Err(HyperLogLogError::InvalidRegisterWidth(regwidth));
+        }
+        let power_of_two = expthresh > 0 && expthresh <= MAX_EXPTHRESH && expthresh & (expthresh - 1) == 0;
+        if !(expthresh == -1 || expthresh == 0 || power_of_two) {
+            return Err(HyperLogLogError::InvalidExplicitThreshold(expthresh));
+        }
+        Ok(Self { regwidth, expthresh, sparse_on })
+    }
+
+    /// Settings recorded in the header of a stored `hll` value, to write results back to the same column.
+    pub fn from_header(bytes: &[u8]) -> Result<Self, HyperLogLogError> {
+        Ok(read_header(bytes)?.settings)
+    }
+
+    pub fn get_regwidth(&self) -> u32 {
+        self.regwidth
+    }
+
+    pub fn get_expthresh(&self) -> i64 {
+        self.expthresh
+    }
+
+    pub fn get_sparse_on(&self) -> bool {
+        self.sparse_on
+    }
+
+    fn cutoff_byte(&self) -> u8 {
+        let code = match self.expthresh {
+            -1 => EXPTHRESH_AUTO,
+            0 => 0,
+            threshold => threshold.trailing_zeros() as u8 + 1,
+        };
+        if self.sparse_on { SPARSE_ON_BIT | code } else { code }
+    }
+}
+
+impl Default for PostgresHllSettings {
+    /// The extension defaults, `regwidth = 5`, automatic `expthresh` and the sparse type enabled.
+    fn default() -> Self {
+        Self { regwidth: DEFAULT_REGWIDTH, expthresh: -1, sparse_on: true }
+    }
+}
+
+/// `BuildHasher` hashing items the way the `hll_hash_*` functions of postgresql-hll do,
+/// MurmurHash3_x64_128 with seed 0, keeping the first half. `log2m` must be the precision of the
+/// sketch the hasher is used with. `hll_hash_bigint` and `hll_hash_integer` hash little-endian
+/// words and `hll_hash_text` the UTF-8 bytes, as `ToBytes` does for `i64`, `i32` and `String`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PostgresHllHasherBuilder {
+    log2m: u32,
+}
+
+impl PostgresHllHasherBuilder {
+    /// Hasher for sketches with precision `log2m`, between 4 and 31.
+    pub fn new(log2m: u32) -> Result<Self, HyperLogLogError> {
+        if log2m < MIN_LOG2M {
+            return Err(HyperLogLogError::PrecisionBelowThreshold);
+        }
+        if log2m > MAX_LOG2M {
+            return Err(HyperLogLogError::UnsupportedPrecision(log2m));
+        }
+        Ok(Self { log2m })
+    }
+
+    pub fn get_log2m(&self) -> u32 {
+        self.log2m
+    }
+}
+
+impl Default for PostgresHllHasherBuilder {
+    /// Hasher for the extension default `log2m = 11`.
+    fn default() -> Self {
+        Self { log2m: DEFAULT_LOG2M }
+    }
+}
+
+impl BuildHasher for PostgresHllHasherBuilder {
+    type Hasher = PostgresHllHasher;
+
+    fn build_hasher(&self) -> PostgresHllHasher {
+        PostgresHllHasher { log2m: self.log2m, bytes: HashBuffer::new() }
+    }
+}
+
+impl HasherIdentity for PostgresHllHasherBuilder {
+    fn algorithm(&self) -> &'static str {
+        "postgresql-hll-murmur3-x64-128"
+    }
+
+    fn seed(&self) -> u64 {
+        DEFAULT_SEED
+    }
+}
+
+// the hash bits are moved around for `log2m`, which is recorded as a single byte
+impl HasherState for PostgresHllHasherBuilder {
+    fn parameters(&self) -> Vec<u8> {
+        vec![self.log2m as u8]
+    }
+
+    fn restore(seed: u64, parameters: &[u8]) -> Option<Self> {
+        match parameters {
+            &[log2m] if seed == Self::default().seed() => Self::new(log2m as u32).ok(),
+            _ => None,
+        }
+    }
+}
+
+/// Hasher built by `PostgresHllHasherBuilder`.
+///
+/// postgresql-hll takes the register index from the low `log2m` bits of the hash and the value
+/// from the trailing zeros of the rest, while this crate reads the index from the top bits and
+/// the rank from the leading zeros. `finish` moves the bits around so that both agree. The only
+/// difference is a hash whose upper `64 - log2m` bits are all zero, which the extension ignores.
+#[derive(Debug, Clone)]
+pub struct PostgresHllHasher {
+    log2m: u32,
+    bytes: HashBuffer,
+}
+
+impl Hasher for PostgresHllHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        let (hash, _) = murmurhash3_x64_128(self.bytes.as_slice(), DEFAULT_SEED);
+        remap(hash, self.log2m)
+    }
+}
+
+fn remap(hash: u64, log2m: u32) -> u64 {
+    let index = hash & ((1u64 << log2m) - 1);
+    // the remaining bits reversed, so their trailing zeros become leading zeros
+    let rest = (hash >> log2m).reverse_bits() >> log2m;
+    (index << (64 - log2m)) | rest
+}
+
+struct Header {
+    kind: u8,
+    log2m: u32,
+    settings: PostgresHllSettings,
+}
+
+fn corrupt(msg: String) -> HyperLogLogError {
+    HyperLogLogError::CorruptPayload(format!("postgresql-hll: {}", msg))
+}
+
+fn read_header(bytes: &[u8]) -> Result<Header, HyperLogLogError> {
+    if bytes.len() < HEADER_LEN {
+        return Err(corrupt(format!("value of {} bytes is truncated", bytes.len())));
+    }
+    if bytes[0] >> 4 != SCHEMA_VERSION {
+        return Err(HyperLogLogError::UnsupportedVersion(bytes[0] >> 4));
+    }
+    let regwidth = (bytes[1] >> 5) as u32 + 1;
+    let log2m = (bytes[1] & 0x1f) as u32;
+    let expthresh = match bytes[2] & 0x3f {
+        EXPTHRESH_AUTO => -1,
+        0 => 0,
+        code => 1i64 << (code - 1),
+    };
+    let settings = PostgresHllSettings::new(regwidth, expthresh, bytes[2] & SPARSE_ON_BIT != 0)?;
+    Ok(Header { kind: bytes[0] & 0x0f, log2m, settings })
+}
+
+/// `width` bits starting at bit `offset`, most significant bit first.
+fn read_bits(bytes: &[u8], offset: usize, width: u32) -> u64 {
+    (0..width as usize).fold(0, |word, i| {
+        let bit = offset + i;
+        (word << 1) | ((bytes[bit / 8] >> (7 - bit % 8)) & 1) as u64
+    })
+}
+
+/// Appends `words` of `width` bits, most significant bit first, zero padded to a whole byte.
+fn write_bits(out: &mut Vec<u8>, words: impl Iterator<Item = u64>, width: u32) {
+    let (mut acc, mut filled) = (0u64, 0u32);
+    for word in words {
+        acc = (acc << width) | word;
+        filled += width;
+        while filled >= 8 {
+            filled -= 8;
+            out.push((acc >> filled) as u8);
+        }
+        acc &= (1 << filled) - 1;
+    }
+    if filled > 0 {
+        out.push((acc << (8 - filled)) as u8);
+    }
+}
+
+/// `log2m` and the register values of a stored `hll` value of any type, `None` for an EMPTY value.
+/// Values above the largest rank this crate stores at `log2m`, `64 - log2m + 1`, are lowered to it.
+pub(crate) fn decode(bytes: &[u8]) -> Result<(u32, Option<Vec<u8>>), HyperLogLogError> {
+    let header = read_header(bytes)?;
+    let (log2m, regwidth) = (header.log2m, header.settings.regwidth);
+    if log2m < MIN_LOG2M {
+        return Err(HyperLogLogError::PrecisionBelowThreshold);
+    }
+    // the header allows up to 31, checked before anything is sized from it
+    if log2m > MAX_PRECISION {
+        return Err(HyperLogLogError::UnsupportedPrecision(log2m));
+    }
+    let m = 1usize << log2m;
+    let max_value = ((1u32 << regwidth) - 1) as u8;
+    let payload = &bytes[HEADER_LEN..];
+
+    if header.kind == EMPTY {
+        if !payload.is_empty() {
+            return Err(corrupt(format!("{} bytes after an EMPTY header", payload.len())));
+        }
+        return Ok((log2m, None));
+    }
+    if header.kind == FULL {
+        let expected = (m * regwidth as usize).div_ceil(8);
+        if payload.len() != expected {
+            return Err(HyperLogLogError::BucketCountMismatch(expected, payload.len()));
+        }
+    }
+    let mut registers = vec![0u8; m];
+
+    match header.kind {
+        // raw 64-bit hashes, big-endian
+        EXPLICIT => {
+            if !payload.len().is_multiple_of(8) {
+                return Err(corrupt(format!("EXPLICIT payload of {} bytes is not a whole number of hashes", payload.len())));
+            }
+            for chunk in payload.chunks_exact(8) {
+                let hash = u64::from_be_bytes(chunk.try_into().unwrap());
+                let (index, substream) = ((hash & (m as u64 - 1)) as usize, hash >> log2m);
+                if substream != 0 {
+                    let value = (substream.trailing_zeros() + 1).min(max_value as u32) as u8;
+                    registers[index] = registers[index].max(value);
+                }
+            }
+        }
+        // (index, value) pairs of log2m + regwidth bits, sorted by index
+        SPARSE => {
+            let width = log2m + regwidth;
+            let count = payload.len() * 8 / width as usize;
+            if payload.len() * 8 - count * width as usize >= 8 {
+                return Err(corrupt(format!("SPARSE payload of {} bytes has a trailing byte", payload.len())));
+            }
+            let mut previous = None;
+            for i in 0..count {
+                let word = read_bits(payload, i * width as usize, width);
+                let (index, value) = ((word >> regwidth) as usize, (word & max_value as u64) as u8);
+                // zero values only show up as padding, the extension skips them
+                if value == 0 {
+                    continue;
+                }
+                if previous.is_some_and(|previous| index <= previous) {
+                    return Err(corrupt(format!("SPARSE register {} is out of order", index)));
+                }
+                previous = Some(index);
+                registers[index] = value;
+            }
+        }
+        // every register in regwidth bits
+        FULL => {
+            for (i, register) in registers.iter_mut().enumerate() {
+                *register = read_bits(payload, i * regwidth as usize, regwidth) as u8;
+            }
+        }
+        UNDEFINED => return Err(corrupt("UNDEFINED values hold no registers".to_string())),
+        kind => return Err(corrupt(format!("unknown type {}", kind))),
+    }
+
+    let max_rank = (64 - log2m + 1) as u8;
+    for register in registers.iter_mut() {
+        *register = (*register).min(max_rank);
+    }
+    Ok((log2m, Some(registers)))
+}
+
+/// Serializes registers at `log2m` as a stored `hll` value: EMPTY when no register is set, SPARSE
+/// when enabled and smaller than FULL, FULL otherwise. EXPLICIT is never written, the hashes it
+/// holds are not kept by this crate. Values above `2^regwidth - 1` are lowered to it.
+pub(crate) fn encode(log2m: u32, registers: &[u8], settings: PostgresHllSettings) -> Vec<u8> {
+    let regwidth = settings.regwidth;
+    let max_value = ((1u32 << regwidth) - 1) as u8;
+    let filled = registers.iter().filter(|&&v| v != 0).count();
+
+    let kind = if filled == 0 {
+        EMPTY
+    } else if settings.sparse_on && filled * ((log2m + regwidth) as usize) < registers.len() * regwidth as usize {
+        SPARSE
+    } else {
+        FULL
+    };
+
+    let mut out = vec![(SCHEMA_VERSION << 4) | kind, (((regwidth - 1) as u8) << 5) | log2m as u8, settings.cutoff_byte()];
+    match kind {
+        SPARSE => {
+            let words = registers.iter().enumerate().filter(|&(_, &v)| v != 0).map(|(index, &v)| ((index as u64) << regwidth) | v.min(max_value) as u64);
+            write_bits(&mut out, words, log2m + regwidth);
+        }
+        FULL => write_bits(&mut out, registers.iter().map(|&v| v.min(max_value) as u64), regwidth),
+        _ => {}
+    }
+    out
+}
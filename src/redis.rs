@@ -0,0 +1,209 @@
+use std::hash::{BuildHasher, Hasher};
+
+use crate::buffer::HashBuffer;
+use crate::error::HyperLogLogError;
+use crate::stable::{unparameterized, HasherIdentity, HasherState};
+use crate::registers::{DenseRegisters, RegisterBackend};
+
+/// Precision of every Redis HyperLogLog, `2^14` registers.
+pub const REDIS_PRECISION: u32 = 14;
+
+const REGISTERS: usize = 1 << REDIS_PRECISION;
+const MAGIC: &[u8; 4] = b"HYLL";
+// magic, encoding, 3 unused bytes and the cached cardinality
+const HEADER_LEN: usize = 16;
+const DENSE: u8 = 0;
+const SPARSE: u8 = 1;
+// the largest value a VAL opcode can hold, larger registers force the dense encoding
+const SPARSE_VAL_MAX: u8 = 32;
+// default `hll-sparse-max-bytes`, Redis promotes sparse strings past this size
+const SPARSE_MAX_BYTES: usize = 3000;
+// cached cardinality with the most significant bit set, which Redis reads as "needs recomputing"
+const STALE_CARDINALITY: [u8; 8] = [0, 0, 0, 0, 0, 0, 0, 0x80];
+
+// seed Redis passes to MurmurHash64A
+const REDIS_SEED: u64 = 0xadc83b19;
+
+/// MurmurHash64A by Austin Appleby, reading the input as little-endian words as Redis does.
+fn murmurhash64a(data: &[u8], seed: u64) -> u64 {
+    const M: u64 = 0xc6a4a7935bd1e995;
+    const R: u32 = 47;
+
+    let mut h = seed ^ (data.len() as u64).wrapping_mul(M);
+    let mut chunks = data.chunks_exact(8);
+    for chunk in &mut chunks {
+        let mut k = u64::from_le_bytes(chunk.try_into().unwrap());
+        k = k.wrapping_mul(M);
+        k ^= k >> R;
+        k = k.wrapping_mul(M);
+        h ^= k;
+        h = h.wrapping_mul(M);
+    }
+
+    let tail = chunks.remainder();
+    if !tail.is_empty() {
+        for (i, &byte) in tail.iter().enumerate() {
+            h ^= (byte as u64) << (8 * i);
+        }
+        h = h.wrapping_mul(M);
+    }
+
+    h ^= h >> R;
+    h = h.wrapping_mul(M);
+    h ^= h >> R;
+    h
+}
+
+/// `BuildHasher` hashing items the way Redis `PFADD` does, so that a sketch imported with
+/// `from_redis` keeps matching the Redis one as items are added. Items must hash the same bytes
+/// as the Redis strings, e.g. `String` or `&str` items rather than integers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RedisHasherBuilder;
+
+impl BuildHasher for RedisHasherBuilder {
+    type Hasher = RedisHasher;
+
+    fn build_hasher(&self) -> RedisHasher {
+        RedisHasher { bytes: HashBuffer::new() }
+    }
+}
+
+impl HasherIdentity for RedisHasherBuilder {
+    fn algorithm(&self) -> &'static str {
+        "redis-murmur64a"
+    }
+
+    fn seed(&self) -> u64 {
+        REDIS_SEED
+    }
+}
+
+impl HasherState for RedisHasherBuilder {
+    fn restore(seed: u64, parameters: &[u8]) -> Option<Self> {
+        unparameterized(seed, parameters)
+    }
+}
+
+/// Hasher built by `RedisHasherBuilder`: MurmurHash64A with the Redis seed over every written byte.
+///
+/// Redis takes the register index from the low 14 bits of the hash and the rank from the trailing
+/// zeros of the rest, while this crate reads the index from the top bits and the rank from the
+/// leading zeros. `finish` moves the bits around so that both agree at p = 14.
+#[derive(Debug, Clone, Default)]
+pub struct RedisHasher {
+    bytes: HashBuffer,
+}
+
+impl Hasher for RedisHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        let hash = murmurhash64a(self.bytes.as_slice(), REDIS_SEED);
+        let index = hash & (REGISTERS as u64 - 1);
+        // the 50 remaining bits reversed, so their trailing zeros become leading zeros
+        let rest = (hash >> REDIS_PRECISION).reverse_bits() >> REDIS_PRECISION;
+        (index << (64 - REDIS_PRECISION)) | rest
+    }
+}
+
+/// Register values of a Redis HYLL string, dense or sparse. The cached cardinality is ignored.
+pub(crate) fn decode(bytes: &[u8]) -> Result<Vec<u8>, HyperLogLogError> {
+    if bytes.len() < HEADER_LEN || &bytes[..4] != MAGIC {
+        return Err(HyperLogLogError::InvalidMagic);
+    }
+    let payload = &bytes[HEADER_LEN..];
+    let corrupt = |msg: String| HyperLogLogError::CorruptPayload(format!("Redis HYLL: {}", msg));
+
+    match bytes[4] {
+        // 6-bit registers, least significant bits first, the layout of `RegisterBackend::Packed6`
+        DENSE => {
+            let max_rank = (64 - REDIS_PRECISION + 1) as u8;
+            Ok(DenseRegisters::read_payload(RegisterBackend::Packed6, REGISTERS, max_rank, payload)?.to_vec())
+        }
+        SPARSE => {
+            let mut registers = Vec::with_capacity(REGISTERS);
+            let mut i = 0;
+            while i < payload.len() {
+                let opcode = payload[i];
+                let (value, run) = match opcode >> 6 {
+                    // ZERO: 00xxxxxx, 1 to 64 empty registers
+                    0b00 => (0, (opcode & 0x3f) as usize + 1),
+                    // XZERO: 01xxxxxx yyyyyyyy, 1 to 16384 empty registers
+                    0b01 => {
+                        let low = *payload.get(i + 1).ok_or_else(|| corrupt("truncated XZERO opcode".to_string()))?;
+                        i += 1;
+                        (0, (((opcode & 0x3f) as usize) << 8 | low as usize) + 1)
+                    }
+                    // VAL: 1vvvvvxx, 1 to 4 registers set to 1 to 32
+                    _ => (((opcode >> 2) & 0x1f) + 1, (opcode & 0x03) as usize + 1),
+                };
+                i += 1;
+
+                if registers.len() + run > REGISTERS {
+                    return Err(corrupt(format!("opcodes cover more than {} registers", REGISTERS)));
+                }
+                registers.resize(registers.len() + run, value);
+            }
+
+            if registers.len() != REGISTERS {
+                return Err(HyperLogLogError::BucketCountMismatch(REGISTERS, registers.len()));
+            }
+            Ok(registers)
+        }
+        encoding => Err(corrupt(format!("unknown encoding {}", encoding))),
+    }
+}
+
+/// Encodes `2^14` register values as a Redis HYLL string, sparse when Redis itself would keep it sparse.
+/// The cached cardinality is marked stale so that Redis recomputes it on the next `PFCOUNT`.
+pub(crate) fn encode(registers: &[u8]) -> Vec<u8> {
+    let mut header = Vec::with_capacity(HEADER_LEN);
+    header.extend_from_slice(MAGIC);
+
+    if registers.iter().all(|&v| v <= SPARSE_VAL_MAX) {
+        let sparse = encode_sparse(registers);
+        if HEADER_LEN + sparse.len() <= SPARSE_MAX_BYTES {
+            header.extend_from_slice(&[SPARSE, 0, 0, 0]);
+            header.extend_from_slice(&STALE_CARDINALITY);
+            header.extend_from_slice(&sparse);
+            return header;
+        }
+    }
+
+    header.extend_from_slice(&[DENSE, 0, 0, 0]);
+    header.extend_from_slice(&STALE_CARDINALITY);
+    DenseRegisters::from_values(RegisterBackend::Packed6, registers.to_vec()).write_payload(&mut header);
+    header
+}
+
+/// Runs of equal registers as ZERO, XZERO and VAL opcodes.
+fn encode_sparse(registers: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < registers.len() {
+        let value = registers[i];
+        let mut run = registers[i..].iter().take_while(|&&v| v == value).count();
+        i += run;
+
+        while run > 0 {
+            if value == 0 {
+                if run > 64 {
+                    let len = run.min(REGISTERS);
+                    out.push(0x40 | ((len - 1) >> 8) as u8);
+                    out.push((len - 1) as u8);
+                    run -= len;
+                } else {
+                    out.push((run - 1) as u8);
+                    run = 0;
+                }
+            } else {
+                let len = run.min(4);
+                out.push(0x80 | ((value - 1) << 2) | (len - 1) as u8);
+                run -= len;
+            }
+        }
+    }
+    out
+}
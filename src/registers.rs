@@ -0,0 +1,391 @@
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::HyperLogLogError;
+
+/// Storage layout of the dense registers, chosen when the sketch is built.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum RegisterBackend {
+    /// One `u8` per register, fastest to update.
+    #[default]
+    U8,
+    /// Registers bit-packed on 6 bits, 25% smaller than `U8`.
+    /// A rank from a 64-bit hash is at most `64 - 4 + 1 = 61`, so 6 bits are always enough.
+    Packed6,
+    /// 4-bit registers relative to a shared base value, with an exception table for the
+    /// registers that do not fit in a nibble (as in the DataSketches `HLL_4` type).
+    /// About half the size of `U8`, at the cost of slower updates.
+    Hll4,
+}
+
+impl RegisterBackend {
+    /// Bytes needed to hold `m` registers with this layout, not counting `Hll4` exceptions.
+    /// Fails if the size does not fit in a `usize`.
+    pub fn size_in_bytes(&self, m: usize) -> Result<usize, HyperLogLogError> {
+        match self {
+            RegisterBackend::U8 => Ok(m),
+            RegisterBackend::Packed6 => {
+                let bits = m.checked_mul(PACKED_BITS).ok_or(HyperLogLogError::PrecisionTooLarge)?;
+                Ok(bits.div_ceil(8))
+            }
+            RegisterBackend::Hll4 => Ok(m.div_ceil(2)),
+        }
+    }
+}
+
+// bits per register in the packed layout
+const PACKED_BITS: usize = 6;
+const PACKED_MASK: u16 = (1 << PACKED_BITS) - 1;
+
+/// Dense register storage, one register per bucket, in one of the `RegisterBackend` layouts.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum DenseRegisters {
+    U8(Vec<u8>),
+    Packed6(PackedRegisters),
+    Hll4(Hll4Registers),
+}
+
+impl DenseRegisters {
+    /// `m` zeroed registers using `backend`.
+    pub(crate) fn new(backend: RegisterBackend, m: usize) -> Self {
+        match backend {
+            RegisterBackend::U8 => DenseRegisters::U8(vec![0u8; m]),
+            RegisterBackend::Packed6 => DenseRegisters::Packed6(PackedRegisters::new(m)),
+            RegisterBackend::Hll4 => DenseRegisters::Hll4(Hll4Registers::new(m)),
+        }
+    }
+
+    /// Registers holding `values` using `backend`.
+    pub(crate) fn from_values(backend: RegisterBackend, values: Vec<u8>) -> Self {
+        match backend {
+            RegisterBackend::U8 => DenseRegisters::U8(values),
+            RegisterBackend::Packed6 => {
+                let mut packed = PackedRegisters::new(values.len());
+                for (i, &v) in values.iter().enumerate() {
+                    packed.set(i, v);
+                }
+                DenseRegisters::Packed6(packed)
+            }
+            RegisterBackend::Hll4 => DenseRegisters::Hll4(Hll4Registers::from_values(&values)),
+        }
+    }
+
+    pub(crate) fn backend(&self) -> RegisterBackend {
+        match self {
+            DenseRegisters::U8(_) => RegisterBackend::U8,
+            DenseRegisters::Packed6(_) => RegisterBackend::Packed6,
+            DenseRegisters::Hll4(_) => RegisterBackend::Hll4,
+        }
+    }
+
+    /// Same registers in another layout.
+    pub(crate) fn convert(&self, backend: RegisterBackend) -> Self {
+        if self.backend() == backend {
+            return self.clone();
+        }
+        Self::from_values(backend, self.to_vec())
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        match self {
+            DenseRegisters::U8(values) => values.len(),
+            DenseRegisters::Packed6(packed) => packed.len,
+            DenseRegisters::Hll4(hll4) => hll4.len,
+        }
+    }
+
+    pub(crate) fn size_in_bytes(&self) -> usize {
+        match self {
+            DenseRegisters::Hll4(hll4) => hll4.size_in_bytes(),
+            _ => self.backend().size_in_bytes(self.len()).expect("allocated registers have a representable size"),
+        }
+    }
+
+    pub(crate) fn get(&self, idx: usize) -> u8 {
+        match self {
+            DenseRegisters::U8(values) => values[idx],
+            DenseRegisters::Packed6(packed) => packed.get(idx),
+            DenseRegisters::Hll4(hll4) => hll4.get(idx),
+        }
+    }
+
+    /// Raises register `idx` to `value` if it is larger.
+    pub(crate) fn update(&mut self, idx: usize, value: u8) {
+        match self {
+            DenseRegisters::U8(values) => values[idx] = values[idx].max(value),
+            DenseRegisters::Packed6(packed) => {
+                if value > packed.get(idx) {
+                    packed.set(idx, value);
+                }
+            }
+            DenseRegisters::Hll4(hll4) => hll4.update(idx, value),
+        }
+    }
+
+    /// Keeps the maximum of each pair of registers, `other` can use any layout.
+    pub(crate) fn merge(&mut self, other: &DenseRegisters) {
+        match (self, other) {
+            (DenseRegisters::U8(values), DenseRegisters::U8(other_values)) => {
+                for (value, &other_value) in values.iter_mut().zip(other_values) {
+                    *value = (*value).max(other_value);
+                }
+            }
+            (this, other) => {
+                for idx in 0..other.len() {
+                    this.update(idx, other.get(idx));
+                }
+            }
+        }
+    }
+
+    /// Register values unpacked into one `u8` each.
+    pub(crate) fn to_vec(&self) -> Vec<u8> {
+        match self {
+            DenseRegisters::U8(values) => values.clone(),
+            DenseRegisters::Packed6(packed) => (0..packed.len).map(|i| packed.get(i)).collect(),
+            DenseRegisters::Hll4(hll4) => (0..hll4.len).map(|i| hll4.get(i)).collect(),
+        }
+    }
+
+    /// Appends the registers in their own layout: one byte per register for `U8`, the packed
+    /// bytes for `Packed6`, and for `Hll4` the base, the nibbles, then the exception count
+    /// followed by `(u32 index, u8 value)` pairs, integers being little-endian.
+    pub(crate) fn write_payload(&self, out: &mut Vec<u8>) {
+        match self {
+            DenseRegisters::U8(values) => out.extend_from_slice(values),
+            DenseRegisters::Packed6(packed) => out.extend_from_slice(&packed.bytes),
+            DenseRegisters::Hll4(hll4) => {
+                out.push(hll4.base);
+                out.extend_from_slice(&hll4.nibbles);
+                out.extend_from_slice(&(hll4.exceptions.len() as u32).to_le_bytes());
+                for &(idx, value) in &hll4.exceptions {
+                    out.extend_from_slice(&idx.to_le_bytes());
+                    out.push(value);
+                }
+            }
+        }
+    }
+
+    /// Reads `len` registers written by `write_payload` with `backend`, failing if the bytes
+    /// do not match the layout or a register is above `max_value`.
+    pub(crate) fn read_payload(backend: RegisterBackend, len: usize, max_value: u8, bytes: &[u8]) -> Result<Self, HyperLogLogError> {
+        let corrupt = |msg: &str| HyperLogLogError::CorruptPayload(format!("{:?} registers: {}", backend, msg));
+        let values = match backend {
+            RegisterBackend::U8 | RegisterBackend::Packed6 => {
+                let size = backend.size_in_bytes(len)?;
+                if bytes.len() != size {
+                    return Err(HyperLogLogError::BucketCountMismatch(size, bytes.len()));
+                }
+                if backend == RegisterBackend::U8 {
+                    bytes.to_vec()
+                } else {
+                    let packed = PackedRegisters { bytes: bytes.to_vec(), len };
+                    (0..len).map(|i| packed.get(i)).collect()
+                }
+            }
+            RegisterBackend::Hll4 => {
+                let (&base, rest) = bytes.split_first().ok_or_else(|| corrupt("truncated"))?;
+                let (nibbles, rest) = rest.split_at_checked(len.div_ceil(2)).ok_or_else(|| corrupt("truncated"))?;
+                let (count, mut rest) = rest.split_first_chunk::<4>().ok_or_else(|| corrupt("truncated"))?;
+                let count = u32::from_le_bytes(*count) as usize;
+                if rest.len() != count * 5 {
+                    return Err(corrupt("exception table length does not match its count"));
+                }
+
+                let mut exceptions: Vec<(u32, u8)> = Vec::with_capacity(count);
+                while let Some((idx, tail)) = rest.split_first_chunk::<4>() {
+                    let (&value, tail) = tail.split_first().ok_or_else(|| corrupt("truncated"))?;
+                    rest = tail;
+                    let idx = u32::from_le_bytes(*idx);
+                    if exceptions.last().is_some_and(|&(previous, _)| idx <= previous) {
+                        return Err(corrupt("exceptions are not sorted by index"));
+                    }
+                    exceptions.push((idx, value));
+                }
+
+                let hll4 = Hll4Registers { nibbles: nibbles.to_vec(), len, base, at_base: 0, exceptions };
+                let mut values = Vec::with_capacity(len);
+                for idx in 0..len {
+                    let value = match hll4.nibble(idx) {
+                        HLL4_EXCEPTION => hll4.exception(idx).ok_or_else(|| corrupt("missing exception"))?,
+                        nibble => base.checked_add(nibble).ok_or_else(|| corrupt("register overflows"))?,
+                    };
+                    values.push(value);
+                }
+                // every exception belongs to a marked register and does not fit in a nibble
+                let marked = (0..len).filter(|&idx| hll4.nibble(idx) == HLL4_EXCEPTION).count();
+                if marked != count || hll4.exceptions.iter().any(|&(_, v)| v < base.saturating_add(HLL4_EXCEPTION)) {
+                    return Err(corrupt("exception table does not match the marked registers"));
+                }
+                values
+            }
+        };
+
+        if let Some(&value) = values.iter().find(|&&v| v > max_value) {
+            return Err(HyperLogLogError::RegisterOutOfRange(value, max_value));
+        }
+        Ok(Self::from_values(backend, values))
+    }
+
+    /// Adds the register values to `histogram`, `histogram[k]` counting registers equal to `k`.
+    pub(crate) fn fill_histogram(&self, histogram: &mut [u32]) {
+        match self {
+            DenseRegisters::U8(values) => {
+                for &v in values {
+                    histogram[v as usize] += 1;
+                }
+            }
+            DenseRegisters::Packed6(packed) => {
+                for i in 0..packed.len {
+                    histogram[packed.get(i) as usize] += 1;
+                }
+            }
+            DenseRegisters::Hll4(hll4) => {
+                for i in 0..hll4.len {
+                    histogram[hll4.get(i) as usize] += 1;
+                }
+            }
+        }
+    }
+}
+
+/// Registers packed on 6 bits each, register `i` taking bits `6i..6i+6` of the little-endian byte stream.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct PackedRegisters {
+    bytes: Vec<u8>,
+    len: usize, // number of registers
+}
+
+impl PackedRegisters {
+    fn new(len: usize) -> Self {
+        let size = RegisterBackend::Packed6.size_in_bytes(len).expect("register count of a supported precision");
+        Self { bytes: vec![0u8; size], len }
+    }
+
+    fn get(&self, idx: usize) -> u8 {
+        let bit = idx * PACKED_BITS;
+        let (byte, shift) = (bit / 8, bit % 8);
+        let mut word = self.bytes[byte] as u16;
+        // the register spills into the next byte
+        if shift + PACKED_BITS > 8 {
+            word |= (self.bytes[byte + 1] as u16) << 8;
+        }
+        ((word >> shift) & PACKED_MASK) as u8
+    }
+
+    fn set(&mut self, idx: usize, value: u8) {
+        let bit = idx * PACKED_BITS;
+        let (byte, shift) = (bit / 8, bit % 8);
+        let value = value as u16 & PACKED_MASK;
+
+        self.bytes[byte] = (self.bytes[byte] & !((PACKED_MASK << shift) as u8)) | (value << shift) as u8;
+        if shift + PACKED_BITS > 8 {
+            let spill = 8 - shift;
+            self.bytes[byte + 1] = (self.bytes[byte + 1] & !((PACKED_MASK >> spill) as u8)) | (value >> spill) as u8;
+        }
+    }
+}
+
+// nibble value marking a register stored in the exception table
+const HLL4_EXCEPTION: u8 = 15;
+
+/// 4-bit registers stored relative to a shared `base`, two per byte (even index in the low nibble).
+/// A register holds `value - base`; values at or above `base + 15` are marked with `HLL4_EXCEPTION`
+/// and kept in `exceptions`. Once no register is left at `base`, the base is raised so that
+/// the nibbles keep covering the range where most registers sit.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Hll4Registers {
+    nibbles: Vec<u8>,
+    len: usize, // number of registers
+    base: u8, // value every nibble is relative to
+    at_base: usize, // number of registers equal to `base`
+    exceptions: Vec<(u32, u8)>, // full values of the registers that overflow the nibble, sorted by index
+}
+
+impl Hll4Registers {
+    fn new(len: usize) -> Self {
+        Self {
+            nibbles: vec![0u8; len.div_ceil(2)],
+            len,
+            base: 0,
+            at_base: len,
+            exceptions: Vec::new(),
+        }
+    }
+
+    fn from_values(values: &[u8]) -> Self {
+        let mut hll4 = Self::new(values.len());
+        hll4.rebuild(values);
+        hll4
+    }
+
+    fn size_in_bytes(&self) -> usize {
+        self.nibbles.len() + self.exceptions.len() * std::mem::size_of::<(u32, u8)>()
+    }
+
+    fn exception(&self, idx: usize) -> Option<u8> {
+        let found = self.exceptions.binary_search_by_key(&(idx as u32), |&(i, _)| i);
+        found.ok().map(|pos| self.exceptions[pos].1)
+    }
+
+    fn nibble(&self, idx: usize) -> u8 {
+        (self.nibbles[idx / 2] >> ((idx % 2) * 4)) & 0x0f
+    }
+
+    fn set_nibble(&mut self, idx: usize, nibble: u8) {
+        let shift = (idx % 2) * 4;
+        let byte = &mut self.nibbles[idx / 2];
+        *byte = (*byte & !(0x0f << shift)) | (nibble << shift);
+    }
+
+    fn get(&self, idx: usize) -> u8 {
+        match self.nibble(idx) {
+            HLL4_EXCEPTION => self.exception(idx).expect("marked registers have an exception"),
+            nibble => self.base + nibble,
+        }
+    }
+
+    /// Stores `value` for register `idx`, which must be at least `base`.
+    fn store(&mut self, idx: usize, value: u8) {
+        let offset = value - self.base;
+        let found = self.exceptions.binary_search_by_key(&(idx as u32), |&(i, _)| i);
+        if offset >= HLL4_EXCEPTION {
+            self.set_nibble(idx, HLL4_EXCEPTION);
+            match found {
+                Ok(pos) => self.exceptions[pos].1 = value,
+                Err(pos) => self.exceptions.insert(pos, (idx as u32, value)),
+            }
+        } else {
+            self.set_nibble(idx, offset);
+            if let Ok(pos) = found {
+                self.exceptions.remove(pos);
+            }
+        }
+    }
+
+    fn update(&mut self, idx: usize, value: u8) {
+        let current = self.get(idx);
+        if value <= current {
+            return;
+        }
+
+        self.store(idx, value);
+        if current == self.base {
+            self.at_base -= 1;
+            if self.at_base == 0 {
+                let values: Vec<u8> = (0..self.len).map(|i| self.get(i)).collect();
+                self.rebuild(&values);
+            }
+        }
+    }
+
+    /// Re-encodes all registers around the smallest value.
+    fn rebuild(&mut self, values: &[u8]) {
+        self.base = values.iter().copied().min().unwrap_or(0);
+        self.at_base = values.iter().filter(|&&v| v == self.base).count();
+        self.exceptions.clear();
+        for (idx, &value) in values.iter().enumerate() {
+            self.store(idx, value);
+        }
+    }
+}
@@ -0,0 +1,424 @@
+use crate::estimator::{self, Estimator};
+
+/// Largest register value plus one, the side of the joint histogram.
+pub(crate) const REGISTER_VALUES: usize = 65;
+
+/// Joint histogram of two sketches with the same precision,
+/// `counts[a][b]` being the number of buckets holding `a` in the first sketch and `b` in the second.
+pub(crate) type JointHistogram = Vec<[u32; REGISTER_VALUES]>;
+
+/// Algorithm used to estimate intersections and differences between two sketches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SetEstimator {
+    /// `|A ∩ B| = |A| + |B| - |A ∪ B|`, each term estimated separately.
+    /// Cheap, but the error of the terms swamps small overlaps.
+    InclusionExclusion,
+    /// Ertl's joint maximum-likelihood estimator: fits the cardinalities of `A \ B`, `B \ A` and
+    /// `A ∩ B` to the pairs of registers at once. Much more accurate for small overlaps.
+    #[default]
+    JointMaximumLikelihood,
+}
+
+/// An estimated cardinality (or ratio) with its standard error.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SetEstimate {
+    pub value: f64,
+    pub standard_error: f64,
+}
+
+/// Estimates of all the parts of two sets `A` (the sketch the method is called on) and `B`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct JointEstimate {
+    /// `|A \ B|`
+    pub only_self: SetEstimate,
+    /// `|B \ A|`
+    pub only_other: SetEstimate,
+    /// `|A ∩ B|`
+    pub intersection: SetEstimate,
+    /// `|A ∪ B|`
+    pub union: SetEstimate,
+    /// `|A ∩ B| / |A|`, zero when `A` is empty
+    pub containment: SetEstimate,
+    /// Jaccard similarity `|A ∩ B| / |A ∪ B|`, zero when both sets are empty
+    pub jaccard: SetEstimate,
+}
+
+/// Counts the pairs of register values of two sketches.
+pub(crate) fn joint_histogram(a: &[u8], b: &[u8]) -> JointHistogram {
+    let mut counts = vec![[0u32; REGISTER_VALUES]; REGISTER_VALUES];
+    for (&x, &y) in a.iter().zip(b) {
+        counts[x as usize][y as usize] += 1;
+    }
+    counts
+}
+
+/// Estimates the parts of `A` and `B` from their joint histogram.
+pub(crate) fn estimate(counts: &JointHistogram, p: u32, single: Estimator, set_estimator: SetEstimator) -> JointEstimate {
+    let inclusion_exclusion = inclusion_exclusion(counts, p, single);
+
+    match set_estimator {
+        SetEstimator::InclusionExclusion => inclusion_exclusion,
+        SetEstimator::JointMaximumLikelihood => {
+            joint_maximum_likelihood(counts, p, &inclusion_exclusion).unwrap_or(inclusion_exclusion)
+        }
+    }
+}
+
+fn inclusion_exclusion(counts: &JointHistogram, p: u32, single: Estimator) -> JointEstimate {
+    let mut hist_a = [0u32; REGISTER_VALUES];
+    let mut hist_b = [0u32; REGISTER_VALUES];
+    let mut hist_union = [0u32; REGISTER_VALUES];
+    for (a, row) in counts.iter().enumerate() {
+        for (b, &count) in row.iter().enumerate() {
+            hist_a[a] += count;
+            hist_b[b] += count;
+            hist_union[a.max(b)] += count;
+        }
+    }
+
+    let a = estimator::estimate(single, &hist_a, p).cardinality;
+    let b = estimator::estimate(single, &hist_b, p).cardinality;
+    let union = estimator::estimate(single, &hist_union, p).cardinality;
+    let rse = estimator::relative_standard_error(p);
+
+    // the terms are treated as independent, which overstates the error a little
+    let intersection = SetEstimate {
+        value: (a + b - union).clamp(0.0, a.min(b)),
+        standard_error: rse * (a * a + b * b + union * union).sqrt(),
+    };
+    let only_self = SetEstimate {
+        value: (union - b).clamp(0.0, a),
+        standard_error: rse * (union * union + b * b).sqrt(),
+    };
+    let only_other = SetEstimate {
+        value: (union - a).clamp(0.0, b),
+        standard_error: rse * (union * union + a * a).sqrt(),
+    };
+    let containment = if a > 0.0 {
+        SetEstimate {
+            value: intersection.value / a,
+            standard_error: ((intersection.standard_error / a).powi(2)
+                + (intersection.value * rse / a).powi(2)).sqrt(),
+        }
+    } else {
+        SetEstimate { value: 0.0, standard_error: 0.0 }
+    };
+
+    let union = SetEstimate { value: union, standard_error: rse * union };
+    let jaccard = jaccard(intersection, union);
+
+    JointEstimate { only_self, only_other, intersection, union, containment, jaccard }
+}
+
+/// `intersection / union`, the terms being treated as independent.
+fn jaccard(intersection: SetEstimate, union: SetEstimate) -> SetEstimate {
+    if union.value <= 0.0 {
+        return SetEstimate { value: 0.0, standard_error: 0.0 };
+    }
+    let value = (intersection.value / union.value).min(1.0);
+    SetEstimate {
+        value,
+        standard_error: ((intersection.standard_error / union.value).powi(2)
+            + (value * union.standard_error / union.value).powi(2)).sqrt(),
+    }
+}
+
+// bounds on the log of the fitted cardinalities, e^-10 stands for an empty part
+const MIN_LOG_CARDINALITY: f64 = -10.0;
+const MAX_LOG_CARDINALITY: f64 = 64.0 * std::f64::consts::LN_2;
+
+/// Ertl's joint maximum-likelihood estimate, see
+/// "New cardinality estimation methods for HyperLogLog sketches" (Otmar Ertl, 2017).
+///
+/// Under the Poisson model, the register of `A` is `max(U, X)` and the one of `B` is `max(V, X)`,
+/// with `U`, `V` and `X` the largest ranks seen in that bucket from `A \ B`, `B \ A` and `A ∩ B`.
+/// Returns `None` when the fit does not converge, callers fall back to inclusion-exclusion.
+fn joint_maximum_likelihood(counts: &JointHistogram, p: u32, start: &JointEstimate) -> Option<JointEstimate> {
+    let model = PoissonModel::new(p);
+    // only the register pairs that occur matter
+    let pairs: Vec<(usize, usize, f64)> = counts.iter()
+        .enumerate()
+        .flat_map(|(a, row)| row.iter().enumerate().map(move |(b, &count)| (a, b, count)))
+        .filter(|&(_, _, count)| count > 0)
+        .map(|(a, b, count)| (a, b, count as f64))
+        .collect();
+
+    // both sketches empty, nothing to fit
+    if pairs.len() == 1 && pairs[0].0 == 0 && pairs[0].1 == 0 {
+        let zero = SetEstimate { value: 0.0, standard_error: 0.0 };
+        return Some(JointEstimate {
+            only_self: zero,
+            only_other: zero,
+            intersection: zero,
+            union: zero,
+            containment: zero,
+            jaccard: zero,
+        });
+    }
+
+    let log_likelihood = |theta: &[f64; 3]| -> f64 {
+        let [only_a, only_b, both] = theta.map(|t| t.clamp(MIN_LOG_CARDINALITY, MAX_LOG_CARDINALITY).exp());
+        pairs.iter()
+            .map(|&(a, b, count)| count * model.log_pair_probability(a, b, only_a, only_b, both))
+            .sum()
+    };
+
+    let initial = [start.only_self.value, start.only_other.value, start.intersection.value]
+        .map(|v| v.max(1.0).ln());
+    let theta = nelder_mead(|t| -log_likelihood(t), initial)?;
+    // a part pushed against the lower bound is empty
+    let lambda = theta.map(|t| if t <= MIN_LOG_CARDINALITY { 0.0 } else { t.min(MAX_LOG_CARDINALITY).exp() });
+
+    // covariance from the observed information, taken in linear space so that it stays
+    // meaningful for parts fitted to zero
+    let log_likelihood_linear = |l: &[f64; 3]| log_likelihood(&l.map(|v| v.max(f64::MIN_POSITIVE).ln()));
+    let steps = lambda.map(|l| 1e-3 * l.max(1.0));
+    let center = [0, 1, 2].map(|i| lambda[i].max(steps[i]));
+    let covariance = negative_hessian(&log_likelihood_linear, &center, &steps)
+        .and_then(|h| invert(&h))
+        .filter(|c| (0..3).all(|i| c[i][i].is_finite() && c[i][i] >= 0.0));
+
+    let [only_a, only_b, both] = lambda;
+    let (mut se_a, mut se_b, mut se_x, se_union, mut se_containment, mut se_jaccard) = match covariance {
+        Some(c) => {
+            let union_var = (0..3).flat_map(|i| (0..3).map(move |j| (i, j))).map(|(i, j)| c[i][j]).sum::<f64>();
+            // containment x / (a + x), gradient (-x, a) / (a + x)^2 over (a, x)
+            let total = only_a + both;
+            let containment_var = if total > 0.0 {
+                let (ga, gx) = (-both / total.powi(2), only_a / total.powi(2));
+                ga * ga * c[0][0] + 2.0 * ga * gx * c[0][2] + gx * gx * c[2][2]
+            } else {
+                0.0
+            };
+            // jaccard x / (a + b + x), gradient (-x, -x, a + b) / (a + b + x)^2
+            let union = only_a + only_b + both;
+            let jaccard_var = if union > 0.0 {
+                let gradient = [-both, -both, only_a + only_b].map(|g| g / union.powi(2));
+                (0..3).flat_map(|i| (0..3).map(move |j| (i, j))).map(|(i, j)| gradient[i] * gradient[j] * c[i][j]).sum::<f64>()
+            } else {
+                0.0
+            };
+            (
+                c[0][0].sqrt(),
+                c[1][1].sqrt(),
+                c[2][2].sqrt(),
+                union_var.max(0.0).sqrt(),
+                containment_var.max(0.0).sqrt(),
+                jaccard_var.max(0.0).sqrt(),
+            )
+        }
+        // flat likelihood: keep the inclusion-exclusion errors
+        None => (
+            start.only_self.standard_error,
+            start.only_other.standard_error,
+            start.intersection.standard_error,
+            start.union.standard_error,
+            start.containment.standard_error,
+            start.jaccard.standard_error,
+        ),
+    };
+
+    // the curvature says little about a part fitted to zero, use the size it can grow to
+    // before the likelihood drops by one standard deviation instead
+    let best = log_likelihood_linear(&lambda);
+    let boundary_error = |i: usize| {
+        let drop = |t: f64| {
+            let mut l = lambda;
+            l[i] = t;
+            best - log_likelihood_linear(&l) - 0.5
+        };
+        // bisection on the log scale
+        let (mut lo, mut hi) = (1e-3f64, MAX_LOG_CARDINALITY.exp());
+        if drop(lo) >= 0.0 {
+            return lo;
+        }
+        for _ in 0..200 {
+            let mid = (lo * hi).sqrt();
+            if drop(mid) < 0.0 { lo = mid } else { hi = mid }
+        }
+        hi
+    };
+    if only_a == 0.0 {
+        se_a = boundary_error(0);
+        se_containment = if both > 0.0 { se_a / both } else { se_containment };
+    }
+    if only_b == 0.0 {
+        se_b = boundary_error(1);
+    }
+    if both == 0.0 {
+        se_x = boundary_error(2);
+        se_containment = if only_a > 0.0 { se_x / only_a } else { se_containment };
+        se_jaccard = if only_a + only_b > 0.0 { se_x / (only_a + only_b) } else { se_jaccard };
+    }
+
+    let containment = if only_a + both > 0.0 { both / (only_a + both) } else { 0.0 };
+    let union = only_a + only_b + both;
+    let jaccard = if union > 0.0 { both / union } else { 0.0 };
+
+    Some(JointEstimate {
+        only_self: SetEstimate { value: only_a, standard_error: se_a },
+        only_other: SetEstimate { value: only_b, standard_error: se_b },
+        intersection: SetEstimate { value: both, standard_error: se_x },
+        union: SetEstimate { value: union, standard_error: se_union },
+        containment: SetEstimate { value: containment, standard_error: se_containment },
+        jaccard: SetEstimate { value: jaccard, standard_error: se_jaccard },
+    })
+}
+
+/// Distribution of the largest rank seen in one bucket when `lambda` distinct items are spread over `m` buckets.
+struct PoissonModel {
+    m: f64,
+    q: usize, // ranks go from 0 to q + 1
+}
+
+impl PoissonModel {
+    fn new(p: u32) -> Self {
+        Self { m: (1u64 << p) as f64, q: (64 - p) as usize }
+    }
+
+    /// Expected number of items of the bucket with rank above `k`: lambda / m * 2^-k, none above q + 1.
+    fn rate_above(&self, lambda: f64, k: usize) -> f64 {
+        if k > self.q {
+            0.0
+        } else {
+            lambda / self.m * 2f64.powi(-(k as i32))
+        }
+    }
+
+    /// P(max rank <= k)
+    fn cdf(&self, lambda: f64, k: usize) -> f64 {
+        (-self.rate_above(lambda, k)).exp()
+    }
+
+    /// P(max rank = k), written with expm1 to keep precision when the rate is small.
+    fn pmf(&self, lambda: f64, k: usize) -> f64 {
+        if k == 0 {
+            return self.cdf(lambda, 0);
+        }
+        let below = self.rate_above(lambda, k - 1);
+        let at_or_below = self.rate_above(lambda, k);
+        // e^-at_or_below - e^-below = e^-at_or_below * (1 - e^-(below - at_or_below))
+        (-at_or_below).exp() * -(-(below - at_or_below)).exp_m1()
+    }
+
+    /// log P(register of A = a, register of B = b)
+    fn log_pair_probability(&self, a: usize, b: usize, only_a: f64, only_b: f64, both: f64) -> f64 {
+        let probability = match a.cmp(&b) {
+            // B is larger, so it comes from B \ A and A = max(U, X)
+            std::cmp::Ordering::Less => self.pmf(only_b, b) * self.pmf(only_a + both, a),
+            std::cmp::Ordering::Greater => self.pmf(only_a, a) * self.pmf(only_b + both, b),
+            // either the intersection reached k, or it stayed below and both others reached k
+            std::cmp::Ordering::Equal => {
+                let below = if a == 0 { 0.0 } else { self.cdf(both, a - 1) };
+                self.pmf(both, a) * self.cdf(only_a, a) * self.cdf(only_b, a)
+                    + below * self.pmf(only_a, a) * self.pmf(only_b, a)
+            }
+        };
+        probability.max(f64::MIN_POSITIVE).ln()
+    }
+}
+
+/// Minimises `f` with the Nelder-Mead simplex method.
+fn nelder_mead(f: impl Fn(&[f64; 3]) -> f64, initial: [f64; 3]) -> Option<[f64; 3]> {
+    const MAX_ITERATIONS: usize = 5000;
+    const TOLERANCE: f64 = 1e-10;
+
+    let mut simplex: Vec<([f64; 3], f64)> = (0..4)
+        .map(|i| {
+            let mut point = initial;
+            if i > 0 {
+                point[i - 1] += 1.0;
+            }
+            (point, f(&point))
+        })
+        .collect();
+
+    for _ in 0..MAX_ITERATIONS {
+        simplex.sort_by(|x, y| x.1.total_cmp(&y.1));
+        let (best, worst) = (simplex[0].1, simplex[3].1);
+        let size = simplex.iter()
+            .map(|(point, _)| (0..3).map(|i| (point[i] - simplex[0].0[i]).abs()).fold(0.0, f64::max))
+            .fold(0.0, f64::max);
+        if (worst - best).abs() <= TOLERANCE * (1.0 + best.abs()) && size < 1e-7 {
+            return Some(simplex[0].0);
+        }
+
+        let mut centroid = [0.0; 3];
+        for (point, _) in &simplex[..3] {
+            for i in 0..3 {
+                centroid[i] += point[i] / 3.0;
+            }
+        }
+        let along = |t: f64| -> [f64; 3] {
+            let mut point = [0.0; 3];
+            for i in 0..3 {
+                point[i] = centroid[i] + t * (simplex[3].0[i] - centroid[i]);
+            }
+            point
+        };
+
+        let reflected = along(-1.0);
+        let reflected_value = f(&reflected);
+        if reflected_value < simplex[0].1 {
+            let expanded = along(-2.0);
+            let expanded_value = f(&expanded);
+            simplex[3] = if expanded_value < reflected_value { (expanded, expanded_value) } else { (reflected, reflected_value) };
+        } else if reflected_value < simplex[2].1 {
+            simplex[3] = (reflected, reflected_value);
+        } else {
+            let contracted = along(0.5);
+            let contracted_value = f(&contracted);
+            if contracted_value < simplex[3].1 {
+                simplex[3] = (contracted, contracted_value);
+            } else {
+                // shrink towards the best point
+                let best_point = simplex[0].0;
+                for (point, value) in simplex.iter_mut().skip(1) {
+                    for i in 0..3 {
+                        point[i] = best_point[i] + 0.5 * (point[i] - best_point[i]);
+                    }
+                    *value = f(point);
+                }
+            }
+        }
+    }
+
+    None
+}
+
+/// Negative Hessian of `f` at `x` by central differences with the given `steps`, `None` if it is not finite.
+fn negative_hessian(f: &impl Fn(&[f64; 3]) -> f64, x: &[f64; 3], steps: &[f64; 3]) -> Option<[[f64; 3]; 3]> {
+    let at = |i: usize, di: f64, j: usize, dj: f64| {
+        let mut point = *x;
+        point[i] += di * steps[i];
+        point[j] += dj * steps[j];
+        f(&point)
+    };
+
+    let mut hessian = [[0.0; 3]; 3];
+    for (i, row) in hessian.iter_mut().enumerate() {
+        for (j, value) in row.iter_mut().enumerate() {
+            let second = (at(i, 1.0, j, 1.0) - at(i, 1.0, j, -1.0) - at(i, -1.0, j, 1.0) + at(i, -1.0, j, -1.0))
+                / (4.0 * steps[i] * steps[j]);
+            *value = -second;
+        }
+    }
+
+    hessian.iter().flatten().all(|v| v.is_finite()).then_some(hessian)
+}
+
+/// Inverse of a 3x3 matrix, `None` if it is singular.
+fn invert(m: &[[f64; 3]; 3]) -> Option<[[f64; 3]; 3]> {
+    let cofactor = |r0: usize, r1: usize, c0: usize, c1: usize| m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0];
+    let det = m[0][0] * cofactor(1, 2, 1, 2) - m[0][1] * cofactor(1, 2, 0, 2) + m[0][2] * cofactor(1, 2, 0, 1);
+    if det == 0.0 || !det.is_finite() {
+        return None;
+    }
+
+    let adjugate = [
+        [cofactor(1, 2, 1, 2), -cofactor(0, 2, 1, 2), cofactor(0, 1, 1, 2)],
+        [-cofactor(1, 2, 0, 2), cofactor(0, 2, 0, 2), -cofactor(0, 1, 0, 2)],
+        [cofactor(1, 2, 0, 1), -cofactor(0, 2, 0, 1), cofactor(0, 1, 0, 1)],
+    ];
+    Some(adjugate.map(|row| row.map(|v| v / det)))
+}
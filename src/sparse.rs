@@ -0,0 +1,238 @@
+use std::borrow::Cow;
+
+use crate::error::HyperLogLogError;
+use crate::registers::{DenseRegisters, RegisterBackend};
+
+/// Precision used for the sparse representation, as in HyperLogLog++.
+/// Indices are kept at 25 bits so that converting to any dense precision `p <= 25`
+/// can recompute the exact dense rank from the dropped index bits.
+pub const SPARSE_PRECISION: u32 = 25;
+
+// number of low bits of an entry used to store the rank
+const RANK_BITS: u32 = 6;
+const RANK_MASK: u32 = (1 << RANK_BITS) - 1;
+
+/// Sparse register storage: a sorted list of `(index, rank)` pairs at `SPARSE_PRECISION`,
+/// each packed into a single `u32` as `index << 6 | rank`.
+///
+/// As in HyperLogLog++, new entries go to an unsorted buffer, which is sorted and merged into the
+/// list once full. The sketch gives the sparse form a budget of `max_len` entries, the size of its
+/// dense form, and the buffer takes a quarter of it, so that merging stays cheap however long the
+/// list and the list and buffer together never outgrow the dense form.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct SparseRegisters {
+    entries: Vec<u32>, // sorted by index, at most one entry per index
+    temp: Vec<u32>, // unsorted, possibly several entries per index
+}
+
+impl SparseRegisters {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Rebuilds sparse registers from previously encoded entries, which must be sorted by index
+    /// with at most one entry per index, and hold a rank between 1 and the largest sparse rank.
+    pub(crate) fn from_entries(entries: Vec<u32>) -> Result<Self, HyperLogLogError> {
+        let max_rank = 64 - SPARSE_PRECISION + 1;
+        let mut previous_index = None;
+        for &entry in &entries {
+            let (index, rank) = (entry >> RANK_BITS, entry & RANK_MASK);
+            let sorted = previous_index.is_none_or(|previous| index > previous);
+            if rank == 0 || rank > max_rank || index >= 1 << SPARSE_PRECISION || !sorted {
+                return Err(HyperLogLogError::InvalidSparseEntry(entry));
+            }
+            previous_index = Some(index);
+        }
+        Ok(Self { entries, temp: Vec::new() })
+    }
+
+    /// The encoded entries, sorted by index, including the buffered ones.
+    pub(crate) fn entries(&self) -> Cow<'_, [u32]> {
+        if self.temp.is_empty() {
+            Cow::Borrowed(&self.entries)
+        } else {
+            Cow::Owned(merge_sorted(&self.entries, &sorted_temp(&self.temp)))
+        }
+    }
+
+    /// Whether there are no entries, buffered or not.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.entries.is_empty() && self.temp.is_empty()
+    }
+
+    /// Memory used by the entries, including the buffered ones.
+    pub(crate) fn size_in_bytes(&self) -> usize {
+        (self.entries.len() + self.temp.len()) * std::mem::size_of::<u32>()
+    }
+
+    /// Whether the merged entries and a full buffer fit in a budget of `max_len` entries.
+    /// Sparse registers that do not should be converted to dense ones.
+    pub(crate) fn fits(&self, max_len: usize) -> bool {
+        self.entries.len() + temp_capacity(max_len) <= max_len
+    }
+
+    /// Records `rank` for the sparse `index`, keeping the maximum rank seen, within a budget of `max_len` entries.
+    pub(crate) fn update(&mut self, index: u32, rank: u8, max_len: usize) {
+        self.temp.push(encode(index, rank));
+        if self.temp.len() >= temp_capacity(max_len) {
+            self.flush();
+        }
+    }
+
+    /// Merges the buffered entries into the sorted list.
+    fn flush(&mut self) {
+        if !self.temp.is_empty() {
+            self.entries = merge_sorted(&self.entries, &sorted_temp(&self.temp));
+            self.temp.clear();
+        }
+    }
+
+    /// Unions `other` into `self`, keeping the maximum rank per index.
+    pub(crate) fn merge(&mut self, other: &SparseRegisters) {
+        self.flush();
+        self.entries = merge_sorted(&self.entries, &other.entries());
+    }
+
+    /// Iterates over the `(index, rank)` pairs projected onto the dense precision `p`.
+    /// Several sparse entries can map to the same dense index; they come out adjacent.
+    pub(crate) fn dense_entries(&self, p: u32) -> impl Iterator<Item = (usize, u8)> + '_ {
+        let entries = self.entries();
+        (0..entries.len()).map(move |i| to_dense(entries[i], p))
+    }
+
+    /// Expands the sparse registers into a dense bucket vector of size `2^p`.
+    pub(crate) fn to_dense(&self, p: u32) -> Vec<u8> {
+        let mut buckets = vec![0u8; 1usize << p];
+        for (idx, rank) in self.dense_entries(p) {
+            buckets[idx] = buckets[idx].max(rank);
+        }
+        buckets
+    }
+
+    /// Expands the sparse registers into `2^p` dense registers laid out with `backend`.
+    pub(crate) fn to_registers(&self, p: u32, backend: RegisterBackend) -> DenseRegisters {
+        let mut registers = DenseRegisters::new(backend, 1usize << p);
+        for (idx, rank) in self.dense_entries(p) {
+            registers.update(idx, rank);
+        }
+        registers
+    }
+
+    /// Appends the entry count and the entries, each as a LEB128 varint of its difference
+    /// from the previous entry. Sorted entries keep the differences small.
+    pub(crate) fn write_payload(&self, out: &mut Vec<u8>) {
+        let entries = self.entries();
+        out.extend_from_slice(&(entries.len() as u32).to_le_bytes());
+        let mut previous = 0u32;
+        for &entry in entries.iter() {
+            let mut delta = entry - previous;
+            while delta >= 0x80 {
+                out.push((delta as u8 & 0x7f) | 0x80);
+                delta >>= 7;
+            }
+            out.push(delta as u8);
+            previous = entry;
+        }
+    }
+
+    /// Reads back what `write_payload` wrote, checking the entries as `from_entries` does.
+    pub(crate) fn read_payload(bytes: &[u8]) -> Result<Self, HyperLogLogError> {
+        let truncated = || HyperLogLogError::CorruptPayload("truncated sparse entries".to_string());
+        let (count, mut rest) = bytes.split_first_chunk::<4>().ok_or_else(truncated)?;
+        let count = u32::from_le_bytes(*count) as usize;
+        // every entry takes at least one byte
+        if count > rest.len() {
+            return Err(truncated());
+        }
+
+        let mut entries = Vec::with_capacity(count);
+        let mut previous = 0u32;
+        for _ in 0..count {
+            let mut delta = 0u32;
+            let mut shift = 0;
+            loop {
+                let (&byte, tail) = rest.split_first().ok_or_else(truncated)?;
+                rest = tail;
+                // a u32 fits in five bytes, the last one holding 4 bits
+                if shift == 28 && byte & 0xf0 != 0 {
+                    return Err(HyperLogLogError::CorruptPayload("sparse entry overflows 32 bits".to_string()));
+                }
+                delta |= ((byte & 0x7f) as u32) << shift;
+                shift += 7;
+                if byte & 0x80 == 0 {
+                    break;
+                }
+            }
+
+            previous = previous.checked_add(delta)
+                .ok_or_else(|| HyperLogLogError::CorruptPayload("sparse entry overflows 32 bits".to_string()))?;
+            entries.push(previous);
+        }
+
+        if !rest.is_empty() {
+            return Err(HyperLogLogError::CorruptPayload(format!("{} bytes after the sparse entries", rest.len())));
+        }
+        Self::from_entries(entries)
+    }
+}
+
+/// Number of new entries buffered before a merge, within a budget of `max_len` entries.
+fn temp_capacity(max_len: usize) -> usize {
+    (max_len / 4).max(1)
+}
+
+/// Sorts buffered entries by index, keeping only the maximum rank of each index.
+fn sorted_temp(temp: &[u32]) -> Vec<u32> {
+    let mut sorted = temp.to_vec();
+    // the rank is in the low bits, so the largest one of an index comes last
+    sorted.sort_unstable();
+    sorted.dedup_by(|next, kept| {
+        let same_index = *next >> RANK_BITS == *kept >> RANK_BITS;
+        if same_index {
+            *kept = *next;
+        }
+        same_index
+    });
+    sorted
+}
+
+/// Unions two lists sorted by index, keeping the maximum rank per index.
+fn merge_sorted(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut merged = Vec::with_capacity(a.len() + b.len());
+    let (mut i, mut j) = (0, 0);
+
+    while i < a.len() && j < b.len() {
+        match (a[i] >> RANK_BITS).cmp(&(b[j] >> RANK_BITS)) {
+            std::cmp::Ordering::Less => { merged.push(a[i]); i += 1; }
+            std::cmp::Ordering::Greater => { merged.push(b[j]); j += 1; }
+            std::cmp::Ordering::Equal => { merged.push(a[i].max(b[j])); i += 1; j += 1; }
+        }
+    }
+    merged.extend_from_slice(&a[i..]);
+    merged.extend_from_slice(&b[j..]);
+    merged
+}
+
+fn encode(index: u32, rank: u8) -> u32 {
+    (index << RANK_BITS) | (rank as u32 & RANK_MASK)
+}
+
+/// Converts one sparse entry into its dense `(index, rank)` at precision `p`.
+fn to_dense(entry: u32, p: u32) -> (usize, u8) {
+    let sparse_idx = entry >> RANK_BITS;
+    let sparse_rank = entry & RANK_MASK;
+    // bits of the sparse index that are not part of the dense index
+    let shift = SPARSE_PRECISION - p;
+    let idx = (sparse_idx >> shift) as usize;
+    let low = sparse_idx & ((1u32 << shift) - 1);
+
+    // if any dropped index bit is set, the dense rank is found among them,
+    // otherwise all of them count as leading zeros on top of the sparse rank
+    let rank = if low != 0 {
+        low.leading_zeros() - (32 - shift) + 1
+    } else {
+        shift + sparse_rank
+    };
+
+    (idx, rank as u8)
+}
@@ -0,0 +1,148 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, BuildHasherDefault, DefaultHasher, Hasher};
+
+use xxhash_rust::xxh3::{Xxh3, Xxh3DefaultBuilder};
+
+/// Name recorded for sketches hashed with `StableHasherBuilder`.
+pub const STABLE_HASH_ALGORITHM: &str = "xxh3-64";
+
+/// Seed of `StableHasherBuilder::default()`, the hasher of `HyperLogLog::new`.
+pub const DEFAULT_HASH_SEED: u64 = 0x9E37_79B9_7F4A_7C15;
+
+/// Hash function a sketch records next to its registers, so that a reader can tell which
+/// function filled them and refuse registers filled by another one.
+///
+/// The algorithm name must change whenever the function's output does; a hasher whose output
+/// is not specified should say so in its name, as `std-siphash-1-3` does.
+pub trait HasherIdentity {
+    /// Name and version of the hash function, e.g. `"xxh3-64"`.
+    fn algorithm(&self) -> &'static str;
+
+    /// Seed of the hash function, 0 for functions without one.
+    fn seed(&self) -> u64;
+}
+
+/// Hasher that can be rebuilt from what a sketch records about it, so that a sketch built with a
+/// seeded or keyed hasher loads with the same hasher rather than with `S::default()`.
+///
+/// `RandomState` cannot implement it, as the standard library does not expose its keys;
+/// `StableHasherBuilder::random` gives a random seed that can be persisted instead.
+///
+/// Serializing a sketch with serde needs it since 0.2.0. A hasher with no seed or parameters
+/// implements it with `restore` returning `Some(Self::default())` when `seed` is 0 and
+/// `parameters` is empty.
+pub trait HasherState: HasherIdentity + Sized {
+    /// Parameters besides the seed, e.g. keys or a precision the hash depends on. None by default.
+    fn parameters(&self) -> Vec<u8> {
+        Vec::new()
+    }
+
+    /// Rebuilds the hasher from a recorded seed and parameters, or `None` if they do not describe
+    /// a hasher of this type. The algorithm name is checked separately.
+    fn restore(seed: u64, parameters: &[u8]) -> Option<Self>;
+}
+
+/// `BuildHasher` for 64-bit XXH3 with an explicit seed, the default hasher of `HyperLogLog`.
+///
+/// XXH3 is a specified hash function, so unlike the standard library's `DefaultHasher` its output
+/// does not change across Rust or crate releases, and persisted sketches stay valid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StableHasherBuilder {
+    seed: u64,
+}
+
+impl StableHasherBuilder {
+    pub fn new(seed: u64) -> Self {
+        StableHasherBuilder { seed }
+    }
+
+    /// A builder with a random seed, drawn from the keys of a fresh `RandomState`. Unlike
+    /// `RandomState`, the seed is recorded with the sketch, which therefore reloads with it.
+    pub fn random() -> Self {
+        Self::new(RandomState::new().hash_one(DEFAULT_HASH_SEED))
+    }
+}
+
+impl Default for StableHasherBuilder {
+    fn default() -> Self {
+        Self::new(DEFAULT_HASH_SEED)
+    }
+}
+
+impl BuildHasher for StableHasherBuilder {
+    type Hasher = StableHasher;
+
+    fn build_hasher(&self) -> StableHasher {
+        StableHasher(Xxh3::with_seed(self.seed))
+    }
+}
+
+impl HasherIdentity for StableHasherBuilder {
+    fn algorithm(&self) -> &'static str {
+        STABLE_HASH_ALGORITHM
+    }
+
+    fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+impl HasherState for StableHasherBuilder {
+    fn restore(seed: u64, parameters: &[u8]) -> Option<Self> {
+        parameters.is_empty().then(|| Self::new(seed))
+    }
+}
+
+/// Hasher built by `StableHasherBuilder`: streaming XXH3, giving `XXH3_64bits_withSeed` of every
+/// written byte without buffering them.
+#[derive(Clone)]
+pub struct StableHasher(Xxh3);
+
+impl Hasher for StableHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.update(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        self.0.digest()
+    }
+}
+
+impl HasherIdentity for BuildHasherDefault<DefaultHasher> {
+    // the standard library does not specify this output, sketches using it may not survive a toolchain upgrade
+    fn algorithm(&self) -> &'static str {
+        "std-siphash-1-3"
+    }
+
+    fn seed(&self) -> u64 {
+        0
+    }
+}
+
+impl HasherState for BuildHasherDefault<DefaultHasher> {
+    fn restore(seed: u64, parameters: &[u8]) -> Option<Self> {
+        unparameterized(seed, parameters)
+    }
+}
+
+impl HasherIdentity for Xxh3DefaultBuilder {
+    fn algorithm(&self) -> &'static str {
+        STABLE_HASH_ALGORITHM
+    }
+
+    fn seed(&self) -> u64 {
+        0
+    }
+}
+
+impl HasherState for Xxh3DefaultBuilder {
+    fn restore(seed: u64, parameters: &[u8]) -> Option<Self> {
+        unparameterized(seed, parameters)
+    }
+}
+
+/// `restore` of hashers with a fixed seed and no parameters, which are all alike.
+pub(crate) fn unparameterized<S: HasherIdentity + Default>(seed: u64, parameters: &[u8]) -> Option<S> {
+    let hasher = S::default();
+    (hasher.seed() == seed && parameters.is_empty()).then_some(hasher)
+}
@@ -1,7 +1,50 @@
+use std::hash::Hasher;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Version of the encodings below, part of every sketch's fingerprint. It changes whenever an
+/// encoding does, so that sketches of items encoded differently are never merged or loaded together.
+pub const ENCODING_VERSION: u8 = 2;
+
+/// Bytes an item is hashed as. The encoding must not depend on the platform: integers and floats
+/// are little-endian, text is UTF-8, so sketches built on any machine agree.
+///
+/// Composite items (tuples, sequences, options) write their parts one after the other, each part
+/// of a type without `FIXED_LEN` after its length as a little-endian `u64`, so that `("ab", "c")`
+/// and `("a", "bc")` differ. Sequences of `T` all encode alike, e.g. `Vec<u8>` as its bytes.
 pub trait ToBytes {
     fn to_bytes(&self) -> Vec<u8>;
 
+    /// Writes the bytes of `to_bytes` into `hasher`. The default goes through `to_bytes`,
+    /// the implementations below write from the stack so that hashing an item does not allocate.
+    fn hash_into<H: Hasher>(&self, hasher: &mut H) {
+        hasher.write(&self.to_bytes());
+    }
+
+    /// Length of `to_bytes`. The default goes through `FIXED_LEN` or `to_bytes`.
+    fn encoded_len(&self) -> usize {
+        match Self::FIXED_LEN {
+            Some(len) => len,
+            None => self.to_bytes().len(),
+        }
+    }
+
+    /// Writes the bytes of `items.to_bytes()` into `hasher`: each item after its length unless
+    /// `FIXED_LEN` is set. Types that can write a whole slice at once override it.
+    fn hash_slice_into<H: Hasher>(items: &[Self], hasher: &mut H)
+    where
+        Self: Sized,
+    {
+        for item in items {
+            hash_part(item, hasher);
+        }
+    }
+
     const TYPE_ID: &'static [u8];
+
+    /// Length of the encoding when all values have the same one, so that composite items need not
+    /// write it. `None` by default.
+    const FIXED_LEN: Option<usize> = None;
 }
 
 // Integer types (Little Endian)
@@ -10,7 +53,17 @@ impl ToBytes for u8 {
         vec![*self]
     }
 
+    fn hash_into<H: Hasher>(&self, hasher: &mut H) {
+        hasher.write(&[*self]);
+    }
+
+    // byte sequences are their bytes, written in one go
+    fn hash_slice_into<H: Hasher>(items: &[Self], hasher: &mut H) {
+        hasher.write(items);
+    }
+
     const TYPE_ID: &'static [u8] = b"u8";
+    const FIXED_LEN: Option<usize> = Some(1);
 }
 
 impl ToBytes for u16 {
@@ -18,7 +71,12 @@ impl ToBytes for u16 {
         self.to_le_bytes().to_vec()
     }
 
+    fn hash_into<H: Hasher>(&self, hasher: &mut H) {
+        hasher.write(&self.to_le_bytes());
+    }
+
     const TYPE_ID: &'static [u8] = b"u16";
+    const FIXED_LEN: Option<usize> = Some(2);
 }
 
 impl ToBytes for u32 {
@@ -26,7 +84,12 @@ impl ToBytes for u32 {
         self.to_le_bytes().to_vec()
     }
 
+    fn hash_into<H: Hasher>(&self, hasher: &mut H) {
+        hasher.write(&self.to_le_bytes());
+    }
+
     const TYPE_ID: &'static [u8] = b"u32";
+    const FIXED_LEN: Option<usize> = Some(4);
 }
 
 impl ToBytes for u64 {
@@ -34,7 +97,12 @@ impl ToBytes for u64 {
         self.to_le_bytes().to_vec()
     }
 
+    fn hash_into<H: Hasher>(&self, hasher: &mut H) {
+        hasher.write(&self.to_le_bytes());
+    }
+
     const TYPE_ID: &'static [u8] = b"u64";
+    const FIXED_LEN: Option<usize> = Some(8);
 }
 
 impl ToBytes for u128 {
@@ -42,7 +110,12 @@ impl ToBytes for u128 {
         self.to_le_bytes().to_vec()
     }
 
+    fn hash_into<H: Hasher>(&self, hasher: &mut H) {
+        hasher.write(&self.to_le_bytes());
+    }
+
     const TYPE_ID: &'static [u8] = b"u128";
+    const FIXED_LEN: Option<usize> = Some(16);
 }
 
 impl ToBytes for i8 {
@@ -50,8 +123,12 @@ impl ToBytes for i8 {
         vec![*self as u8]
     }
 
-    const TYPE_ID: &'static [u8] = b"i8";
+    fn hash_into<H: Hasher>(&self, hasher: &mut H) {
+        hasher.write(&[*self as u8]);
+    }
 
+    const TYPE_ID: &'static [u8] = b"i8";
+    const FIXED_LEN: Option<usize> = Some(1);
 }
 
 impl ToBytes for i16 {
@@ -59,7 +136,12 @@ impl ToBytes for i16 {
         self.to_le_bytes().to_vec()
     }
 
+    fn hash_into<H: Hasher>(&self, hasher: &mut H) {
+        hasher.write(&self.to_le_bytes());
+    }
+
     const TYPE_ID: &'static [u8] = b"i16";
+    const FIXED_LEN: Option<usize> = Some(2);
 }
 
 impl ToBytes for i32 {
@@ -67,7 +149,12 @@ impl ToBytes for i32 {
         self.to_le_bytes().to_vec()
     }
 
+    fn hash_into<H: Hasher>(&self, hasher: &mut H) {
+        hasher.write(&self.to_le_bytes());
+    }
+
     const TYPE_ID: &'static [u8] = b"i32";
+    const FIXED_LEN: Option<usize> = Some(4);
 }
 
 impl ToBytes for i64 {
@@ -75,7 +162,12 @@ impl ToBytes for i64 {
         self.to_le_bytes().to_vec()
     }
 
+    fn hash_into<H: Hasher>(&self, hasher: &mut H) {
+        hasher.write(&self.to_le_bytes());
+    }
+
     const TYPE_ID: &'static [u8] = b"i64";
+    const FIXED_LEN: Option<usize> = Some(8);
 }
 
 impl ToBytes for i128 {
@@ -83,32 +175,140 @@ impl ToBytes for i128 {
         self.to_le_bytes().to_vec()
     }
 
+    fn hash_into<H: Hasher>(&self, hasher: &mut H) {
+        hasher.write(&self.to_le_bytes());
+    }
+
     const TYPE_ID: &'static [u8] = b"i128";
+    const FIXED_LEN: Option<usize> = Some(16);
 }
 
-// Floating point types (Native Endian)
+// Floating point types (Little Endian bit patterns, so -0.0 and every NaN payload count apart)
 impl ToBytes for f32 {
     fn to_bytes(&self) -> Vec<u8> {
-        self.to_ne_bytes().to_vec()
+        self.to_le_bytes().to_vec()
+    }
+
+    fn hash_into<H: Hasher>(&self, hasher: &mut H) {
+        hasher.write(&self.to_le_bytes());
     }
 
     const TYPE_ID: &'static [u8] = b"f32";
+    const FIXED_LEN: Option<usize> = Some(4);
 }
 
 impl ToBytes for f64 {
     fn to_bytes(&self) -> Vec<u8> {
-        self.to_ne_bytes().to_vec()
+        self.to_le_bytes().to_vec()
+    }
+
+    fn hash_into<H: Hasher>(&self, hasher: &mut H) {
+        hasher.write(&self.to_le_bytes());
     }
 
     const TYPE_ID: &'static [u8] = b"f64";
+    const FIXED_LEN: Option<usize> = Some(8);
+}
+
+/// `f32` counted by value rather than by bit pattern: -0.0 counts as 0.0 and all NaNs as one.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
+pub struct CanonicalF32(pub f32);
+
+impl CanonicalF32 {
+    fn canonical_bits(self) -> u32 {
+        if self.0.is_nan() {
+            f32::NAN.to_bits()
+        } else if self.0 == 0.0 {
+            0
+        } else {
+            self.0.to_bits()
+        }
+    }
+}
+
+impl From<f32> for CanonicalF32 {
+    fn from(value: f32) -> Self {
+        CanonicalF32(value)
+    }
+}
+
+impl ToBytes for CanonicalF32 {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.canonical_bits().to_le_bytes().to_vec()
+    }
+
+    fn hash_into<H: Hasher>(&self, hasher: &mut H) {
+        hasher.write(&self.canonical_bits().to_le_bytes());
+    }
+
+    const TYPE_ID: &'static [u8] = b"canonical-f32";
+    const FIXED_LEN: Option<usize> = Some(4);
+}
+
+/// `f64` counted by value rather than by bit pattern: -0.0 counts as 0.0 and all NaNs as one.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
+pub struct CanonicalF64(pub f64);
+
+impl CanonicalF64 {
+    fn canonical_bits(self) -> u64 {
+        if self.0.is_nan() {
+            f64::NAN.to_bits()
+        } else if self.0 == 0.0 {
+            0
+        } else {
+            self.0.to_bits()
+        }
+    }
 }
 
+impl From<f64> for CanonicalF64 {
+    fn from(value: f64) -> Self {
+        CanonicalF64(value)
+    }
+}
+
+impl ToBytes for CanonicalF64 {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.canonical_bits().to_le_bytes().to_vec()
+    }
+
+    fn hash_into<H: Hasher>(&self, hasher: &mut H) {
+        hasher.write(&self.canonical_bits().to_le_bytes());
+    }
+
+    const TYPE_ID: &'static [u8] = b"canonical-f64";
+    const FIXED_LEN: Option<usize> = Some(8);
+}
+
+impl ToBytes for str {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+
+    fn hash_into<H: Hasher>(&self, hasher: &mut H) {
+        hasher.write(self.as_bytes());
+    }
+
+    fn encoded_len(&self) -> usize {
+        self.len()
+    }
+
+    const TYPE_ID: &'static [u8] = b"str";
+}
 
 impl ToBytes for &str {
     fn to_bytes(&self) -> Vec<u8> {
         self.as_bytes().to_vec()
     }
 
+    fn hash_into<H: Hasher>(&self, hasher: &mut H) {
+        hasher.write(self.as_bytes());
+    }
+
+    fn encoded_len(&self) -> usize {
+        self.len()
+    }
+
     const TYPE_ID: &'static [u8] = b"&str";
 }
 
@@ -116,7 +316,15 @@ impl ToBytes for String {
     fn to_bytes(&self) -> Vec<u8> {
         self.as_bytes().to_vec()
     }
-    
+
+    fn hash_into<H: Hasher>(&self, hasher: &mut H) {
+        hasher.write(self.as_bytes());
+    }
+
+    fn encoded_len(&self) -> usize {
+        self.len()
+    }
+
     const TYPE_ID: &'static [u8] = b"String";
 }
 
@@ -125,5 +333,408 @@ impl ToBytes for char {
         self.to_string().to_bytes()
     }
 
+    fn hash_into<H: Hasher>(&self, hasher: &mut H) {
+        hasher.write(self.encode_utf8(&mut [0; 4]).as_bytes());
+    }
+
+    fn encoded_len(&self) -> usize {
+        self.len_utf8()
+    }
+
     const TYPE_ID: &'static [u8] = b"char";
 }
+
+impl ToBytes for bool {
+    fn to_bytes(&self) -> Vec<u8> {
+        vec![*self as u8]
+    }
+
+    fn hash_into<H: Hasher>(&self, hasher: &mut H) {
+        hasher.write(&[*self as u8]);
+    }
+
+    const TYPE_ID: &'static [u8] = b"bool";
+    const FIXED_LEN: Option<usize> = Some(1);
+}
+
+/// Collects what `hash_into` writes, the `to_bytes` of composite items.
+struct ByteSink(Vec<u8>);
+
+impl Hasher for ByteSink {
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.extend_from_slice(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        unreachable!("only collects bytes")
+    }
+}
+
+fn collect_bytes<T: ToBytes + ?Sized>(item: &T) -> Vec<u8> {
+    let mut sink = ByteSink(Vec::with_capacity(item.encoded_len()));
+    item.hash_into(&mut sink);
+    sink.0
+}
+
+/// Writes one part of a composite item, after its length unless its type has a fixed one.
+fn hash_part<T: ToBytes + ?Sized, H: Hasher>(part: &T, hasher: &mut H) {
+    if T::FIXED_LEN.is_none() {
+        hasher.write(&(part.encoded_len() as u64).to_le_bytes());
+    }
+    part.hash_into(hasher);
+}
+
+/// Number of bytes `hash_part` writes.
+fn part_len<T: ToBytes + ?Sized>(part: &T) -> usize {
+    match T::FIXED_LEN {
+        Some(len) => len,
+        None => 8 + part.encoded_len(),
+    }
+}
+
+/// Sum of the fixed lengths of parts, if they all have one.
+const fn fixed_sum(lengths: &[Option<usize>]) -> Option<usize> {
+    let mut sum = 0;
+    let mut i = 0;
+    while i < lengths.len() {
+        match lengths[i] {
+            Some(len) => sum += len,
+            None => return None,
+        }
+        i += 1;
+    }
+    Some(sum)
+}
+
+/// Longest `TYPE_ID` of a composite type.
+const MAX_TYPE_ID_LEN: usize = 512;
+
+/// `TYPE_ID` of a composite type, built at compile time from the ids of its parts.
+struct TypeIdBuilder {
+    bytes: [u8; MAX_TYPE_ID_LEN],
+    len: usize,
+}
+
+impl TypeIdBuilder {
+    const fn new() -> Self {
+        TypeIdBuilder { bytes: [0; MAX_TYPE_ID_LEN], len: 0 }
+    }
+
+    const fn push(mut self, part: &[u8]) -> Self {
+        assert!(self.len + part.len() <= MAX_TYPE_ID_LEN, "TYPE_ID too long");
+        let mut i = 0;
+        while i < part.len() {
+            self.bytes[self.len] = part[i];
+            self.len += 1;
+            i += 1;
+        }
+        self
+    }
+
+    const fn push_decimal(self, n: usize) -> Self {
+        let mut digits = [0; 20];
+        let mut start = digits.len();
+        let mut rest = n;
+        loop {
+            start -= 1;
+            digits[start] = b'0' + (rest % 10) as u8;
+            rest /= 10;
+            if rest == 0 {
+                break;
+            }
+        }
+        self.push(digits.split_at(start).1)
+    }
+
+    const fn as_slice(&'static self) -> &'static [u8] {
+        self.bytes.split_at(self.len).0
+    }
+}
+
+// Composite types build their `TYPE_ID` here: a constant of a generic type only becomes `'static`
+// through a reference to another constant.
+trait ComposedTypeId {
+    const ID: TypeIdBuilder;
+    const ID_REF: &'static TypeIdBuilder = &Self::ID;
+}
+
+// Sequences: their parts one after the other. Slices, vectors and arrays of `T` encode alike, so
+// that for example a `Vec<u8>` sketch takes `&[u8]` through `insert_ref`.
+impl<T: ToBytes> ComposedTypeId for [T] {
+    const ID: TypeIdBuilder = TypeIdBuilder::new().push(b"[").push(T::TYPE_ID).push(b"]");
+}
+
+impl<T: ToBytes> ToBytes for [T] {
+    fn to_bytes(&self) -> Vec<u8> {
+        collect_bytes(self)
+    }
+
+    fn hash_into<H: Hasher>(&self, hasher: &mut H) {
+        T::hash_slice_into(self, hasher);
+    }
+
+    fn encoded_len(&self) -> usize {
+        self.iter().map(part_len).sum()
+    }
+
+    const TYPE_ID: &'static [u8] = <Self as ComposedTypeId>::ID_REF.as_slice();
+}
+
+impl<T: ToBytes> ComposedTypeId for &[T] {
+    const ID: TypeIdBuilder = TypeIdBuilder::new().push(b"&").push(<[T]>::TYPE_ID);
+}
+
+impl<T: ToBytes> ToBytes for &[T] {
+    fn to_bytes(&self) -> Vec<u8> {
+        (**self).to_bytes()
+    }
+
+    fn hash_into<H: Hasher>(&self, hasher: &mut H) {
+        (**self).hash_into(hasher);
+    }
+
+    fn encoded_len(&self) -> usize {
+        (**self).encoded_len()
+    }
+
+    const TYPE_ID: &'static [u8] = <Self as ComposedTypeId>::ID_REF.as_slice();
+}
+
+impl<T: ToBytes> ComposedTypeId for Vec<T> {
+    const ID: TypeIdBuilder = TypeIdBuilder::new().push(b"Vec<").push(T::TYPE_ID).push(b">");
+}
+
+impl<T: ToBytes> ToBytes for Vec<T> {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.as_slice().to_bytes()
+    }
+
+    fn hash_into<H: Hasher>(&self, hasher: &mut H) {
+        self.as_slice().hash_into(hasher);
+    }
+
+    fn encoded_len(&self) -> usize {
+        self.as_slice().encoded_len()
+    }
+
+    const TYPE_ID: &'static [u8] = <Self as ComposedTypeId>::ID_REF.as_slice();
+}
+
+impl<T: ToBytes, const N: usize> ComposedTypeId for [T; N] {
+    const ID: TypeIdBuilder = TypeIdBuilder::new().push(b"[").push(T::TYPE_ID).push(b"; ").push_decimal(N).push(b"]");
+}
+
+impl<T: ToBytes, const N: usize> ToBytes for [T; N] {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.as_slice().to_bytes()
+    }
+
+    fn hash_into<H: Hasher>(&self, hasher: &mut H) {
+        self.as_slice().hash_into(hasher);
+    }
+
+    fn encoded_len(&self) -> usize {
+        self.as_slice().encoded_len()
+    }
+
+    const TYPE_ID: &'static [u8] = <Self as ComposedTypeId>::ID_REF.as_slice();
+    const FIXED_LEN: Option<usize> = match T::FIXED_LEN {
+        Some(len) => Some(len * N),
+        None => None,
+    };
+}
+
+// 0 for `None`, 1 and the value for `Some`
+impl<T: ToBytes> ComposedTypeId for Option<T> {
+    const ID: TypeIdBuilder = TypeIdBuilder::new().push(b"Option<").push(T::TYPE_ID).push(b">");
+}
+
+impl<T: ToBytes> ToBytes for Option<T> {
+    fn to_bytes(&self) -> Vec<u8> {
+        collect_bytes(self)
+    }
+
+    fn hash_into<H: Hasher>(&self, hasher: &mut H) {
+        match self {
+            None => hasher.write(&[0]),
+            Some(value) => {
+                hasher.write(&[1]);
+                hash_part(value, hasher);
+            }
+        }
+    }
+
+    fn encoded_len(&self) -> usize {
+        1 + self.as_ref().map_or(0, part_len)
+    }
+
+    const TYPE_ID: &'static [u8] = <Self as ComposedTypeId>::ID_REF.as_slice();
+}
+
+// Tuples: their parts one after the other, `TYPE_ID` as written in Rust, e.g. `(u64, String)`.
+macro_rules! tuple_to_bytes {
+    ($first:ident $($rest:ident)*) => {
+        impl<$first: ToBytes, $($rest: ToBytes),*> ComposedTypeId for ($first, $($rest,)*) {
+            const ID: TypeIdBuilder = TypeIdBuilder::new()
+                .push(b"(")
+                .push($first::TYPE_ID)
+                $(.push(b", ").push($rest::TYPE_ID))*
+                // a 1-tuple is written `(A,)`
+                .push(if <[&str]>::is_empty(&[$(stringify!($rest)),*]) { b",)".as_slice() } else { b")".as_slice() });
+        }
+
+        impl<$first: ToBytes, $($rest: ToBytes),*> ToBytes for ($first, $($rest,)*) {
+            fn to_bytes(&self) -> Vec<u8> {
+                collect_bytes(self)
+            }
+
+            #[allow(non_snake_case)]
+            fn hash_into<HASHER: Hasher>(&self, hasher: &mut HASHER) {
+                let ($first, $($rest,)*) = self;
+                hash_part($first, hasher);
+                $(hash_part($rest, hasher);)*
+            }
+
+            #[allow(non_snake_case)]
+            fn encoded_len(&self) -> usize {
+                let ($first, $($rest,)*) = self;
+                part_len($first) $(+ part_len($rest))*
+            }
+
+            const TYPE_ID: &'static [u8] = <Self as ComposedTypeId>::ID_REF.as_slice();
+            const FIXED_LEN: Option<usize> = fixed_sum(&[$first::FIXED_LEN, $($rest::FIXED_LEN),*]);
+        }
+    };
+}
+
+tuple_to_bytes!(A);
+tuple_to_bytes!(A B);
+tuple_to_bytes!(A B C);
+tuple_to_bytes!(A B C D);
+tuple_to_bytes!(A B C D E);
+tuple_to_bytes!(A B C D E F);
+tuple_to_bytes!(A B C D E F G);
+tuple_to_bytes!(A B C D E F G H);
+tuple_to_bytes!(A B C D E F G H I);
+tuple_to_bytes!(A B C D E F G H I J);
+tuple_to_bytes!(A B C D E F G H I J K);
+tuple_to_bytes!(A B C D E F G H I J K L);
+
+// Network addresses: octets in network order, ports and IPv6 flow info and scope id little-endian
+impl ToBytes for Ipv4Addr {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.octets().to_vec()
+    }
+
+    fn hash_into<H: Hasher>(&self, hasher: &mut H) {
+        hasher.write(&self.octets());
+    }
+
+    const TYPE_ID: &'static [u8] = b"Ipv4Addr";
+    const FIXED_LEN: Option<usize> = Some(4);
+}
+
+impl ToBytes for Ipv6Addr {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.octets().to_vec()
+    }
+
+    fn hash_into<H: Hasher>(&self, hasher: &mut H) {
+        hasher.write(&self.octets());
+    }
+
+    const TYPE_ID: &'static [u8] = b"Ipv6Addr";
+    const FIXED_LEN: Option<usize> = Some(16);
+}
+
+// 4 or 6, then the octets
+impl ToBytes for IpAddr {
+    fn to_bytes(&self) -> Vec<u8> {
+        collect_bytes(self)
+    }
+
+    fn hash_into<H: Hasher>(&self, hasher: &mut H) {
+        match self {
+            IpAddr::V4(addr) => {
+                hasher.write(&[4]);
+                addr.hash_into(hasher);
+            }
+            IpAddr::V6(addr) => {
+                hasher.write(&[6]);
+                addr.hash_into(hasher);
+            }
+        }
+    }
+
+    fn encoded_len(&self) -> usize {
+        match self {
+            IpAddr::V4(_) => 1 + 4,
+            IpAddr::V6(_) => 1 + 16,
+        }
+    }
+
+    const TYPE_ID: &'static [u8] = b"IpAddr";
+}
+
+// the address as an `IpAddr`, then the port, then for IPv6 the flow info and scope id
+impl ToBytes for SocketAddr {
+    fn to_bytes(&self) -> Vec<u8> {
+        collect_bytes(self)
+    }
+
+    fn hash_into<H: Hasher>(&self, hasher: &mut H) {
+        self.ip().hash_into(hasher);
+        hasher.write(&self.port().to_le_bytes());
+        if let SocketAddr::V6(addr) = self {
+            hasher.write(&addr.flowinfo().to_le_bytes());
+            hasher.write(&addr.scope_id().to_le_bytes());
+        }
+    }
+
+    fn encoded_len(&self) -> usize {
+        match self {
+            SocketAddr::V4(_) => 1 + 4 + 2,
+            SocketAddr::V6(_) => 1 + 16 + 2 + 4 + 4,
+        }
+    }
+
+    const TYPE_ID: &'static [u8] = b"SocketAddr";
+}
+
+// Time: seconds as a `u64` and nanoseconds as a `u32`, little-endian
+impl ToBytes for Duration {
+    fn to_bytes(&self) -> Vec<u8> {
+        collect_bytes(self)
+    }
+
+    fn hash_into<H: Hasher>(&self, hasher: &mut H) {
+        hasher.write(&self.as_secs().to_le_bytes());
+        hasher.write(&self.subsec_nanos().to_le_bytes());
+    }
+
+    const TYPE_ID: &'static [u8] = b"Duration";
+    const FIXED_LEN: Option<usize> = Some(12);
+}
+
+// 0 and the time since the Unix epoch, or 1 and the time until it
+impl ToBytes for SystemTime {
+    fn to_bytes(&self) -> Vec<u8> {
+        collect_bytes(self)
+    }
+
+    fn hash_into<H: Hasher>(&self, hasher: &mut H) {
+        match self.duration_since(UNIX_EPOCH) {
+            Ok(since) => {
+                hasher.write(&[0]);
+                since.hash_into(hasher);
+            }
+            Err(before) => {
+                hasher.write(&[1]);
+                before.duration().hash_into(hasher);
+            }
+        }
+    }
+
+    const TYPE_ID: &'static [u8] = b"SystemTime";
+    const FIXED_LEN: Option<usize> = Some(13);
+}
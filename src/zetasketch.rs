@@ -0,0 +1,482 @@
+use std::hash::{BuildHasher, Hasher};
+
+use crate::buffer::HashBuffer;
+use crate::error::HyperLogLogError;
+use crate::stable::{unparameterized, HasherIdentity, HasherState};
+use crate::sparse::SPARSE_PRECISION;
+
+// `AggregatorType.HYPERLOGLOG_PLUS_UNIQUE`, also the field number of the state extension
+const HYPERLOGLOG_PLUS_UNIQUE: u64 = 112;
+const ENCODING_VERSION: u64 = 2;
+// precisions ZetaSketch accepts, the sparse one is either 0 (no sparse data) or between p and 25
+const MIN_PRECISION: u32 = 10;
+const MAX_PRECISION: u32 = 24;
+const MAX_SPARSE_PRECISION: u32 = 25;
+// sparse precision BigQuery and ZetaSketch pick by default, `p + 5` up to 25
+const SPARSE_PRECISION_DELTA: u32 = 5;
+// bits of a sparse value holding the rhoW when it cannot be read from the sparse index
+const RHOW_BITS: u32 = 6;
+
+// `AggregatorStateProto` fields
+const TYPE_FIELD: u64 = 1;
+const NUM_VALUES_FIELD: u64 = 2;
+const ENCODING_VERSION_FIELD: u64 = 3;
+const VALUE_TYPE_FIELD: u64 = 4;
+// `HyperLogLogPlusUniqueStateProto` fields
+const SPARSE_SIZE_FIELD: u64 = 2;
+const PRECISION_FIELD: u64 = 3;
+const SPARSE_PRECISION_FIELD: u64 = 4;
+const DATA_FIELD: u64 = 5;
+const SPARSE_DATA_FIELD: u64 = 6;
+
+// protobuf wire types
+const VARINT: u64 = 0;
+const FIXED64: u64 = 1;
+const LENGTH_DELIMITED: u64 = 2;
+const FIXED32: u64 = 5;
+
+// `DefaultOpsType.Id` of the item types ZetaSketch hashes the way `ToBytes` does
+const INT32: u64 = 1;
+const INT64: u64 = 2;
+const UINT32: u64 = 3;
+const UINT64: u64 = 4;
+const BYTES_OR_UTF8_STRING: u64 = 11;
+
+// primes between 2^63 and 2^64 used by Fingerprint2011
+const K0: u64 = 0xa5b85c5e198ed849;
+const K1: u64 = 0x8d58ac26afe12e47;
+const K2: u64 = 0xc47b6e9e3a970ed3;
+const K3: u64 = 0xc6a4a7935bd1e995;
+
+fn load64(bytes: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap())
+}
+
+fn shift_mix(value: u64) -> u64 {
+    value ^ (value >> 47)
+}
+
+fn hash128_to_64(high: u64, low: u64) -> u64 {
+    let mut a = (low ^ high).wrapping_mul(K3);
+    a ^= a >> 47;
+    let mut b = (high ^ a).wrapping_mul(K3);
+    b ^= b >> 47;
+    b.wrapping_mul(K3)
+}
+
+fn weak_hash_length32_with_seeds(bytes: &[u8], offset: usize, mut seed_a: u64, mut seed_b: u64) -> (u64, u64) {
+    let (part1, part2, part3, part4) =
+        (load64(bytes, offset), load64(bytes, offset + 8), load64(bytes, offset + 16), load64(bytes, offset + 24));
+
+    seed_a = seed_a.wrapping_add(part1);
+    seed_b = seed_b.wrapping_add(seed_a).wrapping_add(part4).rotate_right(51);
+    let c = seed_a;
+    seed_a = seed_a.wrapping_add(part2).wrapping_add(part3);
+    seed_b = seed_b.wrapping_add(seed_a.rotate_right(23));
+    (seed_a.wrapping_add(part4), seed_b.wrapping_add(c))
+}
+
+fn murmurhash64_with_seed(bytes: &[u8], seed: u64) -> u64 {
+    let mut hash = seed ^ (bytes.len() as u64).wrapping_mul(K3);
+    let mut chunks = bytes.chunks_exact(8);
+    for chunk in &mut chunks {
+        let data = shift_mix(u64::from_le_bytes(chunk.try_into().unwrap()).wrapping_mul(K3)).wrapping_mul(K3);
+        hash = (hash ^ data).wrapping_mul(K3);
+    }
+
+    let tail = chunks.remainder();
+    if !tail.is_empty() {
+        let data = tail.iter().enumerate().fold(0u64, |data, (i, &byte)| data | (byte as u64) << (8 * i));
+        hash = (hash ^ data).wrapping_mul(K3);
+    }
+    shift_mix(shift_mix(hash).wrapping_mul(K3))
+}
+
+fn hash_length_33_to_64(bytes: &[u8]) -> u64 {
+    let len = bytes.len();
+    let mut z = load64(bytes, 24);
+    let mut a = load64(bytes, 0).wrapping_add((len as u64).wrapping_add(load64(bytes, len - 16)).wrapping_mul(K0));
+    let mut b = a.wrapping_add(z).rotate_right(52);
+    let mut c = a.rotate_right(37);
+    a = a.wrapping_add(load64(bytes, 8));
+    c = c.wrapping_add(a.rotate_right(7));
+    a = a.wrapping_add(load64(bytes, 16));
+    let vf = a.wrapping_add(z);
+    let vs = b.wrapping_add(a.rotate_right(31)).wrapping_add(c);
+
+    a = load64(bytes, 16).wrapping_add(load64(bytes, len - 32));
+    z = load64(bytes, len - 8);
+    b = a.wrapping_add(z).rotate_right(52);
+    c = a.rotate_right(37);
+    a = a.wrapping_add(load64(bytes, len - 24));
+    c = c.wrapping_add(a.rotate_right(7));
+    a = a.wrapping_add(load64(bytes, len - 16));
+    let wf = a.wrapping_add(z);
+    let ws = b.wrapping_add(a.rotate_right(31)).wrapping_add(c);
+
+    let r = shift_mix(vf.wrapping_add(ws).wrapping_mul(K2).wrapping_add(wf.wrapping_add(vs).wrapping_mul(K0)));
+    shift_mix(r.wrapping_mul(K0).wrapping_add(vs)).wrapping_mul(K2)
+}
+
+fn full_fingerprint(bytes: &[u8]) -> u64 {
+    let len = bytes.len();
+    let mut x = load64(bytes, 0);
+    let mut y = load64(bytes, len - 16) ^ K1;
+    let mut z = load64(bytes, len - 56) ^ K0;
+    let mut v = weak_hash_length32_with_seeds(bytes, len - 64, len as u64, y);
+    let mut w = weak_hash_length32_with_seeds(bytes, len - 32, (len as u64).wrapping_mul(K1), K0);
+    z = z.wrapping_add(shift_mix(v.1).wrapping_mul(K1));
+    x = z.wrapping_add(x).rotate_right(39).wrapping_mul(K1);
+    y = y.rotate_right(33).wrapping_mul(K1);
+
+    // whole 64-byte chunks from the start, the last partial one is covered by the tail reads above
+    for offset in (0..(len - 1) & !63).step_by(64) {
+        x = x.wrapping_add(y).wrapping_add(v.0).wrapping_add(load64(bytes, offset + 16)).rotate_right(37).wrapping_mul(K1);
+        y = y.wrapping_add(v.1).wrapping_add(load64(bytes, offset + 48)).rotate_right(42).wrapping_mul(K1);
+        x ^= w.1;
+        y ^= v.0;
+        z = (z ^ w.0).rotate_right(33);
+        v = weak_hash_length32_with_seeds(bytes, offset, v.1.wrapping_mul(K1), x.wrapping_add(w.0));
+        w = weak_hash_length32_with_seeds(bytes, offset + 32, z.wrapping_add(w.1), y);
+        std::mem::swap(&mut x, &mut z);
+    }
+    hash128_to_64(
+        hash128_to_64(v.0, w.0).wrapping_add(shift_mix(y).wrapping_mul(K1)).wrapping_add(z),
+        hash128_to_64(v.1, w.1).wrapping_add(x),
+    )
+}
+
+/// Fingerprint2011 of Guava, the hash ZetaSketch and BigQuery apply to every item.
+fn fingerprint2011(bytes: &[u8]) -> u64 {
+    let len = bytes.len();
+    let result = match len {
+        0..=32 => murmurhash64_with_seed(bytes, K0 ^ K1 ^ K2),
+        33..=64 => hash_length_33_to_64(bytes),
+        _ => full_fingerprint(bytes),
+    };
+
+    let u = if len >= 8 { load64(bytes, 0) } else { K0 };
+    let v = if len >= 9 { load64(bytes, len - 8) } else { K0 };
+    let result = hash128_to_64(result.wrapping_add(v), u);
+    // 0 and 1 are reserved
+    if result < 2 { result.wrapping_add(!1) } else { result }
+}
+
+/// `BuildHasher` hashing items the way ZetaSketch `HyperLogLogPlusPlus` and BigQuery `HLL_COUNT`
+/// do, Fingerprint2011 over the little-endian integer or the UTF-8 bytes of a string, as `ToBytes` does.
+/// Both read the register index from the top bits and the rank from the leading zeros, as this crate does.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ZetaSketchHasherBuilder;
+
+impl BuildHasher for ZetaSketchHasherBuilder {
+    type Hasher = ZetaSketchHasher;
+
+    fn build_hasher(&self) -> ZetaSketchHasher {
+        ZetaSketchHasher { bytes: HashBuffer::new() }
+    }
+}
+
+impl HasherIdentity for ZetaSketchHasherBuilder {
+    fn algorithm(&self) -> &'static str {
+        "fingerprint2011"
+    }
+
+    fn seed(&self) -> u64 {
+        0
+    }
+}
+
+impl HasherState for ZetaSketchHasherBuilder {
+    fn restore(seed: u64, parameters: &[u8]) -> Option<Self> {
+        unparameterized(seed, parameters)
+    }
+}
+
+/// Hasher built by `ZetaSketchHasherBuilder`: Fingerprint2011 over every written byte.
+#[derive(Debug, Clone, Default)]
+pub struct ZetaSketchHasher {
+    bytes: HashBuffer,
+}
+
+impl Hasher for ZetaSketchHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        fingerprint2011(self.bytes.as_slice())
+    }
+}
+
+/// `DefaultOpsType.Id` ZetaSketch records for items of type `type_id`, if it has one.
+pub(crate) fn value_type(type_id: &[u8]) -> Option<u64> {
+    match type_id {
+        b"i32" => Some(INT32),
+        b"i64" => Some(INT64),
+        b"u32" => Some(UINT32),
+        b"u64" => Some(UINT64),
+        b"String" | b"&str" => Some(BYTES_OR_UTF8_STRING),
+        _ => None,
+    }
+}
+
+/// Registers read from a sketch: dense ones at `p`, or sparse entries at `SPARSE_PRECISION`
+/// when the sketch is empty or sparse at that same precision, so nothing is lost by keeping it sparse.
+pub(crate) enum Decoded {
+    Dense(Vec<u8>),
+    Sparse(Vec<u32>),
+}
+
+fn corrupt(msg: String) -> HyperLogLogError {
+    HyperLogLogError::CorruptPayload(format!("ZetaSketch: {}", msg))
+}
+
+fn read_varint(bytes: &[u8], pos: &mut usize) -> Result<u64, HyperLogLogError> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = *bytes.get(*pos).ok_or_else(|| corrupt("truncated varint".to_string()))?;
+        *pos += 1;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(corrupt("varint longer than 10 bytes".to_string()))
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+enum FieldValue<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+}
+
+/// The fields of a protobuf message, fixed-size ones skipped.
+fn read_fields(bytes: &[u8]) -> Result<Vec<(u64, FieldValue<'_>)>, HyperLogLogError> {
+    let mut fields = Vec::new();
+    let mut pos = 0;
+    while pos < bytes.len() {
+        let tag = read_varint(bytes, &mut pos)?;
+        let skip = |len: usize, pos: &mut usize| {
+            *pos = pos.checked_add(len).filter(|&end| end <= bytes.len()).ok_or_else(|| corrupt("truncated field".to_string()))?;
+            Ok::<_, HyperLogLogError>(*pos - len)
+        };
+        match tag & 7 {
+            VARINT => fields.push((tag >> 3, FieldValue::Varint(read_varint(bytes, &mut pos)?))),
+            LENGTH_DELIMITED => {
+                let len = read_varint(bytes, &mut pos)? as usize;
+                let start = skip(len, &mut pos)?;
+                fields.push((tag >> 3, FieldValue::Bytes(&bytes[start..pos])));
+            }
+            FIXED64 => { skip(8, &mut pos)?; }
+            FIXED32 => { skip(4, &mut pos)?; }
+            wire_type => return Err(corrupt(format!("unsupported wire type {}", wire_type))),
+        }
+    }
+    Ok(fields)
+}
+
+fn write_varint_field(out: &mut Vec<u8>, field: u64, value: u64) {
+    write_varint(out, field << 3 | VARINT);
+    write_varint(out, value);
+}
+
+fn write_bytes_field(out: &mut Vec<u8>, field: u64, bytes: &[u8]) {
+    write_varint(out, field << 3 | LENGTH_DELIMITED);
+    write_varint(out, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+
+// a sparse value with this bit set holds the normal index and the rhoW past the sparse index
+fn rhow_flag(p: u32, sp: u32) -> u32 {
+    1 << sp.max(p + RHOW_BITS)
+}
+
+/// Precision and registers of a serialized `AggregatorStateProto`. `value_type` is the type
+/// the items are expected to have, if ZetaSketch has one for them.
+pub(crate) fn decode(bytes: &[u8], value_type: Option<u64>) -> Result<(u32, Decoded), HyperLogLogError> {
+    let mut state = None;
+    let mut sketch_type = None;
+    for (field, value) in read_fields(bytes)? {
+        match (field, value) {
+            (TYPE_FIELD, FieldValue::Varint(t)) => sketch_type = Some(t),
+            (ENCODING_VERSION_FIELD, FieldValue::Varint(version)) if version != ENCODING_VERSION => {
+                return Err(HyperLogLogError::UnsupportedVersion(version.min(u8::MAX as u64) as u8));
+            }
+            (VALUE_TYPE_FIELD, FieldValue::Varint(found)) if value_type.is_some_and(|expected| found != expected) => {
+                return Err(HyperLogLogError::HasherMismatch);
+            }
+            (HYPERLOGLOG_PLUS_UNIQUE, FieldValue::Bytes(bytes)) => state = Some(bytes),
+            _ => {}
+        }
+    }
+    if sketch_type != Some(HYPERLOGLOG_PLUS_UNIQUE) {
+        return Err(HyperLogLogError::InvalidMagic);
+    }
+    let state = state.ok_or_else(|| corrupt("no HyperLogLog++ state".to_string()))?;
+
+    let (mut p, mut sp, mut sparse_size) = (0, 0, None);
+    let (mut data, mut sparse_data) = (None, None);
+    for (field, value) in read_fields(state)? {
+        match (field, value) {
+            (SPARSE_SIZE_FIELD, FieldValue::Varint(size)) => sparse_size = Some(size as usize),
+            (PRECISION_FIELD, FieldValue::Varint(value)) => p = value.min(u32::MAX as u64) as u32,
+            (SPARSE_PRECISION_FIELD, FieldValue::Varint(value)) => sp = value.min(u32::MAX as u64) as u32,
+            (DATA_FIELD, FieldValue::Bytes(bytes)) => data = Some(bytes),
+            (SPARSE_DATA_FIELD, FieldValue::Bytes(bytes)) => sparse_data = Some(bytes),
+            _ => {}
+        }
+    }
+    if !(MIN_PRECISION..=MAX_PRECISION).contains(&p) {
+        return Err(HyperLogLogError::UnsupportedPrecision(p));
+    }
+    if sp != 0 && !(p..=MAX_SPARSE_PRECISION).contains(&sp) {
+        return Err(corrupt(format!("sparse precision {} with precision {}", sp, p)));
+    }
+
+    // varint-encoded differences between sorted sparse values
+    let mut sparse_values = Vec::new();
+    if let Some(bytes) = sparse_data {
+        if sp == 0 {
+            return Err(corrupt("sparse data without a sparse precision".to_string()));
+        }
+        let (mut pos, mut value) = (0, 0u64);
+        while pos < bytes.len() {
+            let delta = read_varint(bytes, &mut pos)?;
+            if delta == 0 && !sparse_values.is_empty() {
+                return Err(corrupt(format!("sparse value {} repeated", value)));
+            }
+            value += delta;
+            if value >= 2 * rhow_flag(p, sp) as u64 {
+                return Err(corrupt(format!("sparse value {} out of range", value)));
+            }
+            sparse_values.push(value as u32);
+        }
+    }
+    if sparse_size.unwrap_or(0) != sparse_values.len() {
+        return Err(HyperLogLogError::BucketCountMismatch(sparse_size.unwrap_or(0), sparse_values.len()));
+    }
+
+    let flag = rhow_flag(p, sp);
+    let below_index = sp.saturating_sub(p);
+    // (normal index, sparse index, rhoW) of every sparse value, rhoW past the sparse index when flagged
+    let mut decoded = Vec::with_capacity(sparse_values.len());
+    for value in sparse_values {
+        if value & flag != 0 {
+            let index = (value ^ flag) >> RHOW_BITS;
+            let rhow = value & ((1 << RHOW_BITS) - 1);
+            if index >= 1 << p || rhow == 0 || rhow > 64 - sp + 1 {
+                return Err(corrupt(format!("invalid sparse value {:#x}", value)));
+            }
+            decoded.push((index, index << below_index, rhow, true));
+        } else {
+            let low = value & ((1 << below_index) - 1);
+            if value >= 1 << sp || low == 0 {
+                return Err(corrupt(format!("invalid sparse value {:#x}", value)));
+            }
+            decoded.push((value >> below_index, value, below_index - (32 - low.leading_zeros()) + 1, false));
+        }
+    }
+
+    if data.is_none() && (sp == SPARSE_PRECISION || decoded.is_empty()) {
+        // the sparse index is this crate's, only the rank past it is missing for unflagged values,
+        // where it is never read since the index bits below the dense index already set the rank
+        let mut entries: Vec<u32> = decoded.iter().map(|&(_, sparse_index, rhow, flagged)| {
+            sparse_index << RHOW_BITS | if flagged { rhow } else { 1 }
+        }).collect();
+        entries.sort_unstable();
+        return Ok((p, Decoded::Sparse(entries)));
+    }
+
+    let mut registers = match data {
+        Some(bytes) if bytes.len() != 1 << p => return Err(HyperLogLogError::BucketCountMismatch(1 << p, bytes.len())),
+        Some(bytes) => bytes.to_vec(),
+        None => vec![0u8; 1 << p],
+    };
+    for (index, _, rhow, flagged) in decoded {
+        let rhow = if flagged { below_index + rhow } else { rhow };
+        let register = &mut registers[index as usize];
+        *register = (*register).max(rhow as u8);
+    }
+    Ok((p, Decoded::Dense(registers)))
+}
+
+/// Checks that `p` is a precision ZetaSketch supports.
+pub(crate) fn check_precision(p: u32) -> Result<(), HyperLogLogError> {
+    if !(MIN_PRECISION..=MAX_PRECISION).contains(&p) {
+        return Err(HyperLogLogError::UnsupportedPrecision(p));
+    }
+    Ok(())
+}
+
+/// Serializes a sketch at `p` as an `AggregatorStateProto`: sparse at `p + 5` (at most 25) from the
+/// entries at `SPARSE_PRECISION` when given, dense from `registers` otherwise. The number of values
+/// added is not tracked here and is written as 0.
+pub(crate) fn encode(p: u32, sparse_entries: Option<&[u32]>, registers: &[u8], value_type: Option<u64>) -> Vec<u8> {
+    let sp = (p + SPARSE_PRECISION_DELTA).min(MAX_SPARSE_PRECISION);
+    let mut state = Vec::new();
+
+    match sparse_entries {
+        Some(entries) => {
+            let flag = rhow_flag(p, sp);
+            let dropped = SPARSE_PRECISION - sp;
+            let mut values: Vec<u32> = entries.iter().map(|&entry| {
+                let (index, rank) = (entry >> RHOW_BITS, entry & ((1 << RHOW_BITS) - 1));
+                let sparse_index = index >> dropped;
+                if sparse_index & ((1 << (sp - p)) - 1) != 0 {
+                    return sparse_index;
+                }
+                // rhoW of the bits past the sparse index, from the dropped index bits or the rank
+                let low = index & ((1 << dropped) - 1);
+                let rhow = if low != 0 { dropped - (32 - low.leading_zeros()) + 1 } else { dropped + rank };
+                flag | (sparse_index >> (sp - p)) << RHOW_BITS | rhow.min(64 - sp + 1)
+            }).collect();
+            values.sort_unstable();
+            // one value per sparse index, the flagged ones of an index sorted by rhoW
+            let key = |value: u32| if value & flag != 0 { value >> RHOW_BITS } else { value };
+            values.dedup_by(|next, kept| {
+                let same = key(*next) == key(*kept) && (*next & flag) == (*kept & flag);
+                if same {
+                    *kept = (*kept).max(*next);
+                }
+                same
+            });
+
+            if !values.is_empty() {
+                write_varint_field(&mut state, SPARSE_SIZE_FIELD, values.len() as u64);
+            }
+            write_varint_field(&mut state, PRECISION_FIELD, p as u64);
+            write_varint_field(&mut state, SPARSE_PRECISION_FIELD, sp as u64);
+            if !values.is_empty() {
+                let mut sparse_data = Vec::new();
+                let mut previous = 0;
+                for value in values {
+                    write_varint(&mut sparse_data, (value - previous) as u64);
+                    previous = value;
+                }
+                write_bytes_field(&mut state, SPARSE_DATA_FIELD, &sparse_data);
+            }
+        }
+        None => {
+            write_varint_field(&mut state, PRECISION_FIELD, p as u64);
+            write_varint_field(&mut state, SPARSE_PRECISION_FIELD, sp as u64);
+            write_bytes_field(&mut state, DATA_FIELD, registers);
+        }
+    }
+
+    let mut out = Vec::with_capacity(state.len() + 16);
+    write_varint_field(&mut out, TYPE_FIELD, HYPERLOGLOG_PLUS_UNIQUE);
+    write_varint_field(&mut out, NUM_VALUES_FIELD, 0);
+    write_varint_field(&mut out, ENCODING_VERSION_FIELD, ENCODING_VERSION);
+    if let Some(value_type) = value_type {
+        write_varint_field(&mut out, VALUE_TYPE_FIELD, value_type);
+    }
+    write_bytes_field(&mut out, HYPERLOGLOG_PLUS_UNIQUE, &state);
+    out
+}
@@ -0,0 +1,189 @@
+use hyperloglog::{Estimator, HyperLogLog, RegisterBackend, Representation};
+
+fn filled(backend: RegisterBackend, p: u32, range: std::ops::Range<u64>) -> HyperLogLog<u64> {
+    let mut hll = HyperLogLog::<u64>::new(p).unwrap().with_backend(backend);
+    for i in range {
+        hll.insert(i);
+    }
+    hll
+}
+
+#[test]
+fn test_packed_backend_saves_a_quarter() {
+    let p = 14;
+    let dense = HyperLogLog::<u64>::memory_cost_with_backend(p, RegisterBackend::U8).unwrap();
+    let packed = HyperLogLog::<u64>::memory_cost_with_backend(p, RegisterBackend::Packed6).unwrap();
+    assert_eq!(dense, 16384);
+    assert_eq!(packed, 12288);
+
+    let hll = filled(RegisterBackend::Packed6, p, 0..100_000);
+    assert_eq!(hll.representation(), Representation::Packed6);
+    assert_eq!(hll.memory_usage(), packed);
+}
+
+#[test]
+fn test_packed_stores_every_register_value() {
+    let p = 8;
+    let buckets: Vec<u8> = (0..1 << p).map(|i| (i % 58) as u8).collect();
+    let hll = HyperLogLog::<u64>::from_buckets(p, buckets.clone(), Default::default())
+        .unwrap()
+        .with_backend(RegisterBackend::Packed6);
+
+    assert_eq!(hll.representation(), Representation::Packed6);
+    assert_eq!(hll.get_buckets(), buckets);
+}
+
+#[test]
+fn test_backends_give_identical_buckets_and_estimates() {
+    for n in [10u64, 1_000, 100_000] {
+        let dense = filled(RegisterBackend::U8, 12, 0..n);
+        let packed = filled(RegisterBackend::Packed6, 12, 0..n);
+
+        assert_eq!(dense.get_buckets(), packed.get_buckets(), "n={}", n);
+        assert_eq!(dense.calculate_cardinality(), packed.calculate_cardinality(), "n={}", n);
+    }
+}
+
+#[test]
+fn test_merge_across_backends() {
+    let p = 10;
+    let expected = filled(RegisterBackend::U8, p, 0..40_000).get_buckets();
+
+    let mut packed = filled(RegisterBackend::Packed6, p, 0..20_000);
+    let dense = filled(RegisterBackend::U8, p, 20_000..40_000);
+    packed.merge(&dense).unwrap();
+    assert_eq!(packed.representation(), Representation::Packed6);
+    assert_eq!(packed.get_buckets(), expected);
+
+    let mut dense = filled(RegisterBackend::U8, p, 0..20_000);
+    let packed = filled(RegisterBackend::Packed6, p, 20_000..40_000);
+    dense.merge(&packed).unwrap();
+    assert_eq!(dense.representation(), Representation::Dense);
+    assert_eq!(dense.get_buckets(), expected);
+
+    // sparse sketch merged with a packed one becomes packed
+    let mut sparse = filled(RegisterBackend::Packed6, p, 0..10);
+    assert_eq!(sparse.representation(), Representation::Sparse);
+    sparse.merge(&filled(RegisterBackend::Packed6, p, 10..40_000)).unwrap();
+    assert_eq!(sparse.representation(), Representation::Packed6);
+    assert_eq!(sparse.get_buckets(), expected);
+}
+
+#[test]
+fn test_with_backend_converts_dense_sketch() {
+    let dense = filled(RegisterBackend::U8, 10, 0..50_000);
+    let buckets = dense.get_buckets();
+
+    let packed = dense.with_backend(RegisterBackend::Packed6);
+    assert_eq!(packed.get_backend(), RegisterBackend::Packed6);
+    assert_eq!(packed.representation(), Representation::Packed6);
+    assert_eq!(packed.get_buckets(), buckets);
+
+    let back = packed.with_backend(RegisterBackend::U8);
+    assert_eq!(back.representation(), Representation::Dense);
+    assert_eq!(back.get_buckets(), buckets);
+}
+
+#[test]
+fn test_reset_keeps_backend() {
+    let mut hll = filled(RegisterBackend::Packed6, 8, 0..10_000);
+    hll.reset();
+    assert_eq!(hll.get_backend(), RegisterBackend::Packed6);
+    assert!(hll.get_buckets().iter().all(|&b| b == 0));
+
+    for i in 0..10_000u64 {
+        hll.insert(i);
+    }
+    assert_eq!(hll.representation(), Representation::Packed6);
+}
+
+#[test]
+fn test_packed_serde_roundtrip() {
+    let hll = filled(RegisterBackend::Packed6, 12, 0..50_000);
+    let json = serde_json::to_string(&hll).unwrap();
+    let restored: HyperLogLog<u64> = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(restored.representation(), Representation::Packed6);
+    assert_eq!(restored.get_buckets(), hll.get_buckets());
+    assert_eq!(restored.calculate_cardinality(), hll.calculate_cardinality());
+
+    // a packed dump can be compared with a dense one value by value
+    let dense = filled(RegisterBackend::U8, 12, 0..50_000);
+    let dense_json: serde_json::Value = serde_json::to_value(&dense).unwrap();
+    let packed_json: serde_json::Value = serde_json::to_value(&hll).unwrap();
+    assert_eq!(dense_json["buckets"], packed_json["buckets"]);
+}
+
+#[test]
+fn test_hll4_backend_halves_memory() {
+    let p = 14;
+    assert_eq!(HyperLogLog::<u64>::memory_cost_with_backend(p, RegisterBackend::Hll4).unwrap(), 8192);
+
+    let hll = filled(RegisterBackend::Hll4, p, 0..200_000);
+    assert_eq!(hll.representation(), Representation::Hll4);
+    // a few registers overflow the nibble and go to the exception table
+    assert!(hll.memory_usage() >= 8192);
+    assert!(hll.memory_usage() < 8192 + 1024);
+}
+
+#[test]
+fn test_hll4_matches_u8_layout() {
+    // p = 6 with millions of items moves the shared base up several times
+    for (p, n) in [(12, 100u64), (12, 50_000), (6, 2_000_000), (10, 1_000_000)] {
+        let dense = filled(RegisterBackend::U8, p, 0..n);
+        let hll4 = filled(RegisterBackend::Hll4, p, 0..n);
+
+        assert_eq!(dense.get_buckets(), hll4.get_buckets(), "p={} n={}", p, n);
+        for estimator in [Estimator::Classic, Estimator::HyperLogLogPlusPlus, Estimator::Ertl] {
+            assert_eq!(
+                dense.calculate_cardinality_with(estimator),
+                hll4.calculate_cardinality_with(estimator),
+                "p={} n={} {:?}", p, n, estimator
+            );
+        }
+    }
+}
+
+#[test]
+fn test_hll4_exceptions_hold_wide_values() {
+    let p = 8;
+    // values far apart force most registers into the exception table
+    let buckets: Vec<u8> = (0..1 << p).map(|i| if i % 3 == 0 { 1 } else { 40 + (i % 17) as u8 }).collect();
+    let mut hll = HyperLogLog::<u64>::from_buckets(p, buckets.clone(), Default::default())
+        .unwrap()
+        .with_backend(RegisterBackend::Hll4);
+    assert_eq!(hll.get_buckets(), buckets);
+    // values from base + 15 = 16 up are exceptions, each an index and a value padded to 8 bytes
+    let exceptions = buckets.iter().filter(|&&v| v >= 16).count();
+    assert_eq!(hll.memory_usage(), 128 + exceptions * 8);
+
+    // raising every register at the base moves the base and re-encodes the exceptions
+    let mut raised = buckets.clone();
+    for (i, value) in raised.iter_mut().enumerate() {
+        if i % 3 == 0 {
+            *value = 45;
+        }
+    }
+    let other = HyperLogLog::<u64>::from_buckets(p, raised.clone(), Default::default()).unwrap();
+    hll.merge(&other).unwrap();
+    assert_eq!(hll.get_buckets(), raised);
+}
+
+#[test]
+fn test_hll4_merge_reset_and_serde() {
+    let p = 10;
+    let expected = filled(RegisterBackend::U8, p, 0..60_000).get_buckets();
+
+    let mut hll4 = filled(RegisterBackend::Hll4, p, 0..30_000);
+    hll4.merge(&filled(RegisterBackend::Packed6, p, 30_000..60_000)).unwrap();
+    assert_eq!(hll4.get_buckets(), expected);
+
+    let json = serde_json::to_string(&hll4).unwrap();
+    let restored: HyperLogLog<u64> = serde_json::from_str(&json).unwrap();
+    assert_eq!(restored.representation(), Representation::Hll4);
+    assert_eq!(restored.get_buckets(), expected);
+
+    hll4.reset();
+    assert_eq!(hll4.calculate_cardinality(), 0);
+    assert_eq!(hll4.get_backend(), RegisterBackend::Hll4);
+}
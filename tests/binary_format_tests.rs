@@ -0,0 +1,218 @@
+use hyperloglog::{
+    HyperLogLog, HyperLogLogError, RegisterBackend, Representation, DEFAULT_HASH_SEED, FORMAT_VERSION, MAX_PRECISION,
+    STABLE_HASH_ALGORITHM,
+};
+
+fn filled(backend: RegisterBackend, p: u32, range: std::ops::Range<u64>) -> HyperLogLog<u64> {
+    let mut hll = HyperLogLog::<u64>::new(p).unwrap().with_backend(backend);
+    for i in range {
+        hll.insert(i);
+    }
+    hll
+}
+
+#[test]
+fn test_roundtrip_every_representation() {
+    for (backend, n, representation) in [
+        (RegisterBackend::U8, 100, Representation::Sparse),
+        (RegisterBackend::U8, 100_000, Representation::Dense),
+        (RegisterBackend::Packed6, 100_000, Representation::Packed6),
+        (RegisterBackend::Hll4, 100_000, Representation::Hll4),
+        (RegisterBackend::Hll4, 0, Representation::Sparse),
+    ] {
+        let hll = filled(backend, 12, 0..n);
+        assert_eq!(hll.representation(), representation);
+
+        let restored = HyperLogLog::<u64>::from_bytes(&hll.to_bytes()).unwrap();
+        assert_eq!(restored.representation(), representation, "{:?}", backend);
+        assert_eq!(restored.get_backend(), backend);
+        assert_eq!(restored.get_buckets(), hll.get_buckets(), "{:?}", backend);
+        assert_eq!(restored.calculate_cardinality(), hll.calculate_cardinality());
+    }
+}
+
+#[test]
+fn test_header_layout_and_size() {
+    let p = 14;
+    let hll = filled(RegisterBackend::U8, p, 0..200_000);
+    let bytes = hll.to_bytes();
+
+    assert_eq!(&bytes[..4], b"HLLS");
+    assert_eq!(bytes[4], FORMAT_VERSION);
+    assert_eq!(bytes[5], p as u8);
+    // 20 bytes of header plus the hash algorithm, seed and parameters, the raw hash marker, the registers and the checksum
+    let header = 20 + 1 + STABLE_HASH_ALGORITHM.len() + 8 + 2 + 1;
+    assert_eq!(&bytes[16..17 + STABLE_HASH_ALGORITHM.len()], b"\x07xxh3-64");
+    assert_eq!(&bytes[17 + STABLE_HASH_ALGORITHM.len()..header - 7], DEFAULT_HASH_SEED.to_le_bytes());
+    // the default hasher has no parameters besides its seed, and items were inserted rather than raw hashes
+    assert_eq!(&bytes[header - 7..header - 4], [0, 0, 0]);
+    let mut marker = bytes[..bytes.len() - 4].to_vec();
+    marker[header - 5] = 2;
+    let checksum = crc32(&marker);
+    marker.extend_from_slice(&checksum.to_le_bytes());
+    assert!(matches!(HyperLogLog::<u64>::from_bytes(&marker), Err(HyperLogLogError::CorruptPayload(_))));
+    assert_eq!(bytes.len(), header + (1 << p) + 4);
+    assert!(bytes.len() * 2 < serde_json::to_vec(&hll).unwrap().len());
+
+    let packed = filled(RegisterBackend::Packed6, p, 0..200_000).to_bytes();
+    assert_eq!(packed.len(), header + (1 << p) * 3 / 4 + 4);
+
+    // a small sparse sketch stays small
+    let sparse = filled(RegisterBackend::U8, p, 0..100).to_bytes();
+    assert!(sparse.len() < header + 4 + 4 + 100 * 4, "{}", sparse.len());
+}
+
+#[test]
+fn test_streaming_several_sketches() {
+    let sketches = [
+        filled(RegisterBackend::U8, 10, 0..50),
+        filled(RegisterBackend::Packed6, 10, 0..50_000),
+        filled(RegisterBackend::Hll4, 12, 0..80_000),
+    ];
+
+    let mut stream = Vec::new();
+    for hll in &sketches {
+        hll.write_to(&mut stream).unwrap();
+    }
+
+    let mut reader = stream.as_slice();
+    for hll in &sketches {
+        let restored = HyperLogLog::<u64>::read_from(&mut reader).unwrap();
+        assert_eq!(restored.get_p(), hll.get_p());
+        assert_eq!(restored.get_buckets(), hll.get_buckets());
+    }
+    assert!(reader.is_empty());
+    assert!(HyperLogLog::<u64>::read_from(&mut reader).is_err());
+}
+
+#[test]
+fn test_rejects_corrupt_input() {
+    let bytes = filled(RegisterBackend::U8, 10, 0..10_000).to_bytes();
+
+    let mut flipped = bytes.clone();
+    flipped[100] ^= 0x01;
+    assert!(matches!(HyperLogLog::<u64>::from_bytes(&flipped), Err(HyperLogLogError::ChecksumMismatch(_, _))));
+
+    let mut magic = bytes.clone();
+    magic[0] = b'X';
+    assert!(matches!(HyperLogLog::<u64>::from_bytes(&magic), Err(HyperLogLogError::InvalidMagic)));
+
+    let mut version = bytes.clone();
+    version[4] = FORMAT_VERSION + 1;
+    let err = HyperLogLog::<u64>::from_bytes(&version).err().unwrap();
+    assert!(matches!(err, HyperLogLogError::UnsupportedVersion(v) if v == FORMAT_VERSION + 1), "{}", err);
+
+    for len in [0, 3, 10, 30, bytes.len() - 1] {
+        assert!(HyperLogLog::<u64>::from_bytes(&bytes[..len]).is_err(), "len={}", len);
+    }
+
+    let mut trailing = bytes.clone();
+    trailing.push(0);
+    assert!(HyperLogLog::<u64>::from_bytes(&trailing).is_err());
+}
+
+/// CRC-32 (IEEE), as the format uses, computed bit by bit.
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+/// A version 3 sketch with the given header fields, the default hasher record and a valid checksum.
+fn crafted(p: u8, representation: u8, backend: u8, payload: &[u8]) -> Vec<u8> {
+    let mut bytes = b"HLLS".to_vec();
+    bytes.extend_from_slice(&[3, p, representation, backend]);
+    bytes.extend_from_slice(&0u64.to_le_bytes());
+    bytes.push(STABLE_HASH_ALGORITHM.len() as u8);
+    bytes.extend_from_slice(STABLE_HASH_ALGORITHM.as_bytes());
+    bytes.extend_from_slice(&DEFAULT_HASH_SEED.to_le_bytes());
+    bytes.extend_from_slice(&0u16.to_le_bytes());
+    bytes.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(payload);
+    let checksum = crc32(&bytes);
+    bytes.extend_from_slice(&checksum.to_le_bytes());
+    bytes
+}
+
+#[test]
+fn test_rejects_precision_above_maximum() {
+    // a well formed blob whose precision is only checked by the reader: sizing its packed
+    // registers used to overflow, panicking in debug builds and aborting in release ones
+    for p in [62, 63] {
+        let blob = crafted(p, 2, 1, &[]);
+        let err = HyperLogLog::<u64>::from_bytes(&blob).err().unwrap();
+        assert!(matches!(err, HyperLogLogError::PrecisionTooLarge), "p={} {}", p, err);
+    }
+
+    // the same blob at a supported precision fails on the payload instead
+    let blob = crafted(MAX_PRECISION as u8, 2, 1, &[]);
+    let err = HyperLogLog::<u64>::from_bytes(&blob).err().unwrap();
+    assert!(matches!(err, HyperLogLogError::BucketCountMismatch(_, 0)), "{}", err);
+}
+
+#[test]
+fn test_out_of_range_precisions_never_crash() {
+    // every representation and backend code, known or not, with payloads that are empty,
+    // an empty sparse list, or too short for anything dense
+    let payloads: [&[u8]; 3] = [&[], &[0, 0, 0, 0], &[0xff; 16]];
+    for p in 24..=255u8 {
+        for representation in 0..=4u8 {
+            for backend in 0..=3u8 {
+                for payload in payloads {
+                    let blob = crafted(p, representation, backend, payload);
+                    match HyperLogLog::<u64>::from_bytes(&blob) {
+                        // only an empty sparse sketch at a supported precision is valid
+                        Ok(mut hll) => {
+                            assert!(p as u32 <= MAX_PRECISION && representation == 0 && payload.len() == 4);
+                            hll.insert(1);
+                            assert_eq!(hll.calculate_cardinality(), 1);
+                        }
+                        Err(err) if p as u32 > MAX_PRECISION => {
+                            assert!(matches!(err, HyperLogLogError::PrecisionTooLarge), "p={} {}", p, err)
+                        }
+                        Err(_) => {}
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_register_size_overflow_is_an_error() {
+    assert_eq!(RegisterBackend::Packed6.size_in_bytes(1 << 10).unwrap(), 768);
+    assert!(RegisterBackend::Packed6.size_in_bytes(usize::MAX).is_err());
+    assert!(RegisterBackend::Packed6.size_in_bytes(1 << 63).is_err());
+    assert_eq!(RegisterBackend::U8.size_in_bytes(usize::MAX).unwrap(), usize::MAX);
+}
+
+#[test]
+fn test_rejects_other_item_type() {
+    let bytes = filled(RegisterBackend::U8, 10, 0..100).to_bytes();
+    assert!(HyperLogLog::<u64>::from_bytes(&bytes).is_ok());
+    assert!(HyperLogLog::<u32>::from_bytes(&bytes).is_err());
+}
+
+#[test]
+fn test_rejects_unsorted_hll4_exceptions() {
+    // base 0, so both values are exceptions, stored last as (index, value) pairs before the checksum
+    let mut buckets = vec![0u8; 16];
+    buckets[0] = 20;
+    buckets[1] = 21;
+    let hll = HyperLogLog::<u64>::from_buckets(4, buckets, Default::default()).unwrap().with_backend(RegisterBackend::Hll4);
+    let bytes = hll.to_bytes();
+    assert_eq!(HyperLogLog::<u64>::from_bytes(&bytes).unwrap().get_buckets(), hll.get_buckets());
+
+    let mut swapped = bytes[..bytes.len() - 4].to_vec();
+    let pairs = swapped.len() - 10;
+    swapped[pairs..].rotate_left(5);
+    let checksum = crc32(&swapped);
+    swapped.extend_from_slice(&checksum.to_le_bytes());
+    let err = HyperLogLog::<u64>::from_bytes(&swapped).err().unwrap();
+    assert!(matches!(err, HyperLogLogError::CorruptPayload(_)), "{}", err);
+}
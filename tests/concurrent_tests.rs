@@ -0,0 +1,92 @@
+use std::thread;
+
+use hyperloglog::{ConcurrentHyperLogLog, HyperLogLog};
+
+fn assert_sync<T: Sync + Send>() {}
+
+#[test]
+fn test_concurrent_is_sync() {
+    assert_sync::<ConcurrentHyperLogLog<u64>>();
+    // items that are not `Sync` themselves can still be inserted from several threads
+    assert_sync::<ConcurrentHyperLogLog<String>>();
+}
+
+#[test]
+fn test_parallel_inserts_match_sequential() {
+    let p = 14;
+    let threads = 8;
+    let per_thread = 20_000u64;
+
+    let shared = ConcurrentHyperLogLog::<u64>::new(p).unwrap();
+    thread::scope(|scope| {
+        for t in 0..threads {
+            let shared = &shared;
+            // overlapping ranges, so that threads race on the same registers
+            scope.spawn(move || {
+                for i in t * per_thread / 2..t * per_thread / 2 + per_thread {
+                    shared.insert(i);
+                }
+            });
+        }
+    });
+
+    let mut sequential = HyperLogLog::<u64>::new(p).unwrap();
+    for i in 0..(threads + 1) * per_thread / 2 {
+        sequential.insert(i);
+    }
+    let snapshot = shared.snapshot();
+    assert_eq!(snapshot.get_buckets(), sequential.get_buckets());
+    assert_eq!(shared.calculate_cardinality(), sequential.calculate_cardinality());
+}
+
+#[test]
+fn test_snapshots_during_ingest_only_grow() {
+    let shared = ConcurrentHyperLogLog::<u64>::new(12).unwrap();
+    let snapshots = thread::scope(|scope| {
+        for t in 0..4u64 {
+            let shared = &shared;
+            scope.spawn(move || {
+                for i in 0..50_000 {
+                    shared.insert(t * 50_000 + i);
+                }
+            });
+        }
+        (0..20).map(|_| shared.snapshot().get_buckets()).collect::<Vec<_>>()
+    });
+
+    let last = shared.snapshot().get_buckets();
+    for pair in snapshots.windows(2) {
+        assert!(pair[0].iter().zip(&pair[1]).all(|(a, b)| a <= b));
+    }
+    assert!(snapshots.iter().all(|snapshot| snapshot.iter().zip(&last).all(|(a, b)| a <= b)));
+}
+
+#[test]
+fn test_snapshot_merges_and_serializes() {
+    let shared = ConcurrentHyperLogLog::<u64>::new(12).unwrap();
+    for i in 0..10_000 {
+        shared.insert(i);
+    }
+
+    let mut other = HyperLogLog::<u64>::new(12).unwrap();
+    for i in 5_000..15_000 {
+        other.insert(i);
+    }
+
+    let mut merged = shared.snapshot();
+    merged.merge(&other).unwrap();
+    let estimate = merged.calculate_cardinality() as f64;
+    assert!((estimate - 15_000.0).abs() < 0.05 * 15_000.0, "{}", estimate);
+
+    let restored = HyperLogLog::<u64>::from_bytes(&shared.snapshot().to_bytes()).unwrap();
+    assert_eq!(restored.get_buckets(), shared.snapshot().get_buckets());
+}
+
+#[test]
+fn test_precision_limits() {
+    assert!(ConcurrentHyperLogLog::<u64>::new(3).is_err());
+    let shared = ConcurrentHyperLogLog::<u64>::new(10).unwrap();
+    assert_eq!(shared.get_p(), 10);
+    assert_eq!(shared.get_m(), 1024);
+    assert_eq!(shared.calculate_cardinality(), 0);
+}
@@ -0,0 +1,100 @@
+use hyperloglog::{HyperLogLog, HyperLogLogError, RegisterBackend, MAX_PRECISION};
+
+#[test]
+fn test_with_error_picks_smallest_precision() {
+    assert_eq!(HyperLogLog::<u64>::with_error(0.01).unwrap().get_p(), 14);
+    assert_eq!(HyperLogLog::<u64>::with_error(0.02).unwrap().get_p(), 12);
+    assert_eq!(HyperLogLog::<u64>::with_error(0.0325).unwrap().get_p(), 10);
+    // never below the minimum precision
+    assert_eq!(HyperLogLog::<u64>::with_error(0.5).unwrap().get_p(), 4);
+
+    for error in [0.005, 0.01, 0.03, 0.1] {
+        let hll = HyperLogLog::<u64>::with_error(error).unwrap();
+        assert!(hll.relative_standard_error() <= error);
+    }
+}
+
+#[test]
+fn test_with_error_rejects_invalid_error() {
+    assert!(HyperLogLog::<u64>::with_error(0.0).is_err());
+    assert!(HyperLogLog::<u64>::with_error(-0.1).is_err());
+    assert!(HyperLogLog::<u64>::with_error(1.5).is_err());
+    assert!(HyperLogLog::<u64>::with_error(f64::NAN).is_err());
+}
+
+#[test]
+fn test_relative_standard_error() {
+    let hll = HyperLogLog::<u64>::new(14).unwrap();
+    assert!((hll.relative_standard_error() - 1.04 / 128.0).abs() < 1e-12);
+}
+
+#[test]
+fn test_confidence_interval_bounds() {
+    let mut hll = HyperLogLog::<u64>::new(12).unwrap();
+    for i in 0..100_000u64 {
+        hll.insert(i);
+    }
+
+    let interval = hll.estimate_with_confidence(0.95).unwrap();
+    let point = interval.estimate.cardinality;
+    assert_eq!(interval.estimate, hll.estimate());
+    assert_eq!(interval.confidence, 0.95);
+
+    // 95% two sided normal interval is +-1.96 standard errors
+    let margin = 1.959964 * interval.relative_standard_error * point;
+    assert!((interval.upper - (point + margin)).abs() < 1e-3 * point);
+    assert!((interval.lower - (point - margin)).abs() < 1e-3 * point);
+    assert!(interval.lower <= 100_000.0 && 100_000.0 <= interval.upper);
+
+    let wider = hll.estimate_with_confidence(0.99).unwrap();
+    assert!(wider.lower < interval.lower && wider.upper > interval.upper);
+}
+
+#[test]
+fn test_confidence_interval_of_empty_sketch() {
+    let hll = HyperLogLog::<u64>::new(10).unwrap();
+    let interval = hll.estimate_with_confidence(0.9).unwrap();
+    assert_eq!(interval.lower, 0.0);
+    assert_eq!(interval.upper, 0.0);
+}
+
+#[test]
+fn test_confidence_rejects_invalid_level() {
+    let hll = HyperLogLog::<u64>::new(10).unwrap();
+    assert!(hll.estimate_with_confidence(0.0).is_err());
+    assert!(hll.estimate_with_confidence(1.0).is_err());
+    assert!(hll.estimate_with_confidence(95.0).is_err());
+}
+
+#[test]
+fn test_memory_cost_and_usage() {
+    assert_eq!(HyperLogLog::<u64>::memory_cost(14).unwrap(), 16 * 1024);
+    assert_eq!(HyperLogLog::<u64>::memory_cost(4).unwrap(), 16);
+
+    let mut hll = HyperLogLog::<u64>::new(14).unwrap();
+    for i in 0..3u64 {
+        hll.insert(i);
+    }
+    assert!(hll.memory_usage() < HyperLogLog::<u64>::memory_cost(14).unwrap());
+
+    for i in 0..100_000u64 {
+        hll.insert(i);
+    }
+    assert_eq!(hll.memory_usage(), HyperLogLog::<u64>::memory_cost(14).unwrap());
+}
+
+#[test]
+fn test_memory_cost_rejects_unsupported_precisions() {
+    assert_eq!(HyperLogLog::<u64>::memory_cost(MAX_PRECISION).unwrap(), 1 << MAX_PRECISION);
+    assert_eq!(HyperLogLog::<u64>::memory_cost_with_backend(MAX_PRECISION, RegisterBackend::Packed6).unwrap(), 3 << (MAX_PRECISION - 2));
+    for p in [0, 3, MAX_PRECISION + 1, 63, 64, 200, u32::MAX] {
+        assert!(HyperLogLog::<u64>::memory_cost(p).is_err(), "p={}", p);
+        for backend in [RegisterBackend::U8, RegisterBackend::Packed6, RegisterBackend::Hll4] {
+            assert!(HyperLogLog::<u64>::memory_cost_with_backend(p, backend).is_err(), "p={} {:?}", p, backend);
+        }
+    }
+
+    // sketches are refused at the same precisions, whichever way they are built
+    assert!(matches!(HyperLogLog::<u64>::new(MAX_PRECISION + 1), Err(HyperLogLogError::PrecisionTooLarge)));
+    assert!(matches!(HyperLogLog::<u64>::with_error(1e-6), Err(HyperLogLogError::PrecisionTooLarge)));
+}
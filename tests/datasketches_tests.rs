@@ -0,0 +1,125 @@
+use hyperloglog::{DataSketchesHasherBuilder, DataSketchesHllType, HyperLogLog, HyperLogLogError};
+
+// Serialized `HllSketch` images of `update(0L) .. update(n - 1L)` in each mode and register type.
+// Built from the DataSketches hashing and serialization rules, not dumped from a JVM.
+// fixtures/datasketches/README.md has the code to capture real ones.
+const EMPTY_LGK12: &[u8] = include_bytes!("fixtures/datasketches/empty_lgk12.bin");
+const LIST_5_LGK12: &[u8] = include_bytes!("fixtures/datasketches/list_5_lgk12.bin");
+const SET_100_LGK12: &[u8] = include_bytes!("fixtures/datasketches/set_100_lgk12.bin");
+const HLL4_20000_LGK12: &[u8] = include_bytes!("fixtures/datasketches/hll4_20000_lgk12.bin");
+const HLL4_30000_LGK10: &[u8] = include_bytes!("fixtures/datasketches/hll4_30000_lgk10_updatable.bin");
+const HLL6_50000_LGK10: &[u8] = include_bytes!("fixtures/datasketches/hll6_50000_lgk10.bin");
+const HLL8_100000_LGK14: &[u8] = include_bytes!("fixtures/datasketches/hll8_100000_lgk14.bin");
+
+const FIXTURES: [(&[u8], u32, i64); 7] = [
+    (EMPTY_LGK12, 12, 0),
+    (LIST_5_LGK12, 12, 5),
+    (SET_100_LGK12, 12, 100),
+    (HLL4_20000_LGK12, 12, 20_000),
+    (HLL4_30000_LGK10, 10, 30_000),
+    (HLL6_50000_LGK10, 10, 50_000),
+    (HLL8_100000_LGK14, 14, 100_000),
+];
+
+fn import(bytes: &[u8], lg_k: u32) -> HyperLogLog<i64, DataSketchesHasherBuilder> {
+    HyperLogLog::from_datasketches(bytes, DataSketchesHasherBuilder::new(lg_k).unwrap()).unwrap()
+}
+
+fn native(lg_k: u32, range: std::ops::Range<i64>) -> HyperLogLog<i64, DataSketchesHasherBuilder> {
+    let mut hll = HyperLogLog::with_hasher(lg_k, DataSketchesHasherBuilder::new(lg_k).unwrap()).unwrap();
+    for i in range {
+        hll.insert(i);
+    }
+    hll
+}
+
+#[test]
+fn test_import_fixtures() {
+    for (bytes, lg_k, n) in FIXTURES {
+        let hll = import(bytes, lg_k);
+        assert_eq!(hll.get_p(), lg_k);
+        let estimate = hll.calculate_cardinality() as f64;
+        assert!((estimate - n as f64).abs() <= 0.05 * n as f64 + 1.0, "lgK={} n={} estimate={}", lg_k, n, estimate);
+    }
+}
+
+#[test]
+fn test_inserts_match_datasketches_registers() {
+    for (bytes, lg_k, n) in FIXTURES {
+        assert_eq!(native(lg_k, 0..n).get_buckets(), import(bytes, lg_k).get_buckets(), "lgK={} n={}", lg_k, n);
+    }
+}
+
+#[test]
+fn test_merge_imported_with_native() {
+    // a sketch from the JVM side merged with one built here covers both ranges
+    let mut merged = import(HLL4_20000_LGK12, 12);
+    merged.merge(&native(12, 20_000..40_000)).unwrap();
+    assert_eq!(merged.get_buckets(), native(12, 0..40_000).get_buckets());
+}
+
+#[test]
+fn test_export_matches_fixtures() {
+    assert_eq!(native(12, 0..0).to_datasketches(DataSketchesHllType::Hll4).unwrap(), EMPTY_LGK12);
+
+    for (bytes, lg_k, n, hll_type) in [
+        (HLL4_20000_LGK12, 12, 20_000, DataSketchesHllType::Hll4),
+        (HLL6_50000_LGK10, 10, 50_000, DataSketchesHllType::Hll6),
+        (HLL8_100000_LGK14, 14, 100_000, DataSketchesHllType::Hll8),
+    ] {
+        let exported = native(lg_k, 0..n).to_datasketches(hll_type).unwrap();
+        assert_eq!(exported.len(), bytes.len());
+        // preamble ints, version, family, lgK, lgArr; flags differ by the out of order bit
+        assert_eq!(exported[..5], bytes[..5], "{:?}", hll_type);
+        assert_eq!(exported[5] & 16, 16);
+        // current minimum and mode, then kxq sums, count at the minimum, registers and exceptions
+        assert_eq!(exported[6..8], bytes[6..8], "{:?}", hll_type);
+        assert!(exported[16..] == bytes[16..], "{:?}", hll_type);
+    }
+}
+
+#[test]
+fn test_export_roundtrip() {
+    for hll_type in [DataSketchesHllType::Hll4, DataSketchesHllType::Hll6, DataSketchesHllType::Hll8] {
+        for (lg_k, n) in [(4, 200_000), (11, 10), (12, 100_000), (21, 1_000)] {
+            let hll = native(lg_k, 0..n);
+            let restored = import(&hll.to_datasketches(hll_type).unwrap(), lg_k);
+            assert_eq!(restored.get_buckets(), hll.get_buckets(), "{:?} lgK={} n={}", hll_type, lg_k, n);
+        }
+    }
+}
+
+#[test]
+fn test_rejects_malformed_images() {
+    let load = |bytes: &[u8]| HyperLogLog::<i64, DataSketchesHasherBuilder>::from_datasketches(bytes, Default::default());
+    assert!(load(HLL4_20000_LGK12).is_ok());
+
+    for (offset, value) in [(0, 3), (1, 2), (2, 8), (3, 3), (3, 22), (5, 1), (7, 3 | 8)] {
+        let mut bytes = HLL4_20000_LGK12.to_vec();
+        bytes[offset] = value;
+        assert!(load(&bytes).is_err(), "byte {} = {}", offset, value);
+    }
+
+    for bytes in [HLL4_20000_LGK12, HLL6_50000_LGK10, HLL8_100000_LGK14, SET_100_LGK12, LIST_5_LGK12] {
+        assert!(load(&bytes[..bytes.len() - 1]).is_err());
+    }
+
+    // an HLL_4 register marked as an exception with no matching entry
+    let mut bytes = HLL4_20000_LGK12.to_vec();
+    let nibbles = 40..40 + 2048;
+    let slot = bytes[nibbles].iter().position(|&b| b & 0x0f != 0x0f).unwrap();
+    bytes[40 + slot] |= 0x0f;
+    assert!(load(&bytes).is_err());
+}
+
+#[test]
+fn test_precision_limits() {
+    assert!(DataSketchesHasherBuilder::new(3).is_err());
+    let err = DataSketchesHasherBuilder::new(22).err().unwrap();
+    assert!(matches!(err, HyperLogLogError::UnsupportedPrecision(22)), "{}", err);
+    assert_eq!(DataSketchesHasherBuilder::default().get_lg_k(), 12);
+
+    let hll = HyperLogLog::<i64>::new(22).unwrap();
+    let err = hll.to_datasketches(DataSketchesHllType::Hll8).err().unwrap();
+    assert_eq!(err.to_string(), "Precision 22 is not supported by the target format");
+}
@@ -0,0 +1,223 @@
+use std::hash::{BuildHasher, Hasher};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::{Duration, UNIX_EPOCH};
+
+use hyperloglog::{CanonicalF32, CanonicalF64, HyperLogLog, HyperLogLogError, StableHasherBuilder, ToBytes};
+
+// collects what `hash_into` writes, to compare it with `to_bytes`
+#[derive(Default)]
+struct Recorder(Vec<u8>);
+
+impl Hasher for Recorder {
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.extend_from_slice(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        0
+    }
+}
+
+fn encoded<T: ToBytes + ?Sized>(item: &T) -> Vec<u8> {
+    let mut recorder = Recorder::default();
+    item.hash_into(&mut recorder);
+    assert_eq!(recorder.0, item.to_bytes());
+    assert_eq!(recorder.0.len(), item.encoded_len());
+    recorder.0
+}
+
+#[test]
+fn test_encodings_do_not_depend_on_the_platform() {
+    // written out byte by byte, so that these hold on big-endian machines too
+    assert_eq!(encoded(&0xABu8), [0xAB]);
+    assert_eq!(encoded(&-2i8), [0xFE]);
+    assert_eq!(encoded(&0x0102u16), [0x02, 0x01]);
+    assert_eq!(encoded(&-2i16), [0xFE, 0xFF]);
+    assert_eq!(encoded(&0x0102_0304u32), [0x04, 0x03, 0x02, 0x01]);
+    assert_eq!(encoded(&-2i32), [0xFE, 0xFF, 0xFF, 0xFF]);
+    assert_eq!(encoded(&0x0102_0304_0506_0708u64), [8, 7, 6, 5, 4, 3, 2, 1]);
+    assert_eq!(encoded(&-2i64), [0xFE, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]);
+    assert_eq!(encoded(&1u128), [[1].as_slice(), &[0; 15]].concat());
+    assert_eq!(encoded(&-1i128), [0xFF; 16]);
+
+    assert_eq!(encoded(&1.0f32), [0x00, 0x00, 0x80, 0x3F]);
+    assert_eq!(encoded(&1.0f64), [0, 0, 0, 0, 0, 0, 0xF0, 0x3F]);
+    assert_eq!(encoded(&-0.0f64), [0, 0, 0, 0, 0, 0, 0, 0x80]);
+
+    assert_eq!(encoded("hé"), [b'h', 0xC3, 0xA9]);
+    assert_eq!(encoded(&"hé"), [b'h', 0xC3, 0xA9]);
+    assert_eq!(encoded(&String::from("hé")), [b'h', 0xC3, 0xA9]);
+    assert_eq!(encoded(&'é'), [0xC3, 0xA9]);
+}
+
+#[test]
+fn test_floats_count_by_bit_pattern() {
+    let mut hll = HyperLogLog::<f64>::new(12).unwrap();
+    let other_nan = f64::from_bits(f64::NAN.to_bits() | 1);
+    for value in [0.0, -0.0, f64::NAN, other_nan, 1.5, 1.5] {
+        hll.insert(value);
+    }
+    assert_eq!(hll.calculate_cardinality(), 5);
+}
+
+#[test]
+fn test_canonical_floats_count_by_value() {
+    let mut hll = HyperLogLog::<CanonicalF64>::new(12).unwrap();
+    let other_nan = f64::from_bits(f64::NAN.to_bits() | 1);
+    for value in [0.0, -0.0, f64::NAN, -f64::NAN, other_nan, 1.5, 1.5, -1.5] {
+        hll.insert(value.into());
+    }
+    assert_eq!(hll.calculate_cardinality(), 4);
+    assert_eq!(encoded(&CanonicalF64(-0.0)), encoded(&0.0f64));
+    assert_eq!(encoded(&CanonicalF64(f64::NAN)), encoded(&CanonicalF64(other_nan)));
+
+    let mut hll = HyperLogLog::<CanonicalF32>::new(12).unwrap();
+    for value in [0.0f32, -0.0, f32::NAN, f32::from_bits(f32::NAN.to_bits() | 1), 2.0] {
+        hll.insert(value.into());
+    }
+    assert_eq!(hll.calculate_cardinality(), 3);
+    assert_eq!(encoded(&CanonicalF32(-0.0)), encoded(&0.0f32));
+}
+
+#[test]
+fn test_canonical_and_raw_floats_do_not_mix() {
+    let raw = HyperLogLog::<f64>::new(12).unwrap();
+    let mut canonical = HyperLogLog::<CanonicalF64>::new(12).unwrap();
+    assert!(HyperLogLog::<CanonicalF64>::from_bytes(&raw.to_bytes()).is_err());
+    assert!(HyperLogLog::<f64>::from_bytes(&canonical.to_bytes()).is_err());
+
+    let other = HyperLogLog::<CanonicalF64>::new(12).unwrap();
+    canonical.merge(&other).unwrap();
+    let from_raw = HyperLogLog::<CanonicalF64>::from_bytes(&raw.to_bytes());
+    assert!(matches!(from_raw, Err(HyperLogLogError::HasherMismatch)));
+}
+
+#[test]
+fn test_composite_encodings_do_not_collide() {
+    assert_ne!(encoded(&("ab", "c")), encoded(&("a", "bc")));
+    assert_ne!(encoded(&vec!["ab", "c"]), encoded(&vec!["a", "bc"]));
+    assert_ne!(encoded(&vec!["", ""]), encoded(&vec![""]));
+    assert_ne!(encoded(&(Some("a"), "")), encoded(&(None::<&str>, "a")));
+    assert_ne!(encoded(&Some(None::<u8>)), encoded(&None::<Option<u8>>));
+    assert_ne!(encoded(&(vec![1u8, 2], vec![3u8])), encoded(&(vec![1u8], vec![2u8, 3])));
+
+    let mut hll = HyperLogLog::<(String, String)>::new(12).unwrap();
+    for (a, b) in [("ab", "c"), ("a", "bc"), ("abc", ""), ("", "abc"), ("ab", "c")] {
+        hll.insert((a.to_string(), b.to_string()));
+    }
+    assert_eq!(hll.calculate_cardinality(), 4);
+}
+
+#[test]
+fn test_composite_encodings() {
+    // parts of a fixed length are written as is, others after their length
+    assert_eq!(encoded(&(1u16, true)), [1, 0, 1]);
+    assert_eq!(encoded(&(7u8, "hi")), [7, 2, 0, 0, 0, 0, 0, 0, 0, b'h', b'i']);
+    assert_eq!(encoded(&[1u16, 2]), [1, 0, 2, 0]);
+    assert_eq!(encoded(&None::<u32>), [0]);
+    assert_eq!(encoded(&Some(5u32)), [1, 5, 0, 0, 0]);
+    assert_eq!(encoded(&Some("a")), [1, 1, 0, 0, 0, 0, 0, 0, 0, b'a']);
+
+    // byte sequences are their bytes, and all sequences of one type encode alike
+    let bytes: Vec<u8> = vec![1, 2, 3];
+    assert_eq!(encoded(&bytes), [1, 2, 3]);
+    assert_eq!(encoded(bytes.as_slice()), [1, 2, 3]);
+    assert_eq!(encoded(&bytes.as_slice()), [1, 2, 3]);
+    assert_eq!(encoded(&[1u8, 2, 3]), [1, 2, 3]);
+    assert_eq!(encoded(&vec![String::from("ab")]), encoded(["ab"].as_slice()));
+
+    assert_eq!(<(u32, u16)>::FIXED_LEN, Some(6));
+    assert_eq!(<[u64; 4]>::FIXED_LEN, Some(32));
+    assert_eq!(<(u8, String)>::FIXED_LEN, None);
+    assert_eq!(<Vec<u8>>::FIXED_LEN, None);
+}
+
+#[test]
+fn test_composite_type_ids() {
+    assert_eq!(<(u64, String)>::TYPE_ID, b"(u64, String)");
+    assert_eq!(<(u8,)>::TYPE_ID, b"(u8,)");
+    assert_eq!(<[u8; 16]>::TYPE_ID, b"[u8; 16]");
+    assert_eq!(<[u8]>::TYPE_ID, b"[u8]");
+    assert_eq!(<&[u8]>::TYPE_ID, b"&[u8]");
+    assert_eq!(<Vec<Option<&str>>>::TYPE_ID, b"Vec<Option<&str>>");
+    assert_eq!(
+        <(u8, u16, u32, u64, i8, i16, i32, i64, bool, char, String, (f32, f64))>::TYPE_ID,
+        b"(u8, u16, u32, u64, i8, i16, i32, i64, bool, char, String, (f32, f64))"
+    );
+
+    // sketches of different composite types do not mix
+    let pairs = HyperLogLog::<(u32, u32)>::new(10).unwrap().to_bytes();
+    assert!(HyperLogLog::<(u32, u32)>::from_bytes(&pairs).is_ok());
+    assert!(HyperLogLog::<[u32; 2]>::from_bytes(&pairs).is_err());
+    assert!(HyperLogLog::<(u32, u64)>::from_bytes(&pairs).is_err());
+}
+
+#[test]
+fn test_byte_sequences_hash_in_one_write() {
+    #[derive(Default)]
+    struct WriteCounter(usize);
+
+    impl Hasher for WriteCounter {
+        fn write(&mut self, _: &[u8]) {
+            self.0 += 1;
+        }
+
+        fn finish(&self) -> u64 {
+            0
+        }
+    }
+
+    let bytes: Vec<u8> = (0..10_000u32).map(|i| (i * 31) as u8).collect();
+    let mut counter = WriteCounter::default();
+    bytes.hash_into(&mut counter);
+    assert_eq!(counter.0, 1);
+
+    // streamed or written as one buffer, the bytes hash alike
+    assert_streamed_hash_is_whole(&bytes);
+    assert_streamed_hash_is_whole(&vec![String::from("ab"), String::new()]);
+    assert_streamed_hash_is_whole(&[1u32, 2, 3]);
+    assert_streamed_hash_is_whole(&vec![vec![1u8, 2], vec![3u8]]);
+}
+
+fn assert_streamed_hash_is_whole<T: ToBytes>(item: &T) {
+    let builder = StableHasherBuilder::default();
+    let mut streamed = builder.build_hasher();
+    item.hash_into(&mut streamed);
+    let mut whole = builder.build_hasher();
+    whole.write(&item.to_bytes());
+    assert_eq!(streamed.finish(), whole.finish());
+}
+
+#[test]
+fn test_byte_slices_insert_into_byte_vector_sketches() {
+    let mut owned = HyperLogLog::<Vec<u8>>::new(12).unwrap();
+    let mut borrowed = HyperLogLog::<Vec<u8>>::new(12).unwrap();
+    for i in 0..1_000u32 {
+        owned.insert(i.to_be_bytes().to_vec());
+        borrowed.insert_ref(&i.to_be_bytes()[..]);
+    }
+    assert_eq!(owned.get_buckets(), borrowed.get_buckets());
+}
+
+#[test]
+fn test_address_and_time_encodings() {
+    let v4 = Ipv4Addr::new(192, 168, 0, 1);
+    let v6 = Ipv6Addr::LOCALHOST;
+    assert_eq!(encoded(&v4), [192, 168, 0, 1]);
+    assert_eq!(encoded(&v6), [[0; 15].as_slice(), &[1]].concat());
+    assert_eq!(encoded(&IpAddr::V4(v4)), [4, 192, 168, 0, 1]);
+    assert_eq!(encoded(&IpAddr::V6(v6))[0], 6);
+    assert_ne!(encoded(&IpAddr::V6(v4.to_ipv6_mapped())), encoded(&IpAddr::V4(v4)));
+
+    assert_eq!(encoded(&SocketAddr::from((v4, 8080))), [4, 192, 168, 0, 1, 0x90, 0x1F]);
+    let socket = SocketAddr::from((v6, 443));
+    assert_eq!(encoded(&socket).len(), 1 + 16 + 2 + 4 + 4);
+    assert_ne!(encoded(&socket), encoded(&SocketAddr::from((v6, 444))));
+
+    assert_eq!(encoded(&Duration::new(1, 2)), [1, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0]);
+    let after = UNIX_EPOCH + Duration::new(1, 2);
+    assert_eq!(encoded(&after), [0, 1, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0]);
+    let before = UNIX_EPOCH - Duration::new(1, 2);
+    assert_eq!(encoded(&before), [1, 1, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0]);
+    assert_eq!(encoded(&UNIX_EPOCH), [0; 13]);
+}
@@ -0,0 +1,119 @@
+use hyperloglog::{EstimationMethod, Estimator, HyperLogLog};
+
+/// Mean and root mean square of the relative error of `estimator` over `runs` independent sketches of `n` items
+fn relative_errors(p: u32, n: u64, runs: u64, estimator: Estimator) -> (f64, f64) {
+    let (mut total, mut total_sq) = (0.0, 0.0);
+    for run in 0..runs {
+        let mut hll = HyperLogLog::<u64>::new(p).unwrap();
+        for i in 0..n {
+            hll.insert(run << 32 | i);
+        }
+        let error = (hll.calculate_cardinality_with(estimator) as f64 - n as f64) / n as f64;
+        total += error;
+        total_sq += error * error;
+    }
+    (total / runs as f64, (total_sq / runs as f64).sqrt())
+}
+
+#[test]
+fn test_default_estimator_is_hyperloglog_plus_plus() {
+    let hll = HyperLogLog::<u64>::new(10).unwrap();
+    assert_eq!(hll.get_estimator(), Estimator::HyperLogLogPlusPlus);
+}
+
+#[test]
+fn test_selected_estimator_is_used() {
+    let mut hll = HyperLogLog::<u64>::new(10).unwrap().with_estimator(Estimator::Classic);
+    for i in 0..3_000u64 {
+        hll.insert(i);
+    }
+    assert_eq!(hll.calculate_cardinality(), hll.calculate_cardinality_with(Estimator::Classic));
+
+    hll.set_estimator(Estimator::HyperLogLogPlusPlus);
+    assert_eq!(hll.calculate_cardinality(), hll.calculate_cardinality_with(Estimator::HyperLogLogPlusPlus));
+}
+
+#[test]
+fn test_estimators_agree_on_empty_and_tiny_sets() {
+    let mut hll = HyperLogLog::<u64>::new(12).unwrap();
+    assert_eq!(hll.calculate_cardinality_with(Estimator::Classic), 0);
+    assert_eq!(hll.calculate_cardinality_with(Estimator::HyperLogLogPlusPlus), 0);
+
+    for i in 0..10u64 {
+        hll.insert(i);
+    }
+    assert_eq!(hll.calculate_cardinality_with(Estimator::Classic), 10);
+    assert_eq!(hll.calculate_cardinality_with(Estimator::HyperLogLogPlusPlus), 10);
+}
+
+#[test]
+fn test_bias_correction_reduces_mid_range_bias() {
+    // around the classic 2.5m switch, where the raw estimate is still biased upwards
+    let p = 8;
+    let n = 500;
+    let (_, classic_rmse) = relative_errors(p, n, 500, Estimator::Classic);
+    let (corrected_mean, corrected_rmse) = relative_errors(p, n, 500, Estimator::HyperLogLogPlusPlus);
+
+    assert!(
+        corrected_rmse < classic_rmse,
+        "bias corrected error {} should be below classic error {}", corrected_rmse, classic_rmse
+    );
+    assert!(corrected_mean.abs() < 0.01, "bias corrected mean error too large: {}", corrected_mean);
+}
+
+#[test]
+fn test_large_precision_falls_back_to_classic() {
+    let mut hll = HyperLogLog::<u64>::new(20).unwrap();
+    for i in 0..50_000u64 {
+        hll.insert(i);
+    }
+    assert_eq!(
+        hll.calculate_cardinality_with(Estimator::HyperLogLogPlusPlus),
+        hll.calculate_cardinality_with(Estimator::Classic)
+    );
+}
+
+#[test]
+fn test_estimate_reports_method() {
+    let mut hll = HyperLogLog::<u64>::new(8).unwrap();
+    for i in 0..20u64 {
+        hll.insert(i);
+    }
+    assert_eq!(hll.estimate_with(Estimator::Classic).method, EstimationMethod::LinearCounting);
+    assert_eq!(hll.estimate_with(Estimator::Ertl).method, EstimationMethod::Ertl);
+
+    for i in 20..1_000u64 {
+        hll.insert(i);
+    }
+    assert_eq!(hll.estimate_with(Estimator::Classic).method, EstimationMethod::Raw);
+    assert_eq!(hll.estimate_with(Estimator::HyperLogLogPlusPlus).method, EstimationMethod::BiasCorrected);
+    assert_eq!(hll.estimate().rounded(), hll.calculate_cardinality());
+}
+
+#[test]
+fn test_ertl_estimator_accuracy_across_ranges() {
+    let p = 10;
+    let mut hll = HyperLogLog::<u64>::new(p).unwrap().with_estimator(Estimator::Ertl);
+    assert_eq!(hll.calculate_cardinality(), 0);
+
+    let mut inserted = 0u64;
+    for n in [10u64, 100, 1_000, 2_500, 10_000, 100_000, 1_000_000] {
+        while inserted < n {
+            hll.insert(inserted);
+            inserted += 1;
+        }
+        let estimate = hll.calculate_cardinality() as f64;
+        let error = (estimate - n as f64).abs() / n as f64;
+        // three standard errors
+        assert!(error < 3.0 * 1.04 / ((1u64 << p) as f64).sqrt(), "n={} estimate={}", n, estimate);
+    }
+}
+
+#[test]
+fn test_ertl_estimator_is_unbiased_mid_range() {
+    let (mean, rmse) = relative_errors(8, 500, 500, Estimator::Ertl);
+    let (_, classic_rmse) = relative_errors(8, 500, 500, Estimator::Classic);
+
+    assert!(mean.abs() < 0.01, "Ertl mean error too large: {}", mean);
+    assert!(rmse < classic_rmse, "Ertl error {} should be below classic error {}", rmse, classic_rmse);
+}
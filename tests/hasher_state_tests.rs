@@ -0,0 +1,153 @@
+use std::hash::{BuildHasher, Hasher};
+
+use hyperloglog::{
+    DataSketchesHasherBuilder, HasherIdentity, HasherState, HyperLogLog, HyperLogLogError, PostgresHllHasherBuilder,
+    StableHasher, StableHasherBuilder,
+};
+
+// a keyed hasher defined outside of the crate: XXH3 over a 16-byte key followed by the item
+#[derive(Debug, Clone, PartialEq, Default)]
+struct KeyedHasherBuilder {
+    key: [u8; 16],
+}
+
+impl BuildHasher for KeyedHasherBuilder {
+    type Hasher = StableHasher;
+
+    fn build_hasher(&self) -> StableHasher {
+        let mut hasher = StableHasherBuilder::new(0).build_hasher();
+        hasher.write(&self.key);
+        hasher
+    }
+}
+
+impl HasherIdentity for KeyedHasherBuilder {
+    fn algorithm(&self) -> &'static str {
+        "test-keyed-xxh3-64"
+    }
+
+    fn seed(&self) -> u64 {
+        0
+    }
+}
+
+impl HasherState for KeyedHasherBuilder {
+    fn parameters(&self) -> Vec<u8> {
+        self.key.to_vec()
+    }
+
+    fn restore(seed: u64, parameters: &[u8]) -> Option<Self> {
+        Some(KeyedHasherBuilder { key: parameters.try_into().ok()? }).filter(|_| seed == 0)
+    }
+}
+
+// an unseeded hasher defined outside of the crate, made serializable as the README shows
+#[derive(Debug, Clone, Default)]
+struct UnseededHasherBuilder;
+
+impl BuildHasher for UnseededHasherBuilder {
+    type Hasher = StableHasher;
+
+    fn build_hasher(&self) -> StableHasher {
+        StableHasherBuilder::new(42).build_hasher()
+    }
+}
+
+impl HasherIdentity for UnseededHasherBuilder {
+    fn algorithm(&self) -> &'static str {
+        "test-unseeded-xxh3-64"
+    }
+
+    fn seed(&self) -> u64 {
+        0
+    }
+}
+
+impl HasherState for UnseededHasherBuilder {
+    fn restore(seed: u64, parameters: &[u8]) -> Option<Self> {
+        (seed == 0 && parameters.is_empty()).then(Self::default)
+    }
+}
+
+fn filled<S: BuildHasher + Default + Clone>(hasher_builder: S, p: u32, n: u64) -> HyperLogLog<u64, S> {
+    let mut hll = HyperLogLog::with_hasher(p, hasher_builder).unwrap();
+    for i in 0..n {
+        hll.insert(i);
+    }
+    hll
+}
+
+#[test]
+fn test_keyed_sketch_survives_restart() {
+    let key = KeyedHasherBuilder { key: *b"0123456789abcdef" };
+    for n in [100, 20_000] {
+        let hll = filled(key.clone(), 12, n);
+
+        let restored = HyperLogLog::<u64, KeyedHasherBuilder>::from_bytes(&hll.to_bytes()).unwrap();
+        assert_eq!(restored.get_buckets(), hll.get_buckets());
+        let restored: HyperLogLog<u64, KeyedHasherBuilder> = serde_json::from_str(&serde_json::to_string(&hll).unwrap()).unwrap();
+        assert_eq!(restored.get_buckets(), hll.get_buckets());
+
+        // the restored sketch keeps hashing with the key, so it still merges with the original
+        let mut more = restored.clone();
+        more.insert(n + 1);
+        more.merge(&hll).unwrap();
+        assert!(more.merge(&filled(KeyedHasherBuilder::default(), 12, 10)).is_err());
+    }
+}
+
+#[test]
+fn test_custom_unseeded_hasher_serializes() {
+    let hll = filled(UnseededHasherBuilder, 12, 5_000);
+    let json = serde_json::to_string(&hll).unwrap();
+    let restored: HyperLogLog<u64, UnseededHasherBuilder> = serde_json::from_str(&json).unwrap();
+    assert_eq!(restored.get_buckets(), hll.get_buckets());
+    assert!(serde_json::from_str::<HyperLogLog<u64>>(&json).is_err());
+}
+
+#[test]
+fn test_random_seed_survives_restart() {
+    let hll = filled(StableHasherBuilder::random(), 12, 5_000);
+    assert_ne!(StableHasherBuilder::random(), StableHasherBuilder::random());
+
+    let mut restored = HyperLogLog::<u64, StableHasherBuilder>::from_bytes(&hll.to_bytes()).unwrap();
+    assert_eq!(restored.get_buckets(), hll.get_buckets());
+    // new inserts hash with the same seed, so known items change nothing
+    for i in 0..5_000 {
+        restored.insert(i);
+    }
+    assert_eq!(restored.get_buckets(), hll.get_buckets());
+}
+
+#[test]
+fn test_crate_hashers_restore_their_parameters() {
+    let datasketches = filled(DataSketchesHasherBuilder::new(14).unwrap(), 14, 3_000);
+    let restored = HyperLogLog::<u64, DataSketchesHasherBuilder>::from_bytes(&datasketches.to_bytes()).unwrap();
+    assert_eq!(restored.get_buckets(), datasketches.get_buckets());
+
+    let postgres = filled(PostgresHllHasherBuilder::new(13).unwrap(), 13, 3_000);
+    let json = serde_json::to_string(&postgres).unwrap();
+    let restored: HyperLogLog<u64, PostgresHllHasherBuilder> = serde_json::from_str(&json).unwrap();
+    assert_eq!(restored.get_buckets(), postgres.get_buckets());
+}
+
+#[test]
+fn test_unrestorable_hashers_are_refused() {
+    let hll = filled(KeyedHasherBuilder { key: [7; 16] }, 10, 100);
+    let bytes = hll.to_bytes();
+
+    // a key of the wrong length does not describe the hasher
+    let mut json: serde_json::Value = serde_json::to_value(&hll).unwrap();
+    json["hash_parameters"] = serde_json::json!([1, 2, 3]);
+    assert!(serde_json::from_value::<HyperLogLog<u64, KeyedHasherBuilder>>(json).is_err());
+
+    // another key matches the recorded algorithm, but not the fingerprint
+    let mut json: serde_json::Value = serde_json::to_value(&hll).unwrap();
+    json["hash_parameters"] = serde_json::to_value([8u8; 16]).unwrap();
+    assert!(serde_json::from_value::<HyperLogLog<u64, KeyedHasherBuilder>>(json).is_err());
+
+    // and sketches of other hashers are not read as keyed ones
+    assert!(matches!(HyperLogLog::<u64, StableHasherBuilder>::from_bytes(&bytes), Err(HyperLogLogError::HasherMismatch)));
+    let default = filled(StableHasherBuilder::default(), 10, 100).to_bytes();
+    assert!(matches!(HyperLogLog::<u64, KeyedHasherBuilder>::from_bytes(&default), Err(HyperLogLogError::HasherMismatch)));
+}
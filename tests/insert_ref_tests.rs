@@ -0,0 +1,86 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use hyperloglog::{ConcurrentHyperLogLog, HyperLogLog, RedisHasherBuilder, Representation};
+
+// counts the allocations of the current thread, so that tests running in parallel do not interfere
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+fn allocations_during(f: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.with(Cell::get);
+    f();
+    ALLOCATIONS.with(Cell::get) - before
+}
+
+#[test]
+fn test_insert_ref_matches_insert() {
+    let words: Vec<String> = (0..5_000).map(|i| format!("word-{}", i)).collect();
+
+    let mut owned = HyperLogLog::<String>::new(12).unwrap();
+    let mut borrowed = HyperLogLog::<String>::new(12).unwrap();
+    for word in &words {
+        owned.insert(word.clone());
+        borrowed.insert_ref(word.as_str());
+    }
+    assert_eq!(borrowed.get_buckets(), owned.get_buckets());
+
+    let mut numbers = HyperLogLog::<u64>::new(12).unwrap();
+    let mut numbers_ref = HyperLogLog::<u64>::new(12).unwrap();
+    for i in 0..5_000u64 {
+        numbers.insert(i);
+        numbers_ref.insert_ref(&i);
+    }
+    assert_eq!(numbers_ref.get_buckets(), numbers.get_buckets());
+
+    let shared = ConcurrentHyperLogLog::<String>::new(12).unwrap();
+    for word in &words {
+        shared.insert_ref(word.as_str());
+    }
+    assert_eq!(shared.snapshot().get_buckets(), owned.get_buckets());
+}
+
+#[test]
+fn test_dense_inserts_do_not_allocate() {
+    let mut numbers = HyperLogLog::<u64>::new(10).unwrap();
+    for i in 0..10_000u64 {
+        numbers.insert(i);
+    }
+    assert_eq!(numbers.representation(), Representation::Dense);
+    assert_eq!(allocations_during(|| (0..10_000u64).for_each(|i| numbers.insert(i))), 0);
+
+    let words: Vec<String> = (0..10_000).map(|i| format!("word-{}", i)).collect();
+    let mut strings = HyperLogLog::<String>::new(10).unwrap();
+    for word in &words {
+        strings.insert_ref(word.as_str());
+    }
+    assert_eq!(strings.representation(), Representation::Dense);
+    assert_eq!(allocations_during(|| words.iter().for_each(|word| strings.insert_ref(word.as_str()))), 0);
+
+    // hashers that see all of the input at once keep short items on the stack
+    let mut redis = HyperLogLog::<String, RedisHasherBuilder>::with_hasher(14, RedisHasherBuilder).unwrap();
+    for word in &words {
+        redis.insert_ref(word.as_str());
+    }
+    assert_eq!(allocations_during(|| words.iter().for_each(|word| redis.insert_ref(word.as_str()))), 0);
+
+    let shared = ConcurrentHyperLogLog::<String>::new(10).unwrap();
+    assert_eq!(allocations_during(|| words.iter().for_each(|word| shared.insert_ref(word.as_str()))), 0);
+}
@@ -0,0 +1,151 @@
+use std::hash::{BuildHasher, Hasher};
+
+use hyperloglog::{HasherIdentity, HyperLogLog, HyperLogLogError, KeyedHasherBuilder, ToBytes, KEYED_HASH_ALGORITHM};
+
+const KEY: [u8; 16] = *b"0123456789abcdef";
+
+fn filled(key_id: u32, key: [u8; 16], p: u32, range: std::ops::Range<u64>) -> HyperLogLog<u64, KeyedHasherBuilder> {
+    let mut hll = HyperLogLog::with_key(p, key_id, key).unwrap();
+    for i in range {
+        hll.insert(i);
+    }
+    hll
+}
+
+#[allow(deprecated)]
+fn reference_siphash(key: [u8; 16], bytes: &[u8]) -> u64 {
+    let k0 = u64::from_le_bytes(key[..8].try_into().unwrap());
+    let k1 = u64::from_le_bytes(key[8..].try_into().unwrap());
+    let mut hasher = std::hash::SipHasher::new_with_keys(k0, k1);
+    hasher.write(bytes);
+    hasher.finish()
+}
+
+#[test]
+fn test_keyed_hasher_is_siphash_2_4() {
+    let key: [u8; 16] = std::array::from_fn(|i| i as u8);
+    let message: Vec<u8> = (0..15).collect();
+    // reference vector of the SipHash paper: key 00..0f, message 00..0e
+    assert_eq!(reference_siphash(key, &message), 0xa129_ca61_49be_45e5);
+
+    // the key id comes first, then the item, however it is split into writes
+    let input: Vec<u8> = (0..100u32).map(|i| (i * 37 % 256) as u8).collect();
+    for key_id in [0u32, 1, 0xdead_beef] {
+        let builder = KeyedHasherBuilder::new(key_id, key);
+        for len in [0, 1, 3, 4, 7, 8, 9, 15, 16, 17, 100] {
+            let mut expected = key_id.to_le_bytes().to_vec();
+            expected.extend_from_slice(&input[..len]);
+            for chunk_len in [1, 3, 8, 100] {
+                let mut hasher = builder.build_hasher();
+                for chunk in input[..len].chunks(chunk_len) {
+                    hasher.write(chunk);
+                }
+                assert_eq!(hasher.finish(), reference_siphash(key, &expected), "len={} chunk={}", len, chunk_len);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_keyed_sketch_estimates() {
+    for n in [100, 10_000, 200_000] {
+        let hll = filled(1, KEY, 12, 0..n);
+        let estimate = hll.calculate_cardinality() as f64;
+        assert!((estimate - n as f64).abs() < 0.05 * n as f64, "n={} estimate={}", n, estimate);
+    }
+}
+
+#[test]
+fn test_inputs_crafted_for_one_key_do_not_transfer() {
+    // items an adversary picked, knowing the zero key, to all land in register 0
+    let public = KeyedHasherBuilder::new(0, [0; 16]);
+    let crafted: Vec<u64> = (0..)
+        .filter(|i: &u64| {
+            let mut hasher = public.build_hasher();
+            i.hash_into(&mut hasher);
+            hasher.finish() >> 54 == 0
+        })
+        .take(200)
+        .collect();
+
+    let mut known = HyperLogLog::<u64, KeyedHasherBuilder>::with_hasher(10, public).unwrap();
+    let mut secret = HyperLogLog::<u64, KeyedHasherBuilder>::with_key(10, 1, KEY).unwrap();
+    for &item in &crafted {
+        known.insert(item);
+        secret.insert(item);
+    }
+    assert_eq!(known.get_buckets()[1..].iter().filter(|&&r| r != 0).count(), 0);
+    // under a secret key they spread over the registers like any other items
+    let touched = secret.get_buckets().iter().filter(|&&r| r != 0).count();
+    assert!(touched > 150, "{}", touched);
+}
+
+#[test]
+fn test_sketches_of_other_keys_do_not_merge() {
+    let mut hll = filled(1, KEY, 12, 0..1_000);
+    hll.merge(&filled(1, KEY, 12, 500..2_000)).unwrap();
+
+    let other_key = filled(1, *b"fedcba9876543210", 12, 0..1_000);
+    assert!(matches!(hll.merge(&other_key), Err(HyperLogLogError::HasherMismatch)));
+    // the same key in another rotation generation is another hash function too
+    let other_id = filled(2, KEY, 12, 0..1_000);
+    assert!(matches!(hll.merge(&other_id), Err(HyperLogLogError::HasherMismatch)));
+}
+
+#[test]
+fn test_keyed_sketches_load_only_with_their_key() {
+    let hll = filled(3, KEY, 12, 0..5_000);
+    let bytes = hll.to_bytes();
+    let json = serde_json::to_string(&hll).unwrap();
+
+    // the key is not in the serialized sketch, only its id and check
+    assert!(!bytes.windows(8).any(|w| w == &KEY[..8] || w == &KEY[8..]));
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(value["hash_algorithm"], KEYED_HASH_ALGORITHM);
+
+    // so it cannot be restored from the record alone
+    assert!(HyperLogLog::<u64, KeyedHasherBuilder>::from_bytes(&bytes).is_err());
+    assert!(serde_json::from_str::<HyperLogLog<u64, KeyedHasherBuilder>>(&json).is_err());
+
+    let key = KeyedHasherBuilder::new(3, KEY);
+    let restored = HyperLogLog::<u64, _>::from_bytes_with_hasher(&bytes, key.clone()).unwrap();
+    assert_eq!(restored.get_buckets(), hll.get_buckets());
+    let mut deserializer = serde_json::Deserializer::from_str(&json);
+    let mut restored = HyperLogLog::<u64, _>::deserialize_with_hasher(&mut deserializer, key).unwrap();
+    assert_eq!(restored.get_buckets(), hll.get_buckets());
+    restored.merge(&hll).unwrap();
+
+    for wrong in [KeyedHasherBuilder::new(3, [0; 16]), KeyedHasherBuilder::new(4, KEY)] {
+        let err = HyperLogLog::<u64, _>::from_bytes_with_hasher(&bytes, wrong.clone()).err().unwrap();
+        assert!(matches!(err, HyperLogLogError::HasherMismatch), "{}", err);
+        let mut deserializer = serde_json::Deserializer::from_str(&json);
+        assert!(HyperLogLog::<u64, _>::deserialize_with_hasher(&mut deserializer, wrong).is_err());
+    }
+}
+
+#[test]
+fn test_key_stays_out_of_debug_output() {
+    let builder = KeyedHasherBuilder::new(7, KEY);
+    let debug = format!("{:?}", builder);
+    assert!(debug.contains("key_id: 7"), "{}", debug);
+    assert!(!debug.contains(&format!("{:?}", KEY)) && !debug.contains("0123456789abcdef"), "{}", debug);
+    assert_eq!(builder.algorithm(), KEYED_HASH_ALGORITHM);
+    assert_ne!(builder.key_check(), KeyedHasherBuilder::new(7, [0; 16]).key_check());
+}
+
+#[test]
+fn test_default_key_is_random() {
+    let zero = KeyedHasherBuilder::new(0, [0; 16]);
+    let (a, b) = (KeyedHasherBuilder::default(), KeyedHasherBuilder::default());
+    assert_eq!(a.key_id(), 0);
+    assert_ne!(a.key_check(), zero.key_check());
+    assert_ne!(a.key_check(), b.key_check());
+    assert_eq!(KeyedHasherBuilder::random(5).key_id(), 5);
+
+    // sketches of two default builders hash differently, so they do not merge
+    let mut first = HyperLogLog::<u64, _>::with_hasher(10, a).unwrap();
+    let second = HyperLogLog::<u64, _>::with_hasher(10, b).unwrap();
+    first.insert(1);
+    let err = first.merge(&second).err().unwrap();
+    assert!(matches!(err, HyperLogLogError::HasherMismatch), "{}", err);
+}
@@ -0,0 +1,112 @@
+use hyperloglog::{Estimator, HyperLogLog};
+
+/// splitmix64, deterministic stand-in for random numbers
+fn next_u64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Draws the registers of a sketch with `p` bits after inserting `n` distinct items.
+/// Each register sees about n/m items, and the max of their ranks is at most k with probability exp(-n/m * 2^-k).
+fn synthetic_buckets(p: u32, n: f64, seed: u64) -> Vec<u8> {
+    let m = (1u64 << p) as f64;
+    let lambda = n / m;
+    let max_rank = (64 - p + 1) as f64;
+    let mut state = seed;
+
+    (0..1usize << p)
+        .map(|_| {
+            // uniform in (0, 1)
+            let u = ((next_u64(&mut state) >> 11) as f64 + 0.5) / (1u64 << 53) as f64;
+            let k = (lambda / -u.ln()).log2().ceil();
+            k.clamp(0.0, max_rank) as u8
+        })
+        .collect()
+}
+
+#[test]
+fn test_rank_is_capped_at_64_minus_p_plus_1() {
+    use std::hash::{BuildHasher, Hasher};
+
+    #[derive(Clone, Default)]
+    struct ZeroHasherBuilder;
+    struct ZeroHasher;
+    impl BuildHasher for ZeroHasherBuilder {
+        type Hasher = ZeroHasher;
+        fn build_hasher(&self) -> ZeroHasher { ZeroHasher }
+    }
+    impl Hasher for ZeroHasher {
+        fn write(&mut self, _bytes: &[u8]) {}
+        fn finish(&self) -> u64 { 0 }
+    }
+
+    for p in [4, 10, 14, 25] {
+        let mut hll = HyperLogLog::<u64, ZeroHasherBuilder>::with_hasher(p, ZeroHasherBuilder).unwrap();
+        hll.insert(1);
+        assert_eq!(hll.get_buckets()[0], (64 - p + 1) as u8, "p={}", p);
+    }
+}
+
+#[test]
+fn test_from_buckets_rejects_invalid_registers() {
+    let p = 10;
+    assert!(HyperLogLog::<u64>::from_buckets(p, vec![0; 1 << p], Default::default()).is_ok());
+    assert!(HyperLogLog::<u64>::from_buckets(p, vec![0; 100], Default::default()).is_err());
+
+    let mut buckets = vec![0; 1 << p];
+    buckets[3] = 64 - p as u8 + 2;
+    assert!(HyperLogLog::<u64>::from_buckets(p, buckets, Default::default()).is_err());
+}
+
+#[test]
+fn test_accurate_estimates_up_to_2_pow_50() {
+    let p = 14;
+    let tolerance = 3.0 * 1.04 / ((1u64 << p) as f64).sqrt();
+
+    for exp in (20..=50).step_by(5) {
+        let n = 2f64.powi(exp);
+        let buckets = synthetic_buckets(p, n, exp as u64);
+        let hll = HyperLogLog::<u64>::from_buckets(p, buckets, Default::default()).unwrap();
+
+        for estimator in [Estimator::Classic, Estimator::HyperLogLogPlusPlus, Estimator::Ertl] {
+            let estimate = hll.estimate_with(estimator).cardinality;
+            assert!(estimate.is_finite(), "2^{} with {:?} gave {}", exp, estimator, estimate);
+
+            let error = (estimate - n).abs() / n;
+            assert!(error < tolerance, "2^{} with {:?}: estimate {} off by {}", exp, estimator, estimate, error);
+        }
+    }
+}
+
+#[test]
+fn test_estimates_past_2_pow_32_keep_growing() {
+    let p = 12;
+    let mut previous = 0.0;
+    for exp in [30, 32, 34, 36, 40] {
+        let buckets = synthetic_buckets(p, 2f64.powi(exp), 7);
+        let hll = HyperLogLog::<u64>::from_buckets(p, buckets, Default::default()).unwrap();
+        let estimate = hll.estimate().cardinality;
+        assert!(estimate > previous, "estimate for 2^{} did not grow: {}", exp, estimate);
+        previous = estimate;
+    }
+}
+
+#[test]
+fn test_synthetic_state_matches_inserted_state() {
+    // sanity check of the register model against real inserts
+    let p = 10;
+    let n = 200_000u64;
+    let mut hll = HyperLogLog::<u64>::new(p).unwrap();
+    for i in 0..n {
+        hll.insert(i);
+    }
+    let synthetic = HyperLogLog::<u64>::from_buckets(p, synthetic_buckets(p, n as f64, 1), Default::default()).unwrap();
+
+    let real = hll.estimate_with(Estimator::Ertl).cardinality;
+    let modelled = synthetic.estimate_with(Estimator::Ertl).cardinality;
+    let tolerance = 4.0 * 1.04 / ((1u64 << p) as f64).sqrt();
+    assert!((real - modelled).abs() / n as f64 <= tolerance, "real {} vs synthetic {}", real, modelled);
+}
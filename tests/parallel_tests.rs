@@ -0,0 +1,74 @@
+use hyperloglog::{HyperLogLog, RegisterBackend};
+
+fn sequential(p: u32, items: &[u64]) -> HyperLogLog<u64> {
+    let mut hll = HyperLogLog::<u64>::new(p).unwrap();
+    for &item in items {
+        hll.insert(item);
+    }
+    hll
+}
+
+#[test]
+fn test_insert_parallel_matches_sequential() {
+    let items: Vec<u64> = (0..300_000).collect();
+    for threads in [0, 1, 3, 8, 64] {
+        let mut hll = HyperLogLog::<u64>::new(14).unwrap();
+        hll.insert_parallel(&items, threads);
+        assert_eq!(hll.get_buckets(), sequential(14, &items).get_buckets(), "threads={}", threads);
+    }
+}
+
+#[test]
+fn test_insert_parallel_into_filled_sketch() {
+    let mut hll = HyperLogLog::<u64>::new(12).unwrap().with_backend(RegisterBackend::Hll4);
+    for i in 0..1_000 {
+        hll.insert(i);
+    }
+    let items: Vec<u64> = (500..50_000).collect();
+    hll.insert_parallel(&items, 4);
+
+    assert_eq!(hll.get_backend(), RegisterBackend::Hll4);
+    let all: Vec<u64> = (0..50_000).collect();
+    assert_eq!(hll.get_buckets(), sequential(12, &all).get_buckets());
+
+    // an empty slice changes nothing, a short one leaves some threads without items
+    let mut small = HyperLogLog::<u64>::new(14).unwrap();
+    small.insert_parallel(&[], 4);
+    small.insert_parallel(&[1, 2, 3], 8);
+    assert_eq!(small.get_buckets(), sequential(14, &[1, 2, 3]).get_buckets());
+}
+
+#[test]
+fn test_insert_parallel_iter_matches_sequential() {
+    let items: Vec<u64> = (0..300_000).collect();
+    for threads in [0, 1, 3, 8, 64] {
+        let mut hll = HyperLogLog::<u64>::new(14).unwrap();
+        hll.insert_parallel_iter(0..300_000u64, threads);
+        assert_eq!(hll.get_buckets(), sequential(14, &items).get_buckets(), "threads={}", threads);
+    }
+
+    // an empty iterator changes nothing, items already inserted stay
+    let mut hll = sequential(12, &items[..100]);
+    hll.insert_parallel_iter(std::iter::empty(), 4);
+    assert_eq!(hll.get_buckets(), sequential(12, &items[..100]).get_buckets());
+    hll.insert_parallel_iter(items[50..20_000].iter().copied(), 4);
+    assert_eq!(hll.get_buckets(), sequential(12, &items[..20_000]).get_buckets());
+}
+
+#[test]
+fn test_merge_parallel() {
+    let items: Vec<u64> = (0..100_000).collect();
+    for parts in [1, 2, 7, 16, 100] {
+        let sketches: Vec<_> = items.chunks(items.len().div_ceil(parts)).map(|chunk| sequential(12, chunk)).collect();
+        for threads in [0, 1, 3, 8, 200] {
+            let merged = HyperLogLog::merge_parallel(sketches.clone(), threads).unwrap().unwrap();
+            assert_eq!(merged.get_buckets(), sequential(12, &items).get_buckets(), "parts={} threads={}", parts, threads);
+        }
+    }
+
+    assert!(HyperLogLog::<u64>::merge_parallel(Vec::new(), 4).unwrap().is_none());
+    let mixed = vec![sequential(12, &items[..10]), sequential(12, &items[10..20]), sequential(14, &items[20..30])];
+    for threads in [1, 2, 3] {
+        assert!(HyperLogLog::merge_parallel(mixed.clone(), threads).is_err());
+    }
+}
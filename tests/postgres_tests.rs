@@ -0,0 +1,159 @@
+use hyperloglog::{HyperLogLog, HyperLogLogError, PostgresHllHasherBuilder, PostgresHllSettings, Representation, MAX_PRECISION};
+
+// Stored `hll` values of `hll_add_agg(hll_hash_bigint(i))` for `i` in `1 ..= n`, in `hll(11, 5, -1, 1)`
+// columns unless the name says otherwise. Built from the postgresql-hll hashing and storage
+// specification, not dumped from a database.
+// fixtures/postgres/README.md has the commands to capture real ones.
+const EMPTY: &[u8] = include_bytes!("fixtures/postgres/empty.bin");
+const EXPLICIT_100: &[u8] = include_bytes!("fixtures/postgres/explicit_100.bin");
+const SPARSE_300: &[u8] = include_bytes!("fixtures/postgres/sparse_300.bin");
+const FULL_100000: &[u8] = include_bytes!("fixtures/postgres/full_100000.bin");
+const FULL_200000_LOG2M14: &[u8] = include_bytes!("fixtures/postgres/full_200000_log2m14_regwidth6.bin");
+
+const FIXTURES: [(&[u8], u32, i64); 5] = [
+    (EMPTY, 11, 0),
+    (EXPLICIT_100, 11, 100),
+    (SPARSE_300, 11, 300),
+    (FULL_100000, 11, 100_000),
+    (FULL_200000_LOG2M14, 14, 200_000),
+];
+
+fn import(bytes: &[u8], log2m: u32) -> HyperLogLog<i64, PostgresHllHasherBuilder> {
+    HyperLogLog::from_postgres_hll(bytes, PostgresHllHasherBuilder::new(log2m).unwrap()).unwrap()
+}
+
+fn native(log2m: u32, range: std::ops::RangeInclusive<i64>) -> HyperLogLog<i64, PostgresHllHasherBuilder> {
+    let mut hll = HyperLogLog::with_hasher(log2m, PostgresHllHasherBuilder::new(log2m).unwrap()).unwrap();
+    for i in range {
+        hll.insert(i);
+    }
+    hll
+}
+
+#[test]
+fn test_import_fixtures() {
+    for (bytes, log2m, n) in FIXTURES {
+        let hll = import(bytes, log2m);
+        assert_eq!(hll.get_p(), log2m);
+        let estimate = hll.calculate_cardinality() as f64;
+        assert!((estimate - n as f64).abs() <= 0.05 * n as f64 + 1.0, "log2m={} n={} estimate={}", log2m, n, estimate);
+    }
+}
+
+#[test]
+fn test_inserts_match_postgres_registers() {
+    for (bytes, log2m, n) in FIXTURES {
+        assert_eq!(native(log2m, 1..=n).get_buckets(), import(bytes, log2m).get_buckets(), "log2m={} n={}", log2m, n);
+    }
+}
+
+#[test]
+fn test_merge_imported_with_native() {
+    let mut merged = import(FULL_100000, 11);
+    merged.merge(&native(11, 100_001..=150_000)).unwrap();
+    assert_eq!(merged.get_buckets(), native(11, 1..=150_000).get_buckets());
+}
+
+#[test]
+fn test_export_matches_fixtures() {
+    let settings = PostgresHllSettings::default();
+    let empty = HyperLogLog::<i64, _>::with_hasher(11, PostgresHllHasherBuilder::default()).unwrap();
+    assert_eq!(empty.to_postgres_hll(settings).unwrap(), EMPTY);
+    assert_eq!(native(11, 1..=300).to_postgres_hll(settings).unwrap(), SPARSE_300);
+    assert_eq!(native(11, 1..=100_000).to_postgres_hll(settings).unwrap(), FULL_100000);
+
+    let wide = PostgresHllSettings::from_header(FULL_200000_LOG2M14).unwrap();
+    assert_eq!(wide.get_regwidth(), 6);
+    assert_eq!(native(14, 1..=200_000).to_postgres_hll(wide).unwrap(), FULL_200000_LOG2M14);
+
+    // the hashes of an EXPLICIT value are not kept, it is written back as SPARSE
+    let explicit = import(EXPLICIT_100, 11).to_postgres_hll(settings).unwrap();
+    assert_eq!(explicit[0], 0x13);
+    assert_eq!(import(&explicit, 11).get_buckets(), import(EXPLICIT_100, 11).get_buckets());
+}
+
+#[test]
+fn test_export_settings() {
+    let hll = native(11, 1..=300);
+
+    // sparse disabled and a fixed explicit threshold of 2^7, the header records both
+    let full = hll.to_postgres_hll(PostgresHllSettings::new(5, 128, false).unwrap()).unwrap();
+    assert_eq!(&full[..3], &[0x14, 0x8b, 8]);
+    assert_eq!(full.len(), 3 + 2048 * 5 / 8);
+    assert_eq!(import(&full, 11).get_buckets(), hll.get_buckets());
+
+    // narrow registers hold at most 2^regwidth - 1
+    let narrow = hll.to_postgres_hll(PostgresHllSettings::new(2, -1, true).unwrap()).unwrap();
+    assert!(import(&narrow, 11).get_buckets().iter().all(|&v| v <= 3));
+
+    assert!(PostgresHllSettings::new(0, -1, true).is_err());
+    assert!(PostgresHllSettings::new(9, -1, true).is_err());
+    assert!(PostgresHllSettings::new(5, 100, true).is_err());
+    assert!(PostgresHllSettings::new(5, -2, true).is_err());
+}
+
+#[test]
+fn test_roundtrip() {
+    for (log2m, n) in [(4, 10_000), (11, 20), (11, 2_000), (16, 500_000)] {
+        let hll = native(log2m, 1..=n);
+        let restored = import(&hll.to_postgres_hll(PostgresHllSettings::default()).unwrap(), log2m);
+        assert_eq!(restored.get_buckets(), hll.get_buckets(), "log2m={} n={}", log2m, n);
+    }
+}
+
+#[test]
+fn test_rejects_malformed_values() {
+    let load = |bytes: &[u8]| HyperLogLog::<i64, PostgresHllHasherBuilder>::from_postgres_hll(bytes, Default::default());
+    assert!(load(FULL_100000).is_ok());
+
+    // version 2, UNDEFINED type, unknown type, log2m below 4
+    for (offset, value) in [(0, 0x24), (0, 0x10), (0, 0x15), (1, 0x83)] {
+        let mut bytes = FULL_100000.to_vec();
+        bytes[offset] = value;
+        assert!(load(&bytes).is_err(), "byte {} = {:#x}", offset, value);
+    }
+
+    for bytes in [EXPLICIT_100, FULL_100000] {
+        assert!(load(&bytes[..bytes.len() - 1]).is_err());
+    }
+    for bytes in [EMPTY, SPARSE_300, FULL_100000] {
+        let mut long = bytes.to_vec();
+        long.push(0xff);
+        assert!(load(&long).is_err());
+    }
+    assert!(load(&EMPTY[..2]).is_err());
+
+    // SPARSE registers listed twice
+    let mut repeated = SPARSE_300.to_vec();
+    repeated.extend_from_slice(&SPARSE_300[3..5]);
+    assert!(load(&repeated).is_err());
+}
+
+#[test]
+fn test_rejects_precisions_above_maximum_before_allocating() {
+    let load = |bytes: &[u8]| HyperLogLog::<i64, PostgresHllHasherBuilder>::from_postgres_hll(bytes, Default::default());
+    // EMPTY, EXPLICIT, SPARSE and FULL values with log2m = 31 and regwidth 1, which would be 2^31 registers
+    for kind in [0x11, 0x12, 0x13, 0x14] {
+        let err = load(&[kind, 0x1f, 0x7f]).err().unwrap();
+        assert!(matches!(err, HyperLogLogError::UnsupportedPrecision(31)), "{:#x}: {}", kind, err);
+    }
+    for log2m in MAX_PRECISION + 1..32 {
+        let err = load(&[0x14, log2m as u8, 0x7f, 0xff]).err().unwrap();
+        assert!(matches!(err, HyperLogLogError::UnsupportedPrecision(p) if p == log2m), "log2m={}: {}", log2m, err);
+    }
+
+    // EMPTY values load as sparse sketches, without registers
+    let empty = load(&[0x11, 0x11, 0x7f]).unwrap();
+    assert_eq!(empty.get_p(), 17);
+    assert_eq!(empty.representation(), Representation::Sparse);
+    assert_eq!(empty.calculate_cardinality(), 0);
+}
+
+#[test]
+fn test_precision_limits() {
+    assert!(PostgresHllHasherBuilder::new(3).is_err());
+    assert!(PostgresHllHasherBuilder::new(31).is_ok());
+    let err = PostgresHllHasherBuilder::new(32).err().unwrap();
+    assert!(matches!(err, HyperLogLogError::UnsupportedPrecision(32)), "{}", err);
+    assert_eq!(PostgresHllHasherBuilder::default().get_log2m(), 11);
+}
@@ -0,0 +1,86 @@
+use hyperloglog::{HyperLogLog, HyperLogLogError, MergeMode, RegisterBackend, Representation};
+
+fn filled(p: u32, backend: RegisterBackend, range: std::ops::Range<u64>) -> HyperLogLog<u64> {
+    let mut hll = HyperLogLog::<u64>::new(p).unwrap().with_backend(backend);
+    for i in range {
+        hll.insert(i);
+    }
+    hll
+}
+
+#[test]
+fn test_reduce_precision_matches_direct_insertion() {
+    for backend in [RegisterBackend::U8, RegisterBackend::Packed6, RegisterBackend::Hll4] {
+        for (p, new_p, n) in [(16, 12, 100_000), (14, 10, 50_000), (12, 4, 20_000), (25, 14, 20_000), (16, 15, 100_000)] {
+            let reduced = filled(p, backend, 0..n).reduce_precision(new_p).unwrap();
+            let direct = filled(new_p, backend, 0..n);
+            assert_eq!(reduced.get_p(), new_p);
+            assert_eq!(reduced.get_m(), 1 << new_p);
+            assert_eq!(reduced.get_backend(), backend);
+            assert_eq!(reduced.get_buckets(), direct.get_buckets(), "{:?} p={} new_p={}", backend, p, new_p);
+            assert_eq!(reduced.calculate_cardinality(), direct.calculate_cardinality());
+        }
+    }
+}
+
+#[test]
+fn test_reduce_precision_of_sparse_sketch() {
+    let sparse = filled(16, RegisterBackend::U8, 0..500);
+    assert_eq!(sparse.representation(), Representation::Sparse);
+
+    let reduced = sparse.reduce_precision(12).unwrap();
+    assert_eq!(reduced.representation(), Representation::Sparse);
+    assert_eq!(reduced.get_buckets(), filled(12, RegisterBackend::U8, 0..500).get_buckets());
+
+    // entries that no longer fit the smaller dense size are promoted
+    let promoted = filled(16, RegisterBackend::U8, 0..5_000).reduce_precision(8).unwrap();
+    assert_eq!(promoted.representation(), Representation::Dense);
+    assert_eq!(promoted.get_buckets(), filled(8, RegisterBackend::U8, 0..5_000).get_buckets());
+}
+
+#[test]
+fn test_reduce_precision_limits() {
+    let hll = filled(12, RegisterBackend::U8, 0..1_000);
+    let err = hll.reduce_precision(13).err().unwrap();
+    assert!(matches!(err, HyperLogLogError::CannotIncreasePrecision(12, 13)), "{}", err);
+    assert_eq!(err.to_string(), "Cannot increase precision from 12 to 13");
+    assert!(hll.reduce_precision(3).is_err());
+    assert_eq!(hll.reduce_precision(12).unwrap().get_buckets(), hll.get_buckets());
+}
+
+#[test]
+fn test_merge_folds_to_lower_precision() {
+    // three generations of sketches over overlapping ranges
+    let p12 = filled(12, RegisterBackend::U8, 0..40_000);
+    let p14 = filled(14, RegisterBackend::Packed6, 30_000..90_000);
+    let p16 = filled(16, RegisterBackend::Hll4, 80_000..150_000);
+
+    let mut union = p16.clone();
+    union.merge_with(&p14, MergeMode::FoldToLowerPrecision).unwrap();
+    assert_eq!(union.get_p(), 14);
+    union.merge_with(&p12, MergeMode::FoldToLowerPrecision).unwrap();
+    assert_eq!(union.get_p(), 12);
+    assert_eq!(union.get_buckets(), filled(12, RegisterBackend::U8, 0..150_000).get_buckets());
+
+    // the lower precision sketch keeps its precision when the other one is folded
+    let mut low = p12.clone();
+    low.merge_with(&p16, MergeMode::FoldToLowerPrecision).unwrap();
+    assert_eq!(low.get_p(), 12);
+    assert_eq!(low.get_buckets(), p12.reduce_precision(12).unwrap().get_buckets().iter()
+        .zip(p16.reduce_precision(12).unwrap().get_buckets())
+        .map(|(&a, b)| a.max(b))
+        .collect::<Vec<_>>());
+}
+
+#[test]
+fn test_merge_modes_with_different_precisions() {
+    let mut hll = filled(12, RegisterBackend::U8, 0..1_000);
+    let other = filled(14, RegisterBackend::U8, 0..1_000);
+
+    assert!(hll.merge(&other).is_err());
+    assert!(hll.merge_with(&other, MergeMode::SamePrecision).is_err());
+    assert_eq!(MergeMode::default(), MergeMode::SamePrecision);
+
+    hll.merge_with(&other, MergeMode::FoldToLowerPrecision).unwrap();
+    assert_eq!(hll.get_buckets(), filled(12, RegisterBackend::U8, 0..1_000).get_buckets());
+}
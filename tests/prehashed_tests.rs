@@ -0,0 +1,154 @@
+use std::hash::{BuildHasher, BuildHasherDefault, DefaultHasher};
+
+use hyperloglog::{ConcurrentHyperLogLog, HyperLogLog, HyperLogLogError, MergeMode, SetEstimator};
+
+// id of the upstream hash function below
+const UPSTREAM: u64 = 1;
+
+// stands in for the hash a pipeline computed upstream
+fn upstream_hash(i: u64) -> u64 {
+    BuildHasherDefault::<DefaultHasher>::default().hash_one(i.wrapping_mul(0x9E37_79B9_7F4A_7C15))
+}
+
+fn prehashed(p: u32, range: std::ops::Range<u64>) -> HyperLogLog<u64> {
+    let mut hll = HyperLogLog::<u64>::new(p).unwrap();
+    hll.insert_hashes(UPSTREAM, range.map(upstream_hash)).unwrap();
+    hll
+}
+
+fn items(p: u32, range: std::ops::Range<u64>) -> HyperLogLog<u64> {
+    let mut hll = HyperLogLog::<u64>::new(p).unwrap();
+    for i in range {
+        hll.insert(i);
+    }
+    hll
+}
+
+#[test]
+fn test_insert_hash_estimates_cardinality() {
+    let mut hll = HyperLogLog::<u64>::new(14).unwrap();
+    assert!(!hll.is_prehashed());
+    for i in 0..100_000 {
+        hll.insert_hash(UPSTREAM, upstream_hash(i)).unwrap();
+        hll.insert_hash(UPSTREAM, upstream_hash(i)).unwrap();
+    }
+    assert!(hll.is_prehashed());
+    assert_eq!(hll.prehashed_id(), Some(UPSTREAM));
+    let estimate = hll.calculate_cardinality() as f64;
+    assert!((estimate - 100_000.0).abs() < 0.03 * 100_000.0, "{}", estimate);
+
+    assert_eq!(hll.get_buckets(), prehashed(14, 0..100_000).get_buckets());
+
+    hll.reset();
+    assert!(!hll.is_prehashed());
+    hll.insert(1);
+}
+
+#[test]
+fn test_prehashed_sketches_merge_together_only() {
+    let mut left = prehashed(12, 0..20_000);
+    let right = prehashed(12, 10_000..30_000);
+    left.merge(&right).unwrap();
+    assert_eq!(left.get_buckets(), prehashed(12, 0..30_000).get_buckets());
+
+    let mut items = items(12, 0..1_000);
+    assert!(matches!(items.merge(&right), Err(HyperLogLogError::HasherMismatch)));
+    assert!(matches!(left.merge(&items), Err(HyperLogLogError::HasherMismatch)));
+    assert!(matches!(left.merge_with(&prehashed(10, 0..10), MergeMode::FoldToLowerPrecision), Ok(())));
+    assert!(left.jaccard(&items, SetEstimator::default()).is_err());
+    assert!(HyperLogLog::jaccard_matrix(&[left.clone(), items], SetEstimator::default()).is_err());
+}
+
+#[test]
+fn test_hashes_of_other_functions_stay_apart() {
+    let mut other = HyperLogLog::<u64>::new(12).unwrap();
+    other.insert_hashes(UPSTREAM + 1, (0..1_000).map(upstream_hash)).unwrap();
+    assert_eq!(other.prehashed_id(), Some(UPSTREAM + 1));
+
+    let mut sketch = prehashed(12, 0..1_000);
+    assert!(matches!(sketch.merge(&other), Err(HyperLogLogError::HasherMismatch)));
+    let err = sketch.insert_hash(UPSTREAM + 1, upstream_hash(1)).err().unwrap();
+    assert!(matches!(err, HyperLogLogError::PrehashedIdMismatch(UPSTREAM, 2)), "{}", err);
+    assert!(sketch.insert_hashes(UPSTREAM + 1, [upstream_hash(1)]).is_err());
+    assert_eq!(sketch.get_buckets(), prehashed(12, 0..1_000).get_buckets());
+}
+
+#[test]
+fn test_raw_hashes_are_refused_after_items() {
+    let mut hll = items(12, 0..10);
+    let err = hll.insert_hash(UPSTREAM, upstream_hash(1)).err().unwrap();
+    assert!(matches!(err, HyperLogLogError::NotPrehashed), "{}", err);
+    assert!(matches!(hll.insert_hashes(UPSTREAM, [upstream_hash(1)]), Err(HyperLogLogError::NotPrehashed)));
+    assert!(!hll.is_prehashed());
+    assert_eq!(hll.get_buckets(), items(12, 0..10).get_buckets());
+
+    // a dense sketch counts its items as well
+    let mut dense = items(12, 0..10_000);
+    assert!(matches!(dense.insert_hash(UPSTREAM, upstream_hash(1)), Err(HyperLogLogError::NotPrehashed)));
+}
+
+#[test]
+fn test_no_hashes_leave_the_sketch_unmarked() {
+    let mut hll = HyperLogLog::<u64>::new(12).unwrap();
+    hll.insert_hashes(UPSTREAM, std::iter::empty()).unwrap();
+    assert!(!hll.is_prehashed());
+
+    // it still takes items and merges with sketches of items
+    hll.insert(1);
+    hll.merge(&items(12, 0..10)).unwrap();
+    assert_eq!(hll.get_buckets(), items(12, 0..10).get_buckets());
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "items cannot be inserted into a sketch of raw hashes")]
+fn test_items_are_refused_after_raw_hashes() {
+    let mut hll = prehashed(12, 0..10);
+    hll.insert(1);
+}
+
+#[test]
+fn test_prehashed_marker_survives_serialization() {
+    let hll = prehashed(12, 0..5_000);
+
+    let restored = HyperLogLog::<u64>::from_bytes(&hll.to_bytes()).unwrap();
+    assert_eq!(restored.prehashed_id(), Some(UPSTREAM));
+    assert_eq!(restored.get_buckets(), hll.get_buckets());
+
+    let json = serde_json::to_string(&hll).unwrap();
+    let restored: HyperLogLog<u64> = serde_json::from_str(&json).unwrap();
+    assert_eq!(restored.prehashed_id(), Some(UPSTREAM));
+
+    // the recorded id is checked against the fingerprint
+    let forged = json.replace(&format!("\"prehashed\":{}", UPSTREAM), "\"prehashed\":2");
+    assert_ne!(forged, json);
+    assert!(serde_json::from_str::<HyperLogLog<u64>>(&forged).is_err());
+
+    // the marker also survives precision changes, so the folded sketch still refuses item sketches
+    let mut reduced = hll.reduce_precision(10).unwrap();
+    assert_eq!(reduced.prehashed_id(), Some(UPSTREAM));
+    assert!(reduced.merge_with(&HyperLogLog::<u64>::new(10).unwrap(), MergeMode::SamePrecision).is_err());
+
+    // a prehashed sketch of another item type is refused when decoding
+    assert!(matches!(HyperLogLog::<String>::from_bytes(&hll.to_bytes()), Err(HyperLogLogError::HasherMismatch)));
+}
+
+#[test]
+fn test_concurrent_insert_hash() {
+    let shared = ConcurrentHyperLogLog::<u64>::new(12).unwrap();
+    for i in 0..20_000 {
+        shared.insert_hash(UPSTREAM, upstream_hash(i)).unwrap();
+    }
+    assert_eq!(shared.prehashed_id(), Some(UPSTREAM));
+    let err = shared.insert_hash(UPSTREAM + 1, upstream_hash(1)).err().unwrap();
+    assert!(matches!(err, HyperLogLogError::PrehashedIdMismatch(UPSTREAM, 2)), "{}", err);
+
+    let snapshot = shared.snapshot();
+    assert_eq!(snapshot.prehashed_id(), Some(UPSTREAM));
+    assert_eq!(snapshot.get_buckets(), prehashed(12, 0..20_000).get_buckets());
+
+    let items = ConcurrentHyperLogLog::<u64>::new(12).unwrap();
+    items.insert(1);
+    assert!(matches!(items.insert_hash(UPSTREAM, upstream_hash(1)), Err(HyperLogLogError::NotPrehashed)));
+    assert!(!items.snapshot().is_prehashed());
+}
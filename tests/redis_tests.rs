@@ -0,0 +1,112 @@
+use hyperloglog::{HyperLogLog, RedisHasherBuilder, REDIS_PRECISION};
+
+// HYLL strings in the layout Redis stores for `PFADD key element:0 .. element:{n-1}`:
+// an empty key (sparse), 100 elements (sparse) and 50000 elements (dense).
+// Built from Redis' hashing and encoding rules, not dumped from a running server.
+// fixtures/redis/README.md has the commands to capture real ones.
+const EMPTY: &[u8] = include_bytes!("fixtures/redis/empty.bin");
+const SPARSE_100: &[u8] = include_bytes!("fixtures/redis/sparse_100.bin");
+const DENSE_50000: &[u8] = include_bytes!("fixtures/redis/dense_50000.bin");
+
+fn import(bytes: &[u8]) -> HyperLogLog<String, RedisHasherBuilder> {
+    HyperLogLog::from_redis(bytes, RedisHasherBuilder).unwrap()
+}
+
+fn elements(n: usize) -> impl Iterator<Item = String> {
+    (0..n).map(|i| format!("element:{}", i))
+}
+
+#[test]
+fn test_import_fixtures() {
+    let empty = import(EMPTY);
+    assert_eq!(empty.get_p(), REDIS_PRECISION);
+    assert_eq!(empty.calculate_cardinality(), 0);
+
+    for (bytes, n) in [(SPARSE_100, 100usize), (DENSE_50000, 50_000)] {
+        let hll = import(bytes);
+        let estimate = hll.calculate_cardinality() as f64;
+        assert!((estimate - n as f64).abs() < 0.03 * n as f64, "n={} estimate={}", n, estimate);
+    }
+}
+
+#[test]
+fn test_inserts_match_redis_registers() {
+    for (bytes, n) in [(SPARSE_100, 100), (DENSE_50000, 50_000)] {
+        // the same elements added here land in the same registers with the same ranks
+        let mut rebuilt = HyperLogLog::<String, RedisHasherBuilder>::with_hasher(REDIS_PRECISION, RedisHasherBuilder).unwrap();
+        for element in elements(n) {
+            rebuilt.insert(element);
+        }
+        assert_eq!(rebuilt.get_buckets(), import(bytes).get_buckets(), "n={}", n);
+
+        // so adding them again to the import changes nothing
+        let mut imported = import(bytes);
+        let before = imported.get_buckets();
+        for element in elements(n) {
+            imported.insert(element);
+        }
+        assert_eq!(imported.get_buckets(), before);
+    }
+}
+
+#[test]
+fn test_export_encoding() {
+    // small sketches are exported sparse, with the same opcodes as the fixtures
+    assert_eq!(&import(EMPTY).to_redis().unwrap()[16..], &EMPTY[16..]);
+    assert_eq!(&import(SPARSE_100).to_redis().unwrap()[16..], &SPARSE_100[16..]);
+
+    let dense = import(DENSE_50000).to_redis().unwrap();
+    assert_eq!(dense.len(), 16 + 12288);
+    assert_eq!(dense[4], 0);
+    assert_eq!(&dense[16..], &DENSE_50000[16..]);
+}
+
+#[test]
+fn test_roundtrip() {
+    for n in [0usize, 10, 1_000, 100_000] {
+        let mut hll = HyperLogLog::<String, RedisHasherBuilder>::with_hasher(REDIS_PRECISION, RedisHasherBuilder).unwrap();
+        for element in elements(n) {
+            hll.insert(element);
+        }
+        let bytes = hll.to_redis().unwrap();
+        assert_eq!(&bytes[..4], b"HYLL");
+        // the cached cardinality is marked stale
+        assert_eq!(bytes[15] & 0x80, 0x80);
+
+        let restored = import(&bytes);
+        assert_eq!(restored.get_buckets(), hll.get_buckets(), "n={}", n);
+    }
+}
+
+#[test]
+fn test_rejects_malformed_input() {
+    assert!(HyperLogLog::<String, RedisHasherBuilder>::from_redis(b"HYLL", RedisHasherBuilder).is_err());
+
+    let mut magic = SPARSE_100.to_vec();
+    magic[0] = b'X';
+    assert!(HyperLogLog::<String, RedisHasherBuilder>::from_redis(&magic, RedisHasherBuilder).is_err());
+
+    let mut encoding = SPARSE_100.to_vec();
+    encoding[4] = 2;
+    assert!(HyperLogLog::<String, RedisHasherBuilder>::from_redis(&encoding, RedisHasherBuilder).is_err());
+
+    // opcodes covering too few or too many registers
+    let short = &SPARSE_100[..SPARSE_100.len() - 1];
+    assert!(HyperLogLog::<String, RedisHasherBuilder>::from_redis(short, RedisHasherBuilder).is_err());
+    let mut long = SPARSE_100.to_vec();
+    long.push(0x00);
+    assert!(HyperLogLog::<String, RedisHasherBuilder>::from_redis(&long, RedisHasherBuilder).is_err());
+
+    // a dense string of the wrong length, and one with a 6-bit register above 64 - 14 + 1
+    let truncated = &DENSE_50000[..DENSE_50000.len() - 1];
+    assert!(HyperLogLog::<String, RedisHasherBuilder>::from_redis(truncated, RedisHasherBuilder).is_err());
+    let mut too_large = DENSE_50000.to_vec();
+    too_large[16] |= 0x3f;
+    assert!(HyperLogLog::<String, RedisHasherBuilder>::from_redis(&too_large, RedisHasherBuilder).is_err());
+}
+
+#[test]
+fn test_export_requires_redis_precision() {
+    let hll = HyperLogLog::<String, RedisHasherBuilder>::with_hasher(12, RedisHasherBuilder).unwrap();
+    assert!(hll.to_redis().is_err());
+}
@@ -12,20 +12,20 @@ fn test_reset_clears_buckets() {
     let are_buckets_filled: bool = hll.get_buckets().iter().any(|x| *x > 0u8);
 
     // Ensure buckets are not in the default state before reset
-    assert!(are_buckets_filled == true);
+    assert!(are_buckets_filled);
 
     // Call reset
     hll.reset();
 
     let are_buckets_filled: bool = hll.get_buckets().iter().any(|x| *x > 0);
 
-    assert!(are_buckets_filled == false);
+    assert!(!are_buckets_filled);
 
 }
 
 #[test]
 fn test_reset_does_not_affect_other_fields() {
-    let mut hll = HyperLogLog::<u32>::new(10).unwrap();;
+    let mut hll = HyperLogLog::<u32>::new(10).unwrap();
 
     let original_p = hll.get_p();
     let original_m = hll.get_m();
@@ -42,7 +42,7 @@ fn test_reset_does_not_affect_other_fields() {
 
 #[test]
 fn test_reset_after_inserting_elements() {
-    let mut hll = HyperLogLog::<u32>::new(10).unwrap();;
+    let mut hll = HyperLogLog::<u32>::new(10).unwrap();
 
     // Insert elements into the HyperLogLog
     hll.insert(1);
@@ -62,7 +62,7 @@ fn test_reset_after_inserting_elements() {
 
 #[test]
 fn test_reset_multiple_times() {
-    let mut hll = HyperLogLog::<u32>::new(10).unwrap();;
+    let mut hll = HyperLogLog::<u32>::new(10).unwrap();
 
     // Call reset multiple times
     hll.reset();
@@ -1,21 +1,19 @@
 // tests/serializing_deserializing_test.rs
 
-use std::hash::{BuildHasherDefault, DefaultHasher};
 use xxhash_rust::xxh3::Xxh3DefaultBuilder;
-use serde_json;
 
-use hyperloglog::HyperLogLog;
+use hyperloglog::{HyperLogLog, StableHasherBuilder, MAX_PRECISION};
 
 #[test]
 fn test_serialize_deserialize_default_hll() {
-    let mut hll_def: HyperLogLog<i64, BuildHasherDefault<DefaultHasher>> =
-        HyperLogLog::new(10).unwrap();;
+    let mut hll_def: HyperLogLog<i64, StableHasherBuilder> =
+        HyperLogLog::new(10).unwrap();
     hll_def.insert(1);
     hll_def.insert(2);
 
     let json = serde_json::to_string(&hll_def).unwrap();
     // round-trip with the SAME hasher should succeed
-    let hll_def2: HyperLogLog<i64, BuildHasherDefault<DefaultHasher>> =
+    let hll_def2: HyperLogLog<i64, StableHasherBuilder> =
         serde_json::from_str(&json).unwrap();
     assert_eq!(hll_def2.calculate_cardinality(), 2);
 }
@@ -23,7 +21,7 @@ fn test_serialize_deserialize_default_hll() {
 #[test]
 fn test_deserialize_with_xxh3_should_fail() {
     let mut hll_def: HyperLogLog<i64> =
-        HyperLogLog::new(10).unwrap();;
+        HyperLogLog::new(10).unwrap();
     hll_def.insert(1);
     hll_def.insert(2);
 
@@ -41,7 +39,7 @@ fn test_deserialize_with_xxh3_should_fail() {
 
 #[test]
 fn test_stable_json_output() {
-    let mut hll = HyperLogLog::<i64>::new(10).unwrap();;
+    let mut hll = HyperLogLog::<i64>::new(10).unwrap();
     hll.insert(42);
     let j1 = serde_json::to_string(&hll).unwrap();
     let j2 = serde_json::to_string(&hll).unwrap();
@@ -52,10 +50,10 @@ fn test_stable_json_output() {
 // Round-trip identity: serialize → deserialize → serialize yields the same JSON again
 #[test]
 fn test_roundtrip_json_identity() {
-    let mut hll = HyperLogLog::<i64, BuildHasherDefault<DefaultHasher>>::new(10).unwrap();;
+    let mut hll = HyperLogLog::<i64, StableHasherBuilder>::new(10).unwrap();
     for i in 0..100 { hll.insert(i); }
     let original = serde_json::to_string(&hll).unwrap();
-    let recovered: HyperLogLog<i64, BuildHasherDefault<DefaultHasher>> =
+    let recovered: HyperLogLog<i64, StableHasherBuilder> =
         serde_json::from_str(&original).unwrap();
     let round_trip = serde_json::to_string(&recovered).unwrap();
 
@@ -66,13 +64,13 @@ fn test_roundtrip_json_identity() {
 #[test]
 fn test_deserialize_missing_field_errors() {
     // build a valid JSON, then remove the "buckets" key
-    let mut hll = HyperLogLog::<i64>::new(10).unwrap();;
+    let hll = HyperLogLog::<i64>::new(10).unwrap();
     let mut json: serde_json::Value = serde_json::to_value(&hll).unwrap();
     let obj = json.as_object_mut().unwrap();
     obj.remove("buckets");
     let broken = serde_json::to_string(&json).unwrap();
 
-    let res: Result<HyperLogLog<i64, BuildHasherDefault<DefaultHasher>>, _> =
+    let res: Result<HyperLogLog<i64, StableHasherBuilder>, _> =
         serde_json::from_str(&broken);
     assert!(res.is_err(), "Deserializing JSON with missing buckets should fail");
 }
@@ -97,7 +95,7 @@ fn test_xxh3_roundtrip_succeeds() {
 #[test]
 fn test_error_on_deserializing_mismatched_element_type() {
     let p = 4;
-    let mut hll: HyperLogLog<i64> = HyperLogLog::new(p).unwrap();
+    let hll: HyperLogLog<i64> = HyperLogLog::new(p).unwrap();
 
     let json = serde_json::to_string(&hll).unwrap();
 
@@ -105,4 +103,121 @@ fn test_error_on_deserializing_mismatched_element_type() {
 
     assert!(res.is_err(), "Deserializing different datatype, should fail");
 
-}
\ No newline at end of file
+}
+// Corrupt dumps with a valid fingerprint must be rejected on load rather than panic later
+fn dense_dump(p: u32) -> serde_json::Value {
+    let mut hll = HyperLogLog::<i64>::new(p).unwrap();
+    for i in 0..100_000 { hll.insert(i); }
+    serde_json::to_value(&hll).unwrap()
+}
+
+fn load(json: &serde_json::Value) -> Result<HyperLogLog<i64>, serde_json::Error> {
+    serde_json::from_value(json.clone())
+}
+
+#[test]
+fn test_deserialize_rejects_inconsistent_precision() {
+    let valid = dense_dump(10);
+    assert!(load(&valid).is_ok());
+
+    for p in [0, 3, 64, 1000] {
+        let mut json = valid.clone();
+        json["p"] = p.into();
+        json["m"] = 1024.into();
+        assert!(load(&json).is_err(), "p={}", p);
+    }
+
+    // m must be 2^p
+    let mut json = valid.clone();
+    json["m"] = 1000.into();
+    let err = load(&json).err().unwrap();
+    assert!(err.to_string().contains("m = 1000"), "{}", err);
+
+    // p and m agree but the buckets are for another precision
+    let mut json = valid.clone();
+    json["p"] = 11.into();
+    json["m"] = 2048.into();
+    assert!(load(&json).is_err());
+}
+
+#[test]
+fn test_deserialize_rejects_bad_buckets() {
+    let valid = dense_dump(10);
+
+    let mut short = valid.clone();
+    short["buckets"].as_array_mut().unwrap().pop();
+    assert!(load(&short).is_err());
+
+    // the largest rank with p = 10 is 64 - 10 + 1 = 55
+    let mut json = valid.clone();
+    json["buckets"][7] = 55.into();
+    assert!(load(&json).is_ok());
+    json["buckets"][7] = 56.into();
+    let err = load(&json).err().unwrap();
+    assert!(err.to_string().contains("56"), "{}", err);
+}
+
+#[test]
+fn test_deserialize_rejects_bad_sparse_entries() {
+    let mut hll = HyperLogLog::<i64>::new(10).unwrap();
+    for i in 0..20 { hll.insert(i); }
+    let valid = serde_json::to_value(&hll).unwrap();
+    assert!(load(&valid).is_ok());
+
+    let entries: Vec<u32> = serde_json::from_value(valid["sparse"].clone()).unwrap();
+
+    let mut unsorted = entries.clone();
+    unsorted.reverse();
+    let mut duplicated = entries.clone();
+    duplicated.push(*entries.last().unwrap());
+    let zero_rank = vec![1 << 6];
+    let rank_too_large = vec![(1 << 6) | 41];
+    let index_too_large = vec![(1 << 31) | 1];
+
+    for bad in [unsorted, duplicated, zero_rank, rank_too_large, index_too_large] {
+        let mut json = valid.clone();
+        json["sparse"] = serde_json::to_value(&bad).unwrap();
+        assert!(load(&json).is_err(), "{:?}", bad);
+    }
+
+    // a sparse dump cannot also carry dense buckets
+    let mut json = valid.clone();
+    json["buckets"] = serde_json::to_value(vec![0u8; 1024]).unwrap();
+    assert!(load(&json).is_err());
+}
+
+#[test]
+fn test_deserialize_rejects_out_of_range_precisions() {
+    let mut sparse = HyperLogLog::<i64>::new(10).unwrap();
+    sparse.insert(1);
+    for (valid, is_sparse) in [(serde_json::to_value(&sparse).unwrap(), true), (dense_dump(10), false)] {
+        for p in 24..=255u32 {
+            let mut json = valid.clone();
+            json["p"] = p.into();
+            json["m"] = (1u64 << p.min(63)).into();
+            match load(&json) {
+                // sparse entries are kept at a higher precision, so they still fit p = 24
+                Ok(mut hll) => {
+                    assert!(is_sparse && p < MAX_PRECISION, "p={}", p);
+                    hll.insert(2);
+                    assert_eq!(hll.calculate_cardinality(), 2);
+                }
+                Err(_) => assert!(!is_sparse || p >= MAX_PRECISION, "p={}", p),
+            }
+        }
+    }
+}
+
+#[test]
+fn test_loaded_corrupt_sketch_never_panics() {
+    // anything that loads must support every operation
+    let valid = dense_dump(4);
+    for value in 0..=70u8 {
+        let mut json = valid.clone();
+        json["buckets"][3] = value.into();
+        if let Ok(mut hll) = load(&json) {
+            hll.insert(1);
+            hll.calculate_cardinality();
+        }
+    }
+}
@@ -0,0 +1,159 @@
+use std::hash::RandomState;
+
+use hyperloglog::{HyperLogLog, SetEstimator};
+
+fn filled(p: u32, range: std::ops::Range<u64>) -> HyperLogLog<u64> {
+    let mut hll = HyperLogLog::<u64>::new(p).unwrap();
+    for i in range {
+        hll.insert(i);
+    }
+    hll
+}
+
+const ESTIMATORS: [SetEstimator; 2] = [SetEstimator::InclusionExclusion, SetEstimator::JointMaximumLikelihood];
+
+#[test]
+fn test_half_overlap() {
+    let a = filled(12, 0..20_000);
+    let b = filled(12, 10_000..30_000);
+
+    for estimator in ESTIMATORS {
+        let joint = a.joint_estimate(&b, estimator).unwrap();
+        for (name, part, truth) in [
+            ("intersection", joint.intersection, 10_000.0),
+            ("only_self", joint.only_self, 10_000.0),
+            ("only_other", joint.only_other, 10_000.0),
+            ("union", joint.union, 30_000.0),
+            ("containment", joint.containment, 0.5),
+        ] {
+            assert!(part.standard_error > 0.0, "{:?} {} has no error estimate", estimator, name);
+            assert!(
+                (part.value - truth).abs() < 4.0 * part.standard_error,
+                "{:?} {}: {} +- {} vs {}", estimator, name, part.value, part.standard_error, truth
+            );
+        }
+    }
+}
+
+#[test]
+fn test_joint_likelihood_is_more_accurate_for_small_overlaps() {
+    let a = filled(12, 0..100_000);
+    let b = filled(12, 99_000..200_000);
+
+    let simple = a.intersection_cardinality(&b, SetEstimator::InclusionExclusion).unwrap();
+    let joint = a.intersection_cardinality(&b, SetEstimator::JointMaximumLikelihood).unwrap();
+
+    assert!(joint.standard_error < simple.standard_error / 2.0, "{:?} vs {:?}", joint, simple);
+    assert!((joint.value - 1_000.0).abs() < 4.0 * joint.standard_error, "{:?}", joint);
+}
+
+#[test]
+fn test_disjoint_and_identical_sets() {
+    let a = filled(11, 0..50_000);
+    let b = filled(11, 50_000..100_000);
+
+    let disjoint = a.intersection_cardinality(&b, SetEstimator::JointMaximumLikelihood).unwrap();
+    assert!(disjoint.value < 3.0 * disjoint.standard_error + 1.0, "{:?}", disjoint);
+
+    let same = a.containment(&a.clone(), SetEstimator::JointMaximumLikelihood).unwrap();
+    assert!((same.value - 1.0).abs() < 0.05, "{:?}", same);
+
+    let difference = a.difference_cardinality(&a.clone(), SetEstimator::JointMaximumLikelihood).unwrap();
+    assert!(difference.value < 3.0 * difference.standard_error + 1.0, "{:?}", difference);
+}
+
+#[test]
+fn test_subset_containment() {
+    let small = filled(12, 0..5_000);
+    let large = filled(12, 0..50_000);
+
+    for estimator in ESTIMATORS {
+        let contained = small.containment(&large, estimator).unwrap();
+        assert!((contained.value - 1.0).abs() < 4.0 * contained.standard_error + 0.01, "{:?} {:?}", estimator, contained);
+
+        let reverse = large.containment(&small, estimator).unwrap();
+        assert!((reverse.value - 0.1).abs() < 4.0 * reverse.standard_error, "{:?} {:?}", estimator, reverse);
+    }
+}
+
+#[test]
+fn test_sparse_sketches() {
+    let a = filled(14, 0..300);
+    let b = filled(14, 200..500);
+
+    for estimator in ESTIMATORS {
+        let intersection = a.intersection_cardinality(&b, estimator).unwrap();
+        assert!((intersection.value - 100.0).abs() < 4.0 * intersection.standard_error + 2.0, "{:?} {:?}", estimator, intersection);
+    }
+}
+
+#[test]
+fn test_empty_sketches() {
+    let a = filled(10, 0..0);
+    let b = filled(10, 0..0);
+
+    for estimator in ESTIMATORS {
+        let joint = a.joint_estimate(&b, estimator).unwrap();
+        assert_eq!(joint.intersection.value, 0.0);
+        assert_eq!(joint.union.value, 0.0);
+        assert_eq!(joint.containment.value, 0.0);
+    }
+}
+
+#[test]
+fn test_incompatible_sketches() {
+    let a = filled(10, 0..100);
+    let b = filled(12, 0..100);
+    assert!(a.intersection_cardinality(&b, SetEstimator::default()).is_err());
+
+    let c = HyperLogLog::<u64, RandomState>::with_hasher(10, RandomState::new()).unwrap();
+    let d = HyperLogLog::<u64, RandomState>::with_hasher(10, RandomState::new()).unwrap();
+    assert!(c.intersection_cardinality(&d, SetEstimator::default()).is_err());
+    assert!(c.intersection_cardinality(&c.clone(), SetEstimator::default()).is_ok());
+}
+
+#[test]
+fn test_jaccard() {
+    let a = filled(12, 0..20_000);
+    let b = filled(12, 10_000..30_000);
+
+    for estimator in ESTIMATORS {
+        let jaccard = a.jaccard(&b, estimator).unwrap();
+        assert!(jaccard.standard_error > 0.0);
+        assert!((jaccard.value - 1.0 / 3.0).abs() < 4.0 * jaccard.standard_error, "{:?} {:?}", estimator, jaccard);
+    }
+
+    let disjoint = a.jaccard(&filled(12, 50_000..60_000), SetEstimator::JointMaximumLikelihood).unwrap();
+    assert!(disjoint.value < 3.0 * disjoint.standard_error + 0.001, "{:?}", disjoint);
+}
+
+#[test]
+fn test_jaccard_matrix() {
+    let sketches = vec![
+        filled(12, 0..20_000),
+        filled(12, 10_000..30_000),
+        filled(12, 0..10),
+        filled(12, 0..0),
+    ];
+
+    for estimator in ESTIMATORS {
+        let matrix = HyperLogLog::jaccard_matrix(&sketches, estimator).unwrap();
+        assert_eq!(matrix.len(), 4);
+        assert_eq!(matrix[0][0], 1.0);
+        assert_eq!(matrix[3][3], 0.0);
+        for i in 0..4 {
+            for j in 0..4 {
+                assert_eq!(matrix[i][j], matrix[j][i]);
+                if i < j {
+                    assert_eq!(matrix[i][j], sketches[i].jaccard(&sketches[j], estimator).unwrap().value);
+                }
+            }
+        }
+        assert!((matrix[0][1] - 1.0 / 3.0).abs() < 0.05, "{:?}", matrix);
+        assert!((matrix[0][2] - 10.0 / 20_000.0).abs() < 0.01, "{:?}", matrix);
+    }
+
+    assert!(HyperLogLog::<u64>::jaccard_matrix(&[], SetEstimator::default()).unwrap().is_empty());
+    let mixed = vec![filled(10, 0..10), filled(12, 0..10)];
+    assert!(HyperLogLog::jaccard_matrix(&mixed, SetEstimator::default()).is_err());
+}
@@ -0,0 +1,191 @@
+use std::hash::{BuildHasher, Hasher};
+
+use hyperloglog::{HyperLogLog, RegisterBackend, Representation, StableHasherBuilder, ToBytes};
+
+/// Computes the dense buckets the sketch should hold, straight from the hashes
+fn expected_buckets(p: u32, items: &[u64]) -> Vec<u8> {
+    let mut buckets = vec![0u8; 1 << p];
+    for item in items {
+        let mut hasher = StableHasherBuilder::default().build_hasher();
+        hasher.write(&item.to_bytes());
+        let hash = hasher.finish();
+
+        let idx = (hash >> (64 - p)) as usize;
+        let rank = ((hash << p).leading_zeros() + 1).min(64 - p + 1) as u8;
+        buckets[idx] = buckets[idx].max(rank);
+    }
+    buckets
+}
+
+#[test]
+fn test_new_sketch_is_sparse() {
+    let hll = HyperLogLog::<u64>::new(14).unwrap();
+    assert_eq!(hll.representation(), Representation::Sparse);
+}
+
+#[test]
+fn test_large_precision_starts_dense() {
+    let hll = HyperLogLog::<u64>::new(25).unwrap();
+    assert_eq!(hll.representation(), Representation::Dense);
+}
+
+#[test]
+fn test_promotes_to_dense_when_larger_than_dense() {
+    let mut hll = HyperLogLog::<u64>::new(10).unwrap();
+    for i in 0..100u64 {
+        hll.insert(i);
+    }
+    assert_eq!(hll.representation(), Representation::Sparse);
+
+    for i in 100..10_000u64 {
+        hll.insert(i);
+    }
+    assert_eq!(hll.representation(), Representation::Dense);
+}
+
+#[test]
+fn test_sparse_memory_never_exceeds_dense_memory() {
+    for backend in [RegisterBackend::U8, RegisterBackend::Packed6, RegisterBackend::Hll4] {
+        for p in 4..=10 {
+            let dense = HyperLogLog::<u64>::memory_cost_with_backend(p, backend).unwrap();
+            let mut hll = HyperLogLog::<u64>::new(p).unwrap().with_backend(backend);
+            for i in 0..(1u64 << p) {
+                hll.insert(i);
+                if hll.representation() == Representation::Sparse {
+                    assert!(hll.memory_usage() <= dense, "{:?} p={} i={}: {} > {}", backend, p, i, hll.memory_usage(), dense);
+                }
+            }
+            assert_eq!(hll.get_buckets(), expected_buckets(p, &(0..1u64 << p).collect::<Vec<_>>()));
+        }
+    }
+}
+
+#[test]
+fn test_sparse_and_dense_buckets_match_hashes() {
+    let p = 8;
+    let items: Vec<u64> = (0..2_000).collect();
+    let mut hll = HyperLogLog::<u64>::new(p).unwrap();
+
+    for &i in &items[..20] {
+        hll.insert(i);
+    }
+    assert_eq!(hll.representation(), Representation::Sparse);
+    assert_eq!(hll.get_buckets(), expected_buckets(p, &items[..20]));
+
+    for &i in &items[20..] {
+        hll.insert(i);
+    }
+    assert_eq!(hll.representation(), Representation::Dense);
+    assert_eq!(hll.get_buckets(), expected_buckets(p, &items));
+}
+
+#[test]
+fn test_sparse_estimate_matches_dense_estimate() {
+    let p = 12;
+    let mut sparse = HyperLogLog::<u64>::new(p).unwrap();
+    for i in 0..300u64 {
+        sparse.insert(i);
+    }
+    assert_eq!(sparse.representation(), Representation::Sparse);
+
+    // load the same buckets through the dense serialized layout
+    let mut json: serde_json::Value = serde_json::to_value(&sparse).unwrap();
+    let obj = json.as_object_mut().unwrap();
+    obj.remove("sparse");
+    obj.insert("buckets".into(), serde_json::to_value(sparse.get_buckets()).unwrap());
+    let dense: HyperLogLog<u64> = serde_json::from_value(json).unwrap();
+    assert_eq!(dense.representation(), Representation::Dense);
+
+    assert_eq!(sparse.calculate_cardinality(), dense.calculate_cardinality());
+}
+
+#[test]
+fn test_merge_sparse_into_dense_and_dense_into_sparse() {
+    let p = 10;
+    let mut small = HyperLogLog::<u64>::new(p).unwrap();
+    let mut large = HyperLogLog::<u64>::new(p).unwrap();
+    for i in 0..50u64 {
+        small.insert(i);
+    }
+    for i in 50..20_000u64 {
+        large.insert(i);
+    }
+    assert_eq!(small.representation(), Representation::Sparse);
+    assert_eq!(large.representation(), Representation::Dense);
+
+    let all: Vec<u64> = (0..20_000).collect();
+
+    let mut a = small.clone();
+    a.merge(&large).unwrap();
+    assert_eq!(a.representation(), Representation::Dense);
+    assert_eq!(a.get_buckets(), expected_buckets(p, &all));
+
+    let mut b = large.clone();
+    b.merge(&small).unwrap();
+    assert_eq!(b.get_buckets(), expected_buckets(p, &all));
+}
+
+#[test]
+fn test_merge_sparse_sketches_promotes_when_needed() {
+    let p = 6;
+    let mut a = HyperLogLog::<u64>::new(p).unwrap();
+    let mut b = HyperLogLog::<u64>::new(p).unwrap();
+    for i in 0..10u64 {
+        a.insert(i);
+        b.insert(i + 10);
+    }
+    assert_eq!(a.representation(), Representation::Sparse);
+    assert_eq!(b.representation(), Representation::Sparse);
+
+    a.merge(&b).unwrap();
+    assert_eq!(a.representation(), Representation::Dense);
+    assert_eq!(a.get_buckets(), expected_buckets(p, &(0..20).collect::<Vec<_>>()));
+}
+
+#[test]
+fn test_reset_returns_to_sparse() {
+    let mut hll = HyperLogLog::<u64>::new(8).unwrap();
+    for i in 0..5_000u64 {
+        hll.insert(i);
+    }
+    assert_eq!(hll.representation(), Representation::Dense);
+
+    hll.reset();
+    assert_eq!(hll.representation(), Representation::Sparse);
+    assert_eq!(hll.calculate_cardinality(), 0);
+}
+
+#[test]
+fn test_sparse_serde_roundtrip() {
+    let mut hll = HyperLogLog::<u64>::new(14).unwrap();
+    for i in 0..500u64 {
+        hll.insert(i);
+    }
+    assert_eq!(hll.representation(), Representation::Sparse);
+
+    let json = serde_json::to_string(&hll).unwrap();
+    let restored: HyperLogLog<u64> = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(restored.representation(), Representation::Sparse);
+    assert_eq!(restored.get_buckets(), hll.get_buckets());
+    assert_eq!(restored.calculate_cardinality(), hll.calculate_cardinality());
+}
+
+#[test]
+fn test_many_sparse_entries_at_high_precision() {
+    // a million entries stay sparse at p = 24, each insert staying cheap
+    let p = 24;
+    let items: Vec<u64> = (0..1_000_000).collect();
+    let mut hll = HyperLogLog::<u64>::new(p).unwrap();
+    for &i in &items {
+        hll.insert(i);
+        // repeated items only buffer duplicates, which must not count twice
+        hll.insert(i / 2);
+    }
+    assert_eq!(hll.representation(), Representation::Sparse);
+    assert_eq!(hll.get_buckets(), expected_buckets(p, &items));
+
+    let restored = HyperLogLog::<u64>::from_bytes(&hll.to_bytes()).unwrap();
+    assert_eq!(restored.get_buckets(), hll.get_buckets());
+    assert_eq!(restored.calculate_cardinality(), hll.calculate_cardinality());
+}
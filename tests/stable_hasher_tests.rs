@@ -0,0 +1,150 @@
+use std::hash::{BuildHasher, BuildHasherDefault, DefaultHasher, Hasher};
+
+use hyperloglog::{
+    HasherIdentity, HyperLogLog, HyperLogLogError, StableHasherBuilder, ToBytes, DEFAULT_HASH_SEED, STABLE_HASH_ALGORITHM,
+};
+use xxhash_rust::xxh3::{xxh3_64_with_seed, Xxh3DefaultBuilder};
+
+// Sketches written by this release with the default hasher, by the release before it in format
+// version 3, and by the releases before those with the first item encodings, in format versions 3,
+// 2 (same hasher) and 1 (SipHash). A change to the bytes of this release breaks every persisted sketch.
+const XXH3_E2_U64_100_P12: &[u8] = include_bytes!("fixtures/stable/xxh3_v4_e2_u64_100_p12.bin");
+const XXH3_E2_STRINGS_10000_P10: &[u8] = include_bytes!("fixtures/stable/xxh3_v4_e2_strings_10000_p10.bin");
+const XXH3_V3_E2_U64_100_P12: &[u8] = include_bytes!("fixtures/stable/xxh3_v3_e2_u64_100_p12.bin");
+const XXH3_V3_E2_STRINGS_10000_P10: &[u8] = include_bytes!("fixtures/stable/xxh3_v3_e2_strings_10000_p10.bin");
+const XXH3_E2_U64_100_P12_JSON: &str = include_str!("fixtures/stable/xxh3_e2_u64_100_p12.json");
+const XXH3_V3_U64_100_P12: &[u8] = include_bytes!("fixtures/stable/xxh3_v3_u64_100_p12.bin");
+const XXH3_V3_STRINGS_10000_P10: &[u8] = include_bytes!("fixtures/stable/xxh3_v3_strings_10000_p10.bin");
+const XXH3_V2_U64_100_P12: &[u8] = include_bytes!("fixtures/stable/xxh3_v2_u64_100_p12.bin");
+const XXH3_V2_STRINGS_10000_P10: &[u8] = include_bytes!("fixtures/stable/xxh3_v2_strings_10000_p10.bin");
+const XXH3_U64_100_P12_JSON: &str = include_str!("fixtures/stable/xxh3_u64_100_p12.json");
+const SIPHASH_V1_U64_100_P12: &[u8] = include_bytes!("fixtures/stable/siphash_v1_u64_100_p12.bin");
+const SIPHASH_V1_U64_10000_P10: &[u8] = include_bytes!("fixtures/stable/siphash_v1_u64_10000_p10.bin");
+
+fn stable_hash(seed: u64, bytes: &[u8]) -> u64 {
+    let mut hasher = StableHasherBuilder::new(seed).build_hasher();
+    hasher.write(bytes);
+    hasher.finish()
+}
+
+#[test]
+fn test_stable_hasher_golden_vectors() {
+    // reference value of XXH3_64bits for the empty input
+    assert_eq!(stable_hash(0, b""), 0x2d06_8005_38d3_94c2);
+
+    assert_eq!(stable_hash(DEFAULT_HASH_SEED, &0u64.to_bytes()), 0x1a00_6557_2063_04f9);
+    assert_eq!(stable_hash(DEFAULT_HASH_SEED, &42u64.to_bytes()), 0xfc72_f81e_1561_6341);
+    assert_eq!(stable_hash(DEFAULT_HASH_SEED, b"hello"), 0x3746_83d7_a799_4223);
+    assert_eq!(stable_hash(7, &0u64.to_bytes()), 0x473e_2c8c_d5d4_6be9);
+    assert_eq!(stable_hash(7, &42u64.to_bytes()), 0xb117_e48c_a46d_c725);
+    assert_eq!(stable_hash(7, b"hello"), 0x1f68_3eda_4e82_9e1b);
+}
+
+#[test]
+fn test_stable_hasher_streams_like_one_shot_xxh3() {
+    let input: Vec<u8> = (0..1_000u32).map(|i| (i * 31 % 251) as u8).collect();
+    for seed in [0, 1, DEFAULT_HASH_SEED, u64::MAX] {
+        for len in [0, 1, 3, 4, 8, 9, 16, 17, 128, 129, 240, 241, 256, 1_000] {
+            // written in uneven pieces, as composite items would be
+            let mut hasher = StableHasherBuilder::new(seed).build_hasher();
+            for chunk in input[..len].chunks(7) {
+                hasher.write(chunk);
+            }
+            assert_eq!(hasher.finish(), xxh3_64_with_seed(&input[..len], seed), "seed={} len={}", seed, len);
+        }
+    }
+}
+
+#[test]
+fn test_default_hasher_identity() {
+    let builder = StableHasherBuilder::default();
+    assert_eq!(builder, StableHasherBuilder::new(DEFAULT_HASH_SEED));
+    assert_eq!(builder.algorithm(), STABLE_HASH_ALGORITHM);
+    assert_eq!(builder.seed(), DEFAULT_HASH_SEED);
+
+    let hll = HyperLogLog::<u64>::new(12).unwrap();
+    let json: serde_json::Value = serde_json::to_value(&hll).unwrap();
+    assert_eq!(json["hash_algorithm"], "xxh3-64");
+    assert_eq!(json["hash_seed"], DEFAULT_HASH_SEED);
+}
+
+#[test]
+fn test_golden_sketches_are_reproduced() {
+    let mut numbers = HyperLogLog::<u64>::new(12).unwrap();
+    for i in 0..100u64 {
+        numbers.insert(i);
+    }
+    assert_eq!(numbers.to_bytes(), XXH3_E2_U64_100_P12);
+    assert_eq!(serde_json::to_string(&numbers).unwrap(), XXH3_E2_U64_100_P12_JSON);
+
+    let mut strings = HyperLogLog::<String>::new(10).unwrap();
+    for i in 0..10_000 {
+        strings.insert(format!("item-{}", i));
+    }
+    assert_eq!(strings.to_bytes(), XXH3_E2_STRINGS_10000_P10);
+    assert_eq!(strings.calculate_cardinality(), 10_155);
+}
+
+#[test]
+fn test_golden_sketches_load() {
+    let numbers = HyperLogLog::<u64>::from_bytes(XXH3_E2_U64_100_P12).unwrap();
+    assert_eq!(numbers.calculate_cardinality(), 100);
+    let from_json: HyperLogLog<u64> = serde_json::from_str(XXH3_E2_U64_100_P12_JSON).unwrap();
+    assert_eq!(from_json.get_buckets(), numbers.get_buckets());
+
+    let strings = HyperLogLog::<String>::from_bytes(XXH3_E2_STRINGS_10000_P10).unwrap();
+    assert_eq!(strings.calculate_cardinality(), 10_155);
+
+    // format version 3 has no raw hash record, its sketches load as sketches of items
+    let previous = HyperLogLog::<u64>::from_bytes(XXH3_V3_E2_U64_100_P12).unwrap();
+    assert_eq!(previous.get_buckets(), numbers.get_buckets());
+    assert!(!previous.is_prehashed());
+    let previous = HyperLogLog::<String>::from_bytes(XXH3_V3_E2_STRINGS_10000_P10).unwrap();
+    assert_eq!(previous.get_buckets(), strings.get_buckets());
+}
+
+#[test]
+fn test_sketches_of_previous_encodings_are_refused() {
+    // they still parse, in every format version, but their fingerprint is the one of the old encodings
+    type SipHash = BuildHasherDefault<DefaultHasher>;
+    for result in [
+        HyperLogLog::<u64>::from_bytes(XXH3_V3_U64_100_P12).map(|_| ()),
+        HyperLogLog::<String>::from_bytes(XXH3_V3_STRINGS_10000_P10).map(|_| ()),
+        HyperLogLog::<u64>::from_bytes(XXH3_V2_U64_100_P12).map(|_| ()),
+        HyperLogLog::<String>::from_bytes(XXH3_V2_STRINGS_10000_P10).map(|_| ()),
+        HyperLogLog::<u64, SipHash>::from_bytes(SIPHASH_V1_U64_100_P12).map(|_| ()),
+        HyperLogLog::<u64, SipHash>::from_bytes(SIPHASH_V1_U64_10000_P10).map(|_| ()),
+    ] {
+        assert!(matches!(result, Err(HyperLogLogError::HasherMismatch)), "{:?}", result.err());
+    }
+    assert!(serde_json::from_str::<HyperLogLog<u64>>(XXH3_U64_100_P12_JSON).is_err());
+
+    // their registers are the ones this release computes, only the fingerprint moved
+    let numbers = HyperLogLog::<u64>::from_bytes(XXH3_V3_E2_U64_100_P12).unwrap();
+    assert_eq!(XXH3_V3_U64_100_P12.len(), XXH3_V3_E2_U64_100_P12.len());
+    assert_eq!(XXH3_V3_U64_100_P12[16..XXH3_V3_U64_100_P12.len() - 4], XXH3_V3_E2_U64_100_P12[16..XXH3_V3_E2_U64_100_P12.len() - 4]);
+    assert_eq!(numbers.calculate_cardinality(), 100);
+}
+
+#[test]
+fn test_recorded_hasher_must_match() {
+    let mut hll = HyperLogLog::<u64, StableHasherBuilder>::with_hasher(12, StableHasherBuilder::new(7)).unwrap();
+    for i in 0..1_000u64 {
+        hll.insert(i);
+    }
+
+    // the seed is restored rather than replaced with the one of `S::default()`
+    let restored = HyperLogLog::<u64, StableHasherBuilder>::from_bytes(&hll.to_bytes()).unwrap();
+    assert_eq!(restored.get_buckets(), hll.get_buckets());
+    let restored: HyperLogLog<u64, StableHasherBuilder> = serde_json::from_str(&serde_json::to_string(&hll).unwrap()).unwrap();
+    assert_eq!(restored.get_buckets(), hll.get_buckets());
+
+    // XXH3 without a seed is another function than the default, even though both are XXH3
+    let default = HyperLogLog::<u64>::new(12).unwrap();
+    assert!(HyperLogLog::<u64, Xxh3DefaultBuilder>::from_bytes(&default.to_bytes()).is_err());
+
+    // a JSON sketch naming another algorithm is refused even when its fingerprint matches
+    let mut json: serde_json::Value = serde_json::to_value(&default).unwrap();
+    json["hash_algorithm"] = "murmur3".into();
+    assert!(serde_json::from_value::<HyperLogLog<u64>>(json).is_err());
+}
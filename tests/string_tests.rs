@@ -5,7 +5,7 @@ use hyperloglog::{HyperLogLog, ToBytes};
 /// Test inserting no strings yields zero cardinality
 #[test]
 fn test_no_inserts_string() {
-    let hll = HyperLogLog::<String>::new(10).unwrap();;
+    let hll = HyperLogLog::<String>::new(10).unwrap();
     assert_eq!(hll.calculate_cardinality(), 0);
 }
 
@@ -68,7 +68,7 @@ fn test_unicode_strings() {
 #[test]
 fn test_very_long_strings() {
     let mut hll = HyperLogLog::<String>::new(12).unwrap();
-    let long = std::iter::repeat('x').take(10_000).collect::<String>();
+    let long = "x".repeat(10_000);
     hll.insert(long.clone());
     let mut count = 1;
     // insert some duplicates and one new long string
@@ -106,6 +106,7 @@ fn test_prefix_suffix_variations() {
 
 /// Hash-collision simulation via wrapper
 #[derive(Clone)]
+#[allow(dead_code)]
 struct CollidingString(String);
 impl ToBytes for CollidingString {
     fn to_bytes(&self) -> Vec<u8> {
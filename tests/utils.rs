@@ -1,3 +1,4 @@
+#[allow(clippy::module_inception)]
 pub mod utils {
 
     pub fn calculate_bounds(n: u64, tolerance: f64) -> (u64, u64) {
@@ -5,7 +6,7 @@ pub mod utils {
         let lower_bound = (expected as f64 * (1.0 - tolerance)).round() as u64;
         let upper_bound = (expected as f64 * (1.0 + tolerance)).round() as u64;
 
-        return (lower_bound, upper_bound)
+        (lower_bound, upper_bound)
     }
 
 }
\ No newline at end of file
@@ -0,0 +1,163 @@
+use std::hash::{BuildHasher, Hasher};
+
+use hyperloglog::{HyperLogLog, HyperLogLogError, Representation, ZetaSketchHasherBuilder};
+
+// Serialized `AggregatorStateProto`s of `HLL_COUNT.INIT` over `1 ..= n` as INT64 at precision 15,
+// and over the strings `item-0 .. item-{n-1}` at precision 12 with sparse precision 25.
+// Built from the ZetaSketch proto definitions and encoding rules, not exported from BigQuery.
+// fixtures/zetasketch/README.md has the commands to capture real ones.
+const EMPTY_P15: &[u8] = include_bytes!("fixtures/zetasketch/longs_empty_p15.bin");
+const SPARSE_1000_P15: &[u8] = include_bytes!("fixtures/zetasketch/longs_sparse_1000_p15.bin");
+const DENSE_100000_P15: &[u8] = include_bytes!("fixtures/zetasketch/longs_dense_100000_p15.bin");
+const STRINGS_500_P12: &[u8] = include_bytes!("fixtures/zetasketch/strings_sparse_500_p12_sp25.bin");
+
+fn fingerprint(bytes: &[u8]) -> u64 {
+    let mut hasher = ZetaSketchHasherBuilder.build_hasher();
+    hasher.write(bytes);
+    hasher.finish()
+}
+
+fn import(bytes: &[u8]) -> HyperLogLog<i64, ZetaSketchHasherBuilder> {
+    HyperLogLog::from_zetasketch(bytes, ZetaSketchHasherBuilder).unwrap()
+}
+
+fn longs(p: u32, range: std::ops::RangeInclusive<i64>) -> HyperLogLog<i64, ZetaSketchHasherBuilder> {
+    let mut hll = HyperLogLog::with_hasher(p, ZetaSketchHasherBuilder).unwrap();
+    for i in range {
+        hll.insert(i);
+    }
+    hll
+}
+
+fn strings(n: usize) -> HyperLogLog<String, ZetaSketchHasherBuilder> {
+    let mut hll = HyperLogLog::with_hasher(12, ZetaSketchHasherBuilder).unwrap();
+    for i in 0..n {
+        hll.insert(format!("item-{}", i));
+    }
+    hll
+}
+
+// the HyperLogLog++ state, the last field, without the count of added values the sketch does not keep
+fn state(bytes: &[u8]) -> &[u8] {
+    let start = bytes.windows(2).position(|tag| tag == [0x82, 0x07]).unwrap();
+    &bytes[start..]
+}
+
+#[test]
+fn test_fingerprint2011_golden_vectors() {
+    // from Guava's Fingerprint2011Test.testReallySimpleFingerprints: 4, 32 and 256 bytes,
+    // which go through murmur and through the 64-byte chunks
+    assert_eq!(fingerprint(b"test") as i64, 8473225671271759044);
+    assert_eq!(fingerprint("test".repeat(8).as_bytes()) as i64, 7345148637025587076);
+    assert_eq!(fingerprint("test".repeat(64).as_bytes()) as i64, 4904844928629814570);
+
+    // outputs of this crate, pinned for every length class: murmur below 33 bytes, 33 to 64
+    // bytes, and whole 64-byte chunks above, with and without a partial last chunk
+    let long: Vec<u8> = (0..200).collect();
+    for (input, expected) in [
+        (&b""[..], 0x23ad7c904aa665e3),
+        (b"a", 0x947947a2d71fb634),
+        (b"abc", 0x004f3187e3175983),
+        (b"12345678", 0x631c1a3b0c3be0a5),
+        (b"123456789", 0x1bcf5f932ff957c7),
+        (b"hello world, fingerprint", 0x90cd180e0b6a19f7),
+        (&[b'x'; 32], 0xdae121ad3d933945),
+        (&[b'y'; 33], 0xc0cebb1f6f3655af),
+        (b"The quick brown fox jumps over the lazy dog", 0x89b013e8848c68c9),
+        (&long[..64], 0xf31a3a8f65e3a99e),
+        (&long[..65], 0x50d6b8702ec7701a),
+        (&long, 0x7ca88dc20a159a22),
+        (&0i64.to_le_bytes(), 0x853a22bd6e14a48f),
+        (&(-1i64).to_le_bytes(), 0xda13d4a9f7078f79),
+        (&42i64.to_le_bytes(), 0x583b2b9df8e0eb60),
+    ] {
+        assert_eq!(fingerprint(input), expected, "{:?}", input);
+    }
+}
+
+#[test]
+fn test_import_fixtures() {
+    for (bytes, n, representation) in [
+        (EMPTY_P15, 0, Representation::Sparse),
+        // sparse data at precision 20 is folded into registers
+        (SPARSE_1000_P15, 1_000, Representation::Dense),
+        (DENSE_100000_P15, 100_000, Representation::Dense),
+    ] {
+        let hll = import(bytes);
+        assert_eq!(hll.get_p(), 15);
+        assert_eq!(hll.representation(), representation);
+        let estimate = hll.calculate_cardinality() as f64;
+        assert!((estimate - n as f64).abs() <= 0.02 * n as f64, "n={} estimate={}", n, estimate);
+    }
+
+    // sparse data at precision 25 stays sparse
+    let hll = HyperLogLog::<String, _>::from_zetasketch(STRINGS_500_P12, ZetaSketchHasherBuilder).unwrap();
+    assert_eq!(hll.get_p(), 12);
+    assert_eq!(hll.representation(), Representation::Sparse);
+    assert!((hll.calculate_cardinality() as f64 - 500.0).abs() <= 10.0);
+}
+
+#[test]
+fn test_inserts_match_zetasketch_registers() {
+    for (bytes, n) in [(EMPTY_P15, 0), (SPARSE_1000_P15, 1_000), (DENSE_100000_P15, 100_000)] {
+        assert_eq!(longs(15, 1..=n).get_buckets(), import(bytes).get_buckets(), "n={}", n);
+    }
+    let imported = HyperLogLog::<String, _>::from_zetasketch(STRINGS_500_P12, ZetaSketchHasherBuilder).unwrap();
+    assert_eq!(strings(500).get_buckets(), imported.get_buckets());
+}
+
+#[test]
+fn test_merge_imported_with_native() {
+    let mut merged = import(SPARSE_1000_P15);
+    merged.merge(&longs(15, 1_001..=50_000)).unwrap();
+    assert_eq!(merged.get_buckets(), longs(15, 1..=50_000).get_buckets());
+
+    // a sparse import merged with the same items adds nothing
+    let mut sparse = HyperLogLog::<String, _>::from_zetasketch(STRINGS_500_P12, ZetaSketchHasherBuilder).unwrap();
+    sparse.merge(&strings(500)).unwrap();
+    assert_eq!(sparse.calculate_cardinality(), strings(500).calculate_cardinality());
+}
+
+#[test]
+fn test_export_matches_fixtures() {
+    let empty = HyperLogLog::<i64, _>::with_hasher(15, ZetaSketchHasherBuilder).unwrap();
+    assert_eq!(state(&empty.to_zetasketch().unwrap()), state(EMPTY_P15));
+    assert_eq!(state(&longs(15, 1..=1_000).to_zetasketch().unwrap()), state(SPARSE_1000_P15));
+    assert_eq!(state(&longs(15, 1..=100_000).to_zetasketch().unwrap()), state(DENSE_100000_P15));
+
+    // type, a count of 0 and encoding version 2, then INT64 or BYTES_OR_UTF8_STRING items
+    assert_eq!(&longs(15, 1..=10).to_zetasketch().unwrap()[..8], &[0x08, 0x70, 0x10, 0x00, 0x18, 0x02, 0x20, 0x02]);
+    assert_eq!(strings(10).to_zetasketch().unwrap()[7], 11);
+}
+
+#[test]
+fn test_roundtrip() {
+    for (p, n) in [(10, 50), (15, 5_000), (15, 200_000), (20, 1_000), (24, 100)] {
+        let hll = longs(p, 1..=n);
+        let restored = import(&hll.to_zetasketch().unwrap());
+        assert_eq!(restored.get_buckets(), hll.get_buckets(), "p={} n={}", p, n);
+    }
+}
+
+#[test]
+fn test_rejects_malformed_sketches() {
+    let load = |bytes: &[u8]| HyperLogLog::<i64, ZetaSketchHasherBuilder>::from_zetasketch(bytes, ZetaSketchHasherBuilder);
+    assert!(load(SPARSE_1000_P15).is_ok());
+
+    // another aggregator type, encoding version 1
+    for (offset, value) in [(1, 0x71), (6, 0x01)] {
+        let mut bytes = SPARSE_1000_P15.to_vec();
+        bytes[offset] = value;
+        assert!(load(&bytes).is_err(), "byte {} = {:#x}", offset, value);
+    }
+
+    for bytes in [SPARSE_1000_P15, DENSE_100000_P15] {
+        assert!(load(&bytes[..bytes.len() - 1]).is_err());
+    }
+
+    // string sketches do not merge into sketches of integers
+    assert!(matches!(load(STRINGS_500_P12), Err(HyperLogLogError::HasherMismatch)));
+
+    assert!(longs(9, 1..=10).to_zetasketch().is_err());
+    assert!(longs(25, 1..=10).to_zetasketch().is_err());
+}